@@ -0,0 +1,154 @@
+use std::path::PathBuf;
+use chrono::NaiveDate;
+use clap::{Parser, Subcommand};
+use crate::importer::LedgerFormat;
+use crate::commands::output::OutputFormat;
+use crate::services::price::QuoteCurrency;
+use crate::models::cost_basis::CostBasisMethod;
+use crate::models::portfolio_snapshot::PositionKind;
+use crate::services::fixtures::FixtureMode;
+
+#[derive(Parser, Debug)]
+#[command(version, about = "Cardano portfolio summary")]
+pub struct Cli {
+    /// JSON config file with API base URL overrides; defaults to config.json
+    /// in the platform config directory
+    #[arg(long, value_name = "FILE")]
+    pub config: Option<PathBuf>,
+
+    /// Database file, SQLite when it ends in .sqlite, .sqlite3 or .db and JSON
+    /// otherwise; defaults to $PORTFOLIO_DATABASE, then the platform data directory
+    #[arg(long, value_name = "FILE")]
+    pub database: Option<PathBuf>,
+
+    /// Record every API response as a JSON fixture in this directory
+    #[arg(long, value_name = "DIR", conflicts_with = "replay")]
+    pub record: Option<PathBuf>,
+
+    /// Serve every API response from fixtures in this directory, without network access
+    #[arg(long, value_name = "DIR")]
+    pub replay: Option<PathBuf>,
+
+    /// Name of the user to act as; skips the name prompt when launching the TUI
+    #[arg(long, global = true)]
+    pub user: Option<String>,
+
+    /// Restrict output to the wallet with this name
+    #[arg(long, global = true)]
+    pub wallet: Option<String>,
+
+    /// Output of subcommands: table, markdown, json or csv
+    #[arg(long, global = true, default_value = "table")]
+    pub format: OutputFormat,
+
+    #[command(subcommand)]
+    pub command: Option<Command>,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum Command {
+    /// Print portfolio totals
+    Summary,
+    /// Print the positions of one type
+    Positions {
+        /// ft, nft or lp
+        #[arg(long = "type", default_value = "ft")]
+        kind: PositionKind,
+    },
+    /// Print ADA and BTC prices in every supported currency
+    Prices,
+    /// Print the top tokens by market cap
+    Mcap,
+    /// Inspect stored users
+    Users {
+        #[command(subcommand)]
+        action: UsersAction,
+    },
+    /// Re-encrypt the user's API key under a new passphrase, or encrypt a
+    /// key still stored in plain text
+    RotatePassphrase,
+    /// Copy every user and the snapshot history into a new SQLite database
+    MigrateDb {
+        /// SQLite file to create, ending in .sqlite, .sqlite3 or .db
+        target: PathBuf,
+    },
+    /// Manage the purchase lots used for profit and loss
+    CostBasis {
+        #[command(subcommand)]
+        action: CostBasisAction,
+    },
+    /// Import and inspect the transaction ledger
+    Ledger {
+        #[command(subcommand)]
+        action: LedgerAction,
+    },
+    /// Export the realized gains of a tax year from the ledger
+    Gains {
+        /// Calendar year of the disposals to report
+        #[arg(long)]
+        year: i32,
+        /// Fiat currency for proceeds, cost and gain
+        #[arg(long, default_value = "usd")]
+        currency: QuoteCurrency,
+        /// File to write the report to, stdout when omitted. Its extension
+        /// (.csv, .json, .md) picks the format, and CSV is the default
+        #[arg(long, short)]
+        output: Option<PathBuf>,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+pub enum UsersAction {
+    /// Print every user with their wallets
+    List,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum CostBasisAction {
+    /// Record a purchase of a token
+    Add {
+        /// Token unit (policy id followed by hex asset name)
+        unit: String,
+        /// Number of tokens bought
+        quantity: f64,
+        /// Total paid for the lot, in ADA
+        cost_ada: f64,
+        /// Purchase date as YYYY-MM-DD, defaults to today
+        #[arg(long)]
+        date: Option<NaiveDate>,
+    },
+    /// Import lots from a JSON array of `{unit, acquired_on, quantity, cost_ada}`
+    Import {
+        file: PathBuf,
+    },
+    /// Choose how held lots are matched against the current balance
+    Method {
+        /// fifo, lifo or average
+        method: CostBasisMethod,
+    },
+    /// Print the recorded lots
+    List,
+}
+
+impl Cli {
+    pub fn fixture_mode(&self) -> FixtureMode {
+        match (&self.record, &self.replay) {
+            (Some(dir), _) => FixtureMode::Record(dir.clone()),
+            (_, Some(dir)) => FixtureMode::Replay(dir.clone()),
+            _ => FixtureMode::Live,
+        }
+    }
+}
+
+#[derive(Subcommand, Debug)]
+pub enum LedgerAction {
+    /// Import trades from a generic, Koinly or CoinTracking CSV export
+    Import {
+        file: PathBuf,
+        /// generic, koinly or cointracking; detected from the header when omitted
+        #[arg(long, value_name = "FORMAT")]
+        from: Option<LedgerFormat>,
+    },
+    /// Print the stored ledger
+    List,
+}
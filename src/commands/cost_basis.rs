@@ -0,0 +1,53 @@
+use std::fs;
+use chrono::Local;
+use crate::cli::CostBasisAction;
+use crate::db::Database;
+use crate::models::cost_basis::Lot;
+use crate::utils::formatting::{format_ada, format_number};
+
+pub fn run(database: &mut Database, user_name: &str, action: CostBasisAction) -> Result<(), Box<dyn std::error::Error>> {
+    let user_id = database.get_user_by_name(user_name)
+        .map(|user| user.id.clone())
+        .ok_or_else(|| format!("User '{}' not found", user_name))?;
+
+    match action {
+        CostBasisAction::Add { unit, quantity, cost_ada, date } => {
+            let lot = Lot {
+                unit,
+                acquired_on: date.unwrap_or_else(|| Local::now().date_naive()),
+                quantity,
+                cost_ada,
+            };
+            database.add_lots(&user_id, vec![lot])?;
+            database.save()?;
+            println!("Lot recorded.");
+        },
+        CostBasisAction::Import { file } => {
+            let content = fs::read_to_string(&file)?;
+            let lots: Vec<Lot> = serde_json::from_str(&content)?;
+            let count = lots.len();
+            database.add_lots(&user_id, lots)?;
+            database.save()?;
+            println!("Imported {} lots from {}.", count, file.display());
+        },
+        CostBasisAction::Method { method } => {
+            database.set_cost_basis_method(&user_id, method)?;
+            database.save()?;
+            println!("Cost basis method set to {}.", method);
+        },
+        CostBasisAction::List => {
+            let book = &database.get_user(&user_id).ok_or("User not found")?.cost_basis;
+            println!("Method: {}", book.method);
+            for lot in &book.lots {
+                println!(
+                    "{}  {}  {}  {}",
+                    lot.acquired_on,
+                    lot.unit,
+                    format_number(lot.quantity, 2),
+                    format_ada(lot.cost_ada, 2),
+                );
+            }
+        },
+    }
+    Ok(())
+}
@@ -0,0 +1,72 @@
+use std::fs::File;
+use std::io::{self, Write};
+use std::path::PathBuf;
+use crate::commands::Context;
+use crate::commands::output::{self, OutputFormat, Table};
+use crate::db::Database;
+use crate::reports::gains::{rate_dates, GainsReport};
+use crate::services::price::QuoteCurrency;
+use crate::utils::formatting::format_number;
+
+pub async fn run(
+    database: &Database,
+    context: &Context<'_>,
+    year: i32,
+    currency: QuoteCurrency,
+    output: Option<PathBuf>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let user_name = context.require_user()?;
+    let user = database.get_user_by_name(user_name)
+        .ok_or_else(|| format!("User '{}' not found", user_name))?;
+
+    // Entries without a value in the chosen currency are valued at the ADA
+    // price of their day
+    let dates = rate_dates(&user.ledger, year, currency);
+    let ada_rates = context.user_service.fetch_ada_rates(&dates, currency).await?;
+
+    let report = GainsReport::build(&user.ledger, year, currency, user.cost_basis.method, &ada_rates)?;
+    match &output {
+        Some(path) => {
+            let format = OutputFormat::for_file(path, context.format);
+            write_report(&report, format, File::create(path)?)?;
+            println!(
+                "Wrote {} disposals with a total gain of {:.2} {} to {}.",
+                report.rows.len(),
+                report.total_gain,
+                currency,
+                path.display()
+            );
+        },
+        None => write_report(&report, context.format, io::stdout())?,
+    }
+    Ok(())
+}
+
+fn write_report<W: Write>(report: &GainsReport, format: OutputFormat, writer: W) -> Result<(), Box<dyn std::error::Error>> {
+    if let Some(report_format) = format.report_format() {
+        return report.write(writer, report_format);
+    }
+
+    let mut table = Table::new(&["Ticker", "Quantity", "Acquired", "Disposed", "Proceeds", "Cost", "Gain"]);
+    for row in &report.rows {
+        table.push(vec![
+            row.ticker.clone(),
+            format_number(row.quantity, 2),
+            row.acquired_on.map(|date| date.to_string()).unwrap_or_else(|| "unknown".to_string()),
+            row.disposed_on.to_string(),
+            format_number(row.proceeds, 2),
+            format_number(row.cost, 2),
+            format_number(row.gain, 2),
+        ]);
+    }
+    table.push(vec![
+        "Total".to_string(),
+        String::new(),
+        String::new(),
+        String::new(),
+        format_number(report.total_proceeds, 2),
+        format_number(report.total_cost, 2),
+        format_number(report.total_gain, 2),
+    ]);
+    output::write(writer, format, &table, report, &report.rows)
+}
@@ -0,0 +1,72 @@
+use std::fs::File;
+use crate::cli::LedgerAction;
+use crate::db::{Database, history::History};
+use crate::importer::{import_ledger, ImportContext};
+use crate::models::ledger::LedgerSide;
+use crate::utils::formatting::{format_ada, format_number};
+
+pub fn run(
+    database: &mut Database,
+    history: &History,
+    user_name: &str,
+    wallet_name: Option<&str>,
+    action: LedgerAction,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let user = database.get_user_by_name(user_name)
+        .cloned()
+        .ok_or_else(|| format!("User '{}' not found", user_name))?;
+
+    let wallet_id = match wallet_name {
+        Some(name) => Some(user.wallets.iter()
+            .find(|w| w.name == name)
+            .map(|w| w.id.clone())
+            .ok_or_else(|| format!("Wallet '{}' not found", name))?),
+        None => None,
+    };
+
+    match action {
+        LedgerAction::Import { file, from } => {
+            // Exports only carry tickers, so match them against the units seen
+            // in the most recent snapshot
+            let latest = history.snapshots_for(&user.id).last();
+            let units = latest.iter()
+                .flat_map(|snapshot| snapshot.positions.iter())
+                .map(|position| (position.ticker.as_str(), position.unit.as_str()));
+            let context = ImportContext::new(user.wallets.clone(), wallet_id).with_units(units);
+
+            let entries = import_ledger(File::open(&file)?, from, &context)?;
+            let unmatched = entries.iter().filter(|entry| entry.unit.is_none()).count();
+            let added = database.import_ledger(&user.id, entries)?;
+            database.save()?;
+
+            println!("Imported {} new ledger entries from {}.", added, file.display());
+            if unmatched > 0 {
+                println!("{} entries have a ticker that matches no known token unit.", unmatched);
+            }
+        },
+        LedgerAction::List => {
+            let entries = user.ledger.iter()
+                .filter(|entry| wallet_id.is_none() || entry.wallet_id == wallet_id);
+            for entry in entries {
+                let side = match entry.side {
+                    LedgerSide::Acquire => "+",
+                    LedgerSide::Dispose => "-",
+                };
+                let wallet = entry.wallet_id.as_ref()
+                    .and_then(|id| user.wallets.iter().find(|w| &w.id == id))
+                    .map(|w| w.name.as_str())
+                    .unwrap_or("-");
+                println!(
+                    "{}  {}  {}{} {}  {}",
+                    entry.timestamp.format("%Y-%m-%d %H:%M"),
+                    wallet,
+                    side,
+                    format_number(entry.quantity, 2),
+                    entry.ticker,
+                    entry.ada_value.map(|value| format_ada(value, 2)).unwrap_or_default(),
+                );
+            }
+        },
+    }
+    Ok(())
+}
@@ -0,0 +1,63 @@
+pub mod cost_basis;
+pub mod gains;
+pub mod ledger;
+pub mod output;
+pub mod passphrase;
+pub mod portfolio;
+pub mod storage;
+pub mod users;
+
+use crate::cli::{Command, UsersAction};
+use crate::commands::output::OutputFormat;
+use crate::config::AppConfig;
+use crate::services::user_service::UserService;
+
+/// Global flags and services shared by every subcommand
+pub struct Context<'a> {
+    /// Name given with `--user`
+    pub user: Option<&'a str>,
+    /// Name given with `--wallet`
+    pub wallet: Option<&'a str>,
+    pub format: OutputFormat,
+    pub user_service: &'a UserService,
+    pub config: &'a AppConfig,
+}
+
+impl Context<'_> {
+    fn require_user(&self) -> Result<&str, Box<dyn std::error::Error>> {
+        self.user.ok_or_else(|| "This command needs --user".into())
+    }
+}
+
+/// Runs a non-interactive subcommand
+pub async fn run(command: Command, context: &Context<'_>) -> Result<(), Box<dyn std::error::Error>> {
+    let database = context.user_service.get_database();
+    match command {
+        Command::Summary => portfolio::summary(context).await,
+        Command::Positions { kind } => portfolio::positions(context, kind).await,
+        Command::Prices => portfolio::prices(context).await,
+        Command::Mcap => portfolio::market_caps(context).await,
+        Command::Users { action: UsersAction::List } => users::list(&*database.lock().await, context.format),
+        Command::RotatePassphrase => passphrase::rotate(&mut *database.lock().await, context.require_user()?),
+        Command::MigrateDb { target } => {
+            let history = context.user_service.get_history();
+            let history = history.lock().await;
+            storage::migrate(&*database.lock().await, &history, &target)
+        },
+        Command::CostBasis { action } => {
+            cost_basis::run(&mut *database.lock().await, context.require_user()?, action)
+        },
+        Command::Ledger { action } => {
+            let history = context.user_service.get_history();
+            let history = history.lock().await;
+            ledger::run(&mut *database.lock().await, &history, context.require_user()?, context.wallet, action)
+        },
+        Command::Gains { year, currency, output } => {
+            let database = database.lock().await;
+            gains::run(&database, context, year, currency, output).await
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests;
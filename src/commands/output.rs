@@ -0,0 +1,168 @@
+use std::fmt;
+use std::io::Write;
+use std::path::Path;
+use std::str::FromStr;
+use serde::Serialize;
+use crate::reports::ReportFormat;
+
+/// How subcommands print their results
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum OutputFormat {
+    /// Aligned columns formatted for people
+    #[default]
+    Table,
+    /// A GitHub flavoured Markdown table
+    Markdown,
+    Json,
+    Csv,
+}
+
+impl OutputFormat {
+    /// The machine readable report format, `None` for human output
+    pub fn report_format(&self) -> Option<ReportFormat> {
+        match self {
+            OutputFormat::Json => Some(ReportFormat::Json),
+            OutputFormat::Csv => Some(ReportFormat::Csv),
+            OutputFormat::Table | OutputFormat::Markdown => None,
+        }
+    }
+
+    /// The format for a report written to `path`: the one its extension
+    /// names, otherwise `requested` unless that is the aligned table, which
+    /// only suits a terminal, in which case CSV
+    pub fn for_file(path: &Path, requested: OutputFormat) -> OutputFormat {
+        let extension = path.extension().and_then(|extension| extension.to_str()).unwrap_or_default();
+        match extension.to_lowercase().as_str() {
+            "csv" => OutputFormat::Csv,
+            "json" => OutputFormat::Json,
+            "md" | "markdown" => OutputFormat::Markdown,
+            "txt" => OutputFormat::Table,
+            _ if requested == OutputFormat::Table => OutputFormat::Csv,
+            _ => requested,
+        }
+    }
+}
+
+impl FromStr for OutputFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "table" => Ok(OutputFormat::Table),
+            "markdown" | "md" => Ok(OutputFormat::Markdown),
+            "json" => Ok(OutputFormat::Json),
+            "csv" => Ok(OutputFormat::Csv),
+            _ => Err(format!("Unknown output format '{}', expected json, csv, table or markdown", s)),
+        }
+    }
+}
+
+impl fmt::Display for OutputFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            OutputFormat::Table => "table",
+            OutputFormat::Markdown => "markdown",
+            OutputFormat::Json => "json",
+            OutputFormat::Csv => "csv",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+/// Writes a result in `format`: `table` for the human formats, `json` as a
+/// single JSON document and `rows` as CSV records
+pub fn write<W, J, R>(
+    mut writer: W,
+    format: OutputFormat,
+    table: &Table,
+    json: &J,
+    rows: &[R],
+) -> Result<(), Box<dyn std::error::Error>>
+where
+    W: Write,
+    J: Serialize + ?Sized,
+    R: Serialize,
+{
+    match format {
+        OutputFormat::Table => write!(writer, "{}", table.render())?,
+        OutputFormat::Markdown => write!(writer, "{}", table.render_markdown())?,
+        OutputFormat::Json => {
+            serde_json::to_writer_pretty(&mut writer, json)?;
+            writeln!(writer)?;
+        },
+        OutputFormat::Csv => {
+            let mut csv = csv::Writer::from_writer(writer);
+            for row in rows {
+                csv.serialize(row)?;
+            }
+            csv.flush()?;
+        },
+    }
+    Ok(())
+}
+
+/// Rows of already formatted cells printed with aligned columns
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Table {
+    pub headers: Vec<String>,
+    pub rows: Vec<Vec<String>>,
+}
+
+impl Table {
+    pub fn new(headers: &[&str]) -> Self {
+        Self {
+            headers: headers.iter().map(|h| h.to_string()).collect(),
+            rows: Vec::new(),
+        }
+    }
+
+    pub fn push(&mut self, row: Vec<String>) {
+        self.rows.push(row);
+    }
+
+    /// Pads every column to its widest cell
+    pub fn render(&self) -> String {
+        let widths: Vec<usize> = (0..self.headers.len())
+            .map(|column| {
+                self.rows.iter()
+                    .filter_map(|row| row.get(column))
+                    .chain(std::iter::once(&self.headers[column]))
+                    .map(|cell| cell.chars().count())
+                    .max()
+                    .unwrap_or_default()
+            })
+            .collect();
+
+        let line = |cells: &[String]| {
+            cells.iter()
+                .zip(&widths)
+                .map(|(cell, width)| format!("{:<width$}", cell, width = width))
+                .collect::<Vec<_>>()
+                .join("  ")
+                .trim_end()
+                .to_string()
+        };
+
+        let mut output = line(&self.headers);
+        output.push('\n');
+        for row in &self.rows {
+            output.push_str(&line(row));
+            output.push('\n');
+        }
+        output
+    }
+
+    pub fn render_markdown(&self) -> String {
+        let line = |cells: &[String]| {
+            let cells: Vec<String> = cells.iter().map(|cell| cell.replace('|', "\\|")).collect();
+            format!("| {} |\n", cells.join(" | "))
+        };
+
+        let mut output = line(&self.headers);
+        output.push_str(&line(&vec!["---".to_string(); self.headers.len()]));
+        for row in &self.rows {
+            output.push_str(&line(row));
+        }
+        output
+    }
+}
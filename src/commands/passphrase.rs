@@ -0,0 +1,24 @@
+use crate::db::Database;
+use crate::utils::passphrase::{prompt_new_passphrase, read_passphrase};
+
+pub fn rotate(database: &mut Database, user_name: &str) -> Result<(), Box<dyn std::error::Error>> {
+    let user_id = database.get_user_by_name(user_name)
+        .map(|user| user.id.clone())
+        .ok_or_else(|| format!("User '{}' not found", user_name))?;
+
+    // A key still in plain text has no old passphrase to rotate from
+    if database.get_user(&user_id).is_some_and(|user| user.encrypted_api_key.is_none()) {
+        let passphrase = prompt_new_passphrase("Your API key is stored unencrypted. Choose a passphrase to encrypt it:")?;
+        database.encrypt_api_key(&user_id, &passphrase)?;
+        println!("API key encrypted.");
+        return Ok(());
+    }
+
+    let old_passphrase = read_passphrase("Current passphrase:")?;
+    // Check the old passphrase before asking for a new one
+    database.unlock_api_key(&user_id, &old_passphrase)?;
+    let new_passphrase = prompt_new_passphrase("New passphrase:")?;
+    database.rotate_passphrase(&user_id, &old_passphrase, &new_passphrase)?;
+    println!("Passphrase changed.");
+    Ok(())
+}
@@ -0,0 +1,172 @@
+use std::io;
+use serde::Serialize;
+use crate::commands::Context;
+use crate::commands::output::{self, Table};
+use crate::models::portfolio_snapshot::PositionKind;
+use crate::models::portfolio_summary::PortfolioSummary;
+use crate::services::price::{QuoteCurrency, BITCOIN_ID, CARDANO_ID};
+use crate::utils::formatting::{format_ada, format_number, format_usd};
+use crate::utils::passphrase::read_passphrase;
+
+/// Portfolio totals as a single CSV record
+#[derive(Debug, Serialize)]
+struct SummaryRow {
+    ada_balance: f64,
+    ada_value: f64,
+    liquid_value: f64,
+    usd_value: f64,
+    num_fts: usize,
+    num_nfts: u32,
+    num_lps: usize,
+}
+
+#[derive(Debug, Serialize)]
+struct PriceRow {
+    coin: &'static str,
+    currency: QuoteCurrency,
+    price: f64,
+    change_24h: Option<f64>,
+}
+
+/// Logs in as the `--user` and fetches the holdings of every wallet, or of
+/// the `--wallet` only
+async fn fetch_summary(context: &Context<'_>) -> Result<PortfolioSummary, Box<dyn std::error::Error>> {
+    login(context).await?;
+    let portfolio = context.user_service.fetch_portfolio_data().await?;
+    match context.wallet {
+        Some(name) => portfolio.wallets.into_iter()
+            .find(|wallet| wallet.wallet_name == name)
+            .map(|wallet| wallet.summary)
+            .ok_or_else(|| format!("Wallet '{}' not found", name).into()),
+        None => Ok(portfolio.total),
+    }
+}
+
+async fn login(context: &Context<'_>) -> Result<(), Box<dyn std::error::Error>> {
+    let user_name = context.require_user()?;
+    let user_id = context.user_service.get_database().lock().await
+        .get_user_by_name(user_name)
+        .map(|user| user.id.clone())
+        .ok_or_else(|| format!("User '{}' not found", user_name))?;
+    let passphrase = read_passphrase(&format!("Passphrase for {}:", user_name))?;
+    context.user_service.login(&user_id, &passphrase).await
+}
+
+pub async fn summary(context: &Context<'_>) -> Result<(), Box<dyn std::error::Error>> {
+    let summary = fetch_summary(context).await?;
+    let ada_usd_price = context.user_service.fetch_prices().await?.price(CARDANO_ID, QuoteCurrency::Usd);
+    let row = SummaryRow {
+        ada_balance: summary.ada_balance,
+        ada_value: summary.ada_value,
+        liquid_value: summary.liquid_value,
+        usd_value: summary.ada_value * ada_usd_price,
+        num_fts: summary.positions_ft.len(),
+        num_nfts: summary.num_nfts,
+        num_lps: summary.positions_lp.len(),
+    };
+
+    let mut table = Table::new(&["Metric", "Value"]);
+    table.push(vec!["ADA balance".to_string(), format_ada(row.ada_balance, 2)]);
+    table.push(vec!["ADA value".to_string(), format_ada(row.ada_value, 2)]);
+    table.push(vec!["Liquid value".to_string(), format_ada(row.liquid_value, 2)]);
+    table.push(vec!["USD value".to_string(), format_usd(row.usd_value, 2)]);
+    table.push(vec!["Fungible tokens".to_string(), row.num_fts.to_string()]);
+    table.push(vec!["NFTs".to_string(), row.num_nfts.to_string()]);
+    table.push(vec!["Liquidity positions".to_string(), row.num_lps.to_string()]);
+    output::write(io::stdout(), context.format, &table, &summary, &[row])
+}
+
+pub async fn positions(context: &Context<'_>, kind: PositionKind) -> Result<(), Box<dyn std::error::Error>> {
+    let summary = fetch_summary(context).await?;
+    let percent = |change: Option<f64>| format!("{:+.2}%", change.unwrap_or(0.0) * 100.0);
+    let stdout = io::stdout();
+
+    match kind {
+        PositionKind::Ft => {
+            let mut table = Table::new(&["Ticker", "Balance", "ADA Value", "Price", "24h %", "7d %", "30d %"]);
+            for p in &summary.positions_ft {
+                table.push(vec![
+                    p.ticker.clone(),
+                    format_number(p.balance, 2),
+                    format_ada(p.ada_value, 2),
+                    format_ada(p.price.unwrap_or(0.0), 4),
+                    percent(p.change_24h),
+                    percent(p.change_7d),
+                    percent(p.change_30d),
+                ]);
+            }
+            output::write(stdout, context.format, &table, &summary.positions_ft, &summary.positions_ft)
+        },
+        PositionKind::Nft => {
+            let mut table = Table::new(&["Name", "Balance", "Floor Price", "ADA Value", "24h %", "7d %", "30d %"]);
+            for p in &summary.positions_nft {
+                table.push(vec![
+                    p.name.clone(),
+                    p.balance.to_string(),
+                    format_ada(p.floor_price, 2),
+                    format_ada(p.ada_value, 2),
+                    percent(p.change_24h),
+                    percent(p.change_7d),
+                    percent(p.change_30d),
+                ]);
+            }
+            output::write(stdout, context.format, &table, &summary.positions_nft, &summary.positions_nft)
+        },
+        PositionKind::Lp => {
+            let mut table = Table::new(&["Pool", "Token A", "Amount A", "Token B", "Amount B", "ADA Value"]);
+            for p in &summary.positions_lp {
+                table.push(vec![
+                    format!("{} ({})", p.exchange, p.ticker),
+                    p.token_a_name.clone(),
+                    format_number(p.token_a_amount, 2),
+                    p.token_b_name.clone(),
+                    format_number(p.token_b_amount, 2),
+                    format_ada(p.ada_value, 2),
+                ]);
+            }
+            output::write(stdout, context.format, &table, &summary.positions_lp, &summary.positions_lp)
+        },
+    }
+}
+
+pub async fn prices(context: &Context<'_>) -> Result<(), Box<dyn std::error::Error>> {
+    let prices = context.user_service.fetch_prices().await?;
+
+    let mut rows = Vec::new();
+    for (coin, id) in [("ADA", CARDANO_ID), ("BTC", BITCOIN_ID)] {
+        for currency in QuoteCurrency::ALL {
+            if let Some(quote) = prices.get(id, currency) {
+                rows.push(PriceRow { coin, currency, price: quote.price, change_24h: quote.change_24h });
+            }
+        }
+    }
+
+    let mut table = Table::new(&["Coin", "Currency", "Price", "24h %"]);
+    for row in &rows {
+        table.push(vec![
+            row.coin.to_string(),
+            row.currency.to_string(),
+            format_number(row.price, if row.currency == QuoteCurrency::Btc { 8 } else { 4 }),
+            row.change_24h.map(|change| format!("{:+.2}%", change)).unwrap_or_default(),
+        ]);
+    }
+    output::write(io::stdout(), context.format, &table, &rows, &rows)
+}
+
+pub async fn market_caps(context: &Context<'_>) -> Result<(), Box<dyn std::error::Error>> {
+    // The market cap endpoint is authenticated with the user's API key
+    login(context).await?;
+    let tokens = context.user_service.get_market_cap_data().await?;
+
+    let mut table = Table::new(&["Ticker", "Price", "Market Cap", "FDV", "Circulating Supply"]);
+    for token in &tokens {
+        table.push(vec![
+            token.ticker.clone(),
+            format_ada(token.price, 6),
+            format_ada(token.mcap, 0),
+            format_ada(token.fdv, 0),
+            format_number(token.circ_supply, 0),
+        ]);
+    }
+    output::write(io::stdout(), context.format, &table, &tokens, &tokens)
+}
@@ -0,0 +1,25 @@
+use std::path::Path;
+use crate::db::{self, Database, DB_PATH_ENV};
+use crate::db::history::History;
+use crate::db::sqlite::SqliteStorage;
+
+/// Copies every user and the snapshot history into a new SQLite database
+/// at `target`
+pub fn migrate(database: &Database, history: &History, target: &Path) -> Result<(), Box<dyn std::error::Error>> {
+    if !db::is_sqlite_path(target) {
+        return Err(format!("{} does not end in .sqlite, .sqlite3 or .db", target.display()).into());
+    }
+    if target.exists() {
+        return Err(format!("{} already exists", target.display()).into());
+    }
+
+    let mut storage = SqliteStorage::open(target)?;
+    let copied = database.copy_to(&mut storage)?;
+    println!("Copied {} users from {} to {}.", copied, database.path().display(), target.display());
+
+    let snapshots = history.copy_to(&mut storage)?;
+    println!("Copied {} snapshots.", snapshots);
+
+    println!("Use it with --database {} or by setting {}.", target.display(), DB_PATH_ENV);
+    Ok(())
+}
@@ -0,0 +1,2 @@
+#[cfg(test)]
+mod output_test;
@@ -0,0 +1,75 @@
+use std::path::Path;
+use crate::commands::output::{self, OutputFormat, Table};
+
+#[test]
+fn test_render_aligns_columns() {
+    let mut table = Table::new(&["Ticker", "Value"]);
+    table.push(vec!["HOSKY".to_string(), "₳1,234.00".to_string()]);
+    table.push(vec!["SNEK".to_string(), "₳5.00".to_string()]);
+
+    assert_eq!(
+        table.render(),
+        "Ticker  Value\nHOSKY   ₳1,234.00\nSNEK    ₳5.00\n"
+    );
+}
+
+#[test]
+fn test_render_empty_table_prints_headers() {
+    assert_eq!(Table::new(&["Name", "ID"]).render(), "Name  ID\n");
+}
+
+#[derive(serde::Serialize)]
+struct Row {
+    ticker: &'static str,
+    value: f64,
+}
+
+fn render(format: OutputFormat) -> String {
+    let rows = [Row { ticker: "HOSKY", value: 1234.0 }];
+    let mut table = Table::new(&["Ticker", "Value"]);
+    table.push(vec!["HOSKY".to_string(), "₳1,234.00".to_string()]);
+
+    let mut out = Vec::new();
+    output::write(&mut out, format, &table, &rows, &rows).unwrap();
+    String::from_utf8(out).unwrap()
+}
+
+#[test]
+fn test_write_in_every_format() {
+    assert_eq!(render(OutputFormat::Table), "Ticker  Value\nHOSKY   ₳1,234.00\n");
+    assert_eq!(
+        render(OutputFormat::Markdown),
+        "| Ticker | Value |\n| --- | --- |\n| HOSKY | ₳1,234.00 |\n"
+    );
+    assert_eq!(render(OutputFormat::Csv), "ticker,value\nHOSKY,1234.0\n");
+
+    let json: serde_json::Value = serde_json::from_str(&render(OutputFormat::Json)).unwrap();
+    assert_eq!(json[0]["ticker"], "HOSKY");
+    assert_eq!(json[0]["value"], 1234.0);
+}
+
+#[test]
+fn test_markdown_escapes_pipes() {
+    let mut table = Table::new(&["Pool"]);
+    table.push(vec!["a|b".to_string()]);
+    assert_eq!(table.render_markdown(), "| Pool |\n| --- |\n| a\\|b |\n");
+}
+
+#[test]
+fn test_output_format_parsing() {
+    assert_eq!("JSON".parse::<OutputFormat>(), Ok(OutputFormat::Json));
+    assert_eq!("md".parse::<OutputFormat>(), Ok(OutputFormat::Markdown));
+    assert!("xml".parse::<OutputFormat>().is_err());
+}
+
+#[test]
+fn test_file_format_follows_the_extension() {
+    assert_eq!(OutputFormat::for_file(Path::new("gains.csv"), OutputFormat::Table), OutputFormat::Csv);
+    assert_eq!(OutputFormat::for_file(Path::new("gains.JSON"), OutputFormat::Table), OutputFormat::Json);
+    assert_eq!(OutputFormat::for_file(Path::new("out/gains.md"), OutputFormat::Csv), OutputFormat::Markdown);
+
+    // Without a known extension the requested format is kept, except the
+    // terminal table
+    assert_eq!(OutputFormat::for_file(Path::new("gains"), OutputFormat::Json), OutputFormat::Json);
+    assert_eq!(OutputFormat::for_file(Path::new("gains.out"), OutputFormat::Table), OutputFormat::Csv);
+}
@@ -0,0 +1,35 @@
+use std::io;
+use serde::Serialize;
+use crate::commands::output::{self, OutputFormat, Table};
+use crate::db::Database;
+
+/// A user without their API key
+#[derive(Debug, Serialize)]
+struct UserRow {
+    name: String,
+    id: String,
+    /// Wallet names separated by commas
+    wallets: String,
+}
+
+pub fn list(database: &Database, format: OutputFormat) -> Result<(), Box<dyn std::error::Error>> {
+    let mut users: Vec<_> = database.users().collect();
+    users.sort_by(|a, b| a.name.cmp(&b.name));
+
+    let rows: Vec<UserRow> = users.iter()
+        .map(|user| UserRow {
+            name: user.name.clone(),
+            id: user.id.clone(),
+            wallets: user.wallets.iter()
+                .map(|wallet| wallet.name.as_str())
+                .collect::<Vec<_>>()
+                .join(", "),
+        })
+        .collect();
+
+    let mut table = Table::new(&["Name", "ID", "Wallets"]);
+    for row in &rows {
+        table.push(vec![row.name.clone(), row.id.clone(), row.wallets.clone()]);
+    }
+    output::write(io::stdout(), format, &table, &rows, &rows)
+}
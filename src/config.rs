@@ -0,0 +1,97 @@
+use std::fs;
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
+use serde::{Deserialize, Serialize};
+use crate::db::{history::RetentionPolicy, APP_DIR_NAME, DEFAULT_BACKUPS};
+
+pub const CONFIG_FILE_NAME: &str = "config.json";
+
+pub const DEFAULT_TAPTOOLS_URL: &str = "https://openapi.taptools.io/api/v1";
+pub const DEFAULT_COINGECKO_URL: &str = "https://api.coingecko.com/api/v3";
+
+const TAPTOOLS_URL_ENV: &str = "TAPTOOLS_API_URL";
+const COINGECKO_URL_ENV: &str = "COINGECKO_API_URL";
+const EXPORT_DIR_ENV: &str = "PORTFOLIO_EXPORT_DIR";
+
+/// Where the config file is read from when `--config` is not given: the
+/// platform config directory (`$XDG_CONFIG_HOME` on Linux), or the working
+/// directory on platforms without one.
+pub fn default_path() -> PathBuf {
+    match dirs::config_dir() {
+        Some(dir) => dir.join(APP_DIR_NAME).join(CONFIG_FILE_NAME),
+        None => PathBuf::from(CONFIG_FILE_NAME),
+    }
+}
+
+/// Runtime settings. Values come from the defaults, then the config file,
+/// then environment variables, each overriding the previous.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AppConfig {
+    pub taptools_url: String,
+    pub coingecko_url: String,
+    pub history_retention: RetentionPolicy,
+    /// How many previous versions of the database to keep as backups
+    pub database_backups: usize,
+    /// Where tables exported from the TUI are written; the downloads
+    /// directory when unset
+    pub export_dir: Option<PathBuf>,
+}
+
+impl Default for AppConfig {
+    fn default() -> Self {
+        Self {
+            taptools_url: DEFAULT_TAPTOOLS_URL.to_string(),
+            coingecko_url: DEFAULT_COINGECKO_URL.to_string(),
+            history_retention: RetentionPolicy::default(),
+            database_backups: DEFAULT_BACKUPS,
+            export_dir: None,
+        }
+    }
+}
+
+impl AppConfig {
+    /// Loads the config file at `path` if it exists and applies environment overrides
+    pub fn load(path: &Path) -> Result<Self, Box<dyn std::error::Error>> {
+        let content = match fs::read_to_string(path) {
+            Ok(content) => Some(content),
+            Err(e) if e.kind() == ErrorKind::NotFound => None,
+            Err(e) => return Err(format!("Failed to read {}: {}", path.display(), e).into()),
+        };
+        Self::from_sources(content.as_deref(), |name| std::env::var(name).ok())
+    }
+
+    pub fn from_sources<F>(file_content: Option<&str>, env: F) -> Result<Self, Box<dyn std::error::Error>>
+    where
+        F: Fn(&str) -> Option<String>,
+    {
+        let mut config = match file_content {
+            Some(content) => serde_json::from_str(content)?,
+            None => AppConfig::default(),
+        };
+
+        if let Some(url) = env(TAPTOOLS_URL_ENV) {
+            config.taptools_url = url;
+        }
+        if let Some(url) = env(COINGECKO_URL_ENV) {
+            config.coingecko_url = url;
+        }
+        if let Some(dir) = env(EXPORT_DIR_ENV) {
+            config.export_dir = Some(PathBuf::from(dir));
+        }
+
+        config.taptools_url = config.taptools_url.trim_end_matches('/').to_string();
+        config.coingecko_url = config.coingecko_url.trim_end_matches('/').to_string();
+        Ok(config)
+    }
+
+    /// The configured export directory, otherwise the platform downloads
+    /// directory, the home directory, or the working directory on platforms
+    /// without either
+    pub fn export_dir(&self) -> PathBuf {
+        self.export_dir.clone()
+            .or_else(dirs::download_dir)
+            .or_else(dirs::home_dir)
+            .unwrap_or_else(|| PathBuf::from("."))
+    }
+}
@@ -0,0 +1,128 @@
+use std::path::{Path, PathBuf};
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use crate::db::is_sqlite_path;
+use crate::db::json::JsonStorage;
+use crate::db::sqlite::SqliteStorage;
+use crate::db::storage::{Storage, StorageError};
+use crate::models::portfolio_snapshot::PortfolioSnapshot;
+
+const HISTORY_FILE_NAME: &str = "history.json";
+
+/// How long snapshots are kept. `None` disables that limit.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct RetentionPolicy {
+    pub max_age_days: Option<u32>,
+    pub max_snapshots_per_user: Option<usize>,
+}
+
+impl Default for RetentionPolicy {
+    fn default() -> Self {
+        Self {
+            max_age_days: Some(365 * 2),
+            max_snapshots_per_user: Some(10_000),
+        }
+    }
+}
+
+/// Append-only log of portfolio snapshots, kept in memory and written
+/// through to the `Storage` of the database: a table in a SQLite database,
+/// or a file beside a JSON one
+#[derive(Default)]
+pub struct History {
+    snapshots: Vec<PortfolioSnapshot>,
+    storage: Option<Box<dyn Storage>>,
+}
+
+impl History {
+    /// A history that is not stored anywhere
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The history file kept in the same directory as a JSON `database`
+    pub fn path_beside(database: &Path) -> PathBuf {
+        database.with_file_name(HISTORY_FILE_NAME)
+    }
+
+    /// Loads the history kept with the database at `database`, starting
+    /// empty when there is none yet
+    pub fn load(database: &Path) -> Result<Self, StorageError> {
+        let storage: Box<dyn Storage> = if is_sqlite_path(database) {
+            Box::new(SqliteStorage::open(database)?)
+        } else {
+            Box::new(JsonStorage::new(database))
+        };
+        Self::with_storage(storage)
+    }
+
+    /// Uses `storage` as it is, reading every snapshot from it
+    pub fn with_storage(storage: Box<dyn Storage>) -> Result<Self, StorageError> {
+        Ok(Self { snapshots: storage.snapshots()?, storage: Some(storage) })
+    }
+
+    pub fn save(&mut self) -> Result<(), Box<dyn std::error::Error>> {
+        let storage = self.storage.as_mut().ok_or("History has no storage to save to")?;
+        storage.flush_snapshots(&self.snapshots)?;
+        Ok(())
+    }
+
+    /// Writes every snapshot into `target`, for moving to another backend
+    pub fn copy_to(&self, target: &mut dyn Storage) -> Result<usize, StorageError> {
+        target.add_snapshots(&self.snapshots)?;
+        target.flush_snapshots(&self.snapshots)?;
+        Ok(self.snapshots.len())
+    }
+
+    /// Appends a snapshot and prunes the user's history to the retention policy
+    pub fn record(&mut self, snapshot: PortfolioSnapshot, retention: &RetentionPolicy) -> Result<(), StorageError> {
+        if let Some(storage) = self.storage.as_mut() {
+            storage.add_snapshots(std::slice::from_ref(&snapshot))?;
+        }
+        let user_id = snapshot.user_id.clone();
+        let now = snapshot.timestamp;
+        self.snapshots.push(snapshot);
+        self.prune(&user_id, now, retention)
+    }
+
+    /// Snapshots of a user in chronological order
+    pub fn snapshots_for<'a>(&'a self, user_id: &'a str) -> impl Iterator<Item = &'a PortfolioSnapshot> + 'a {
+        self.snapshots.iter().filter(move |s| s.user_id == user_id)
+    }
+
+    /// Drops the user's snapshots that are older or more than the policy
+    /// allows. Both limits remove the oldest snapshots, so the storage is
+    /// told to drop everything before the oldest one kept.
+    pub fn prune(&mut self, user_id: &str, now: DateTime<Utc>, retention: &RetentionPolicy) -> Result<(), StorageError> {
+        let count = self.snapshots_for(user_id).count();
+
+        if let Some(days) = retention.max_age_days {
+            let cutoff = now - Duration::days(days as i64);
+            self.snapshots.retain(|s| s.user_id != user_id || s.timestamp >= cutoff);
+        }
+
+        if let Some(max) = retention.max_snapshots_per_user {
+            let count = self.snapshots_for(user_id).count();
+            let mut excess = count.saturating_sub(max);
+            self.snapshots.retain(|s| {
+                if excess > 0 && s.user_id == user_id {
+                    excess -= 1;
+                    false
+                } else {
+                    true
+                }
+            });
+        }
+
+        let Some(storage) = self.storage.as_mut() else {
+            return Ok(());
+        };
+        if self.snapshots.iter().filter(|s| s.user_id == user_id).count() == count {
+            return Ok(());
+        }
+        let oldest_kept = self.snapshots.iter()
+            .find(|s| s.user_id == user_id)
+            .map_or(DateTime::<Utc>::MAX_UTC, |s| s.timestamp);
+        storage.delete_snapshots_before(user_id, oldest_kept)
+    }
+}
@@ -0,0 +1,261 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::fs;
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
+use crate::db::history::History;
+use crate::db::migrations::{self, CURRENT_VERSION};
+use crate::db::storage::{Storage, StorageError};
+use crate::db::{backup_path, with_suffix, write_atomic, LoadError, DEFAULT_BACKUPS};
+use crate::models::{
+    cost_basis::Lot,
+    ledger::LedgerEntry,
+    portfolio_snapshot::PortfolioSnapshot,
+    user::User,
+    wallet::Wallet,
+};
+
+/// The file as read, once `migrations` has brought it to the current version
+#[derive(Deserialize)]
+struct JsonFile {
+    users: HashMap<String, User>,
+}
+
+#[derive(Serialize)]
+struct JsonFileRef<'a> {
+    /// Schema version, see `migrations`
+    version: u32,
+    users: &'a HashMap<String, User>,
+}
+
+/// The snapshot history, kept in its own file beside the database
+#[derive(Deserialize)]
+struct HistoryFile {
+    snapshots: Vec<PortfolioSnapshot>,
+}
+
+#[derive(Serialize)]
+struct HistoryFileRef<'a> {
+    snapshots: &'a [PortfolioSnapshot],
+}
+
+/// Where `JsonStorage::open` moves a file it could not parse
+fn corrupt_path(path: &Path) -> PathBuf {
+    with_suffix(path, ".corrupt")
+}
+
+/// Every user in one JSON file, and the snapshot history in another beside
+/// it. It holds no data of its own: the files are read once on open and
+/// rewritten atomically from what `Database` and `History` hand to each
+/// flush.
+pub struct JsonStorage {
+    path: PathBuf,
+    backups: usize,
+    recovered_from: Option<PathBuf>,
+    migrated_from: Option<u32>,
+}
+
+impl JsonStorage {
+    /// A storage that will write to `path`
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self {
+            path: path.into(),
+            backups: DEFAULT_BACKUPS,
+            recovered_from: None,
+            migrated_from: None,
+        }
+    }
+
+    /// Reads the file at `path` and returns the storage along with its
+    /// users, upgrading files written with an older schema. When the file
+    /// is corrupt, or missing while backups exist, the newest backup that
+    /// parses is used instead and the corrupt file is moved aside to
+    /// `<path>.corrupt`; check `recovered_from` to warn about it.
+    pub fn open(path: impl Into<PathBuf>) -> Result<(Self, Vec<User>), LoadError> {
+        let path = path.into();
+        let error = match Self::read(&path) {
+            Ok((from, users)) => return Ok((Self { migrated_from: from, ..Self::new(path) }, users)),
+            Err(error @ (LoadError::Io { .. } | LoadError::Migration { .. })) => return Err(error),
+            Err(error) => error,
+        };
+
+        let backup = (1..)
+            .map(|n| backup_path(&path, n))
+            .take_while(|backup| backup.exists())
+            .find_map(|backup| Self::read(&backup).ok().map(|read| (backup, read)));
+        let Some((backup, (from, users))) = backup else {
+            return Err(error);
+        };
+
+        if let LoadError::Corrupt { .. } = error {
+            // Keep the broken file for inspection, out of the backup rotation
+            fs::rename(&path, corrupt_path(&path))
+                .map_err(|error| LoadError::Io { path: path.clone(), error })?;
+        }
+        let storage = Self { recovered_from: Some(backup), migrated_from: from, ..Self::new(path) };
+        Ok((storage, users))
+    }
+
+    /// The users in the file at `path` and the schema version it was
+    /// upgraded from, if it was
+    fn read(path: &Path) -> Result<(Option<u32>, Vec<User>), LoadError> {
+        let content = match fs::read_to_string(path) {
+            Ok(content) => content,
+            Err(e) if e.kind() == ErrorKind::NotFound => return Err(LoadError::Missing(path.to_path_buf())),
+            Err(error) => return Err(LoadError::Io { path: path.to_path_buf(), error }),
+        };
+        let corrupt = |error| LoadError::Corrupt { path: path.to_path_buf(), error };
+
+        let mut document: Value = serde_json::from_str(&content).map_err(corrupt)?;
+        let from = migrations::migrate(&mut document)
+            .map_err(|error| LoadError::Migration { path: path.to_path_buf(), error })?;
+        let file: JsonFile = serde_json::from_value(document).map_err(corrupt)?;
+        Ok(((from < CURRENT_VERSION).then_some(from), file.users.into_values().collect()))
+    }
+
+    pub fn recovered_from(&self) -> Option<&Path> {
+        self.recovered_from.as_deref()
+    }
+
+    pub fn migrated_from(&self) -> Option<u32> {
+        self.migrated_from
+    }
+
+    /// Shifts `.bak.1` to `.bak.2` and so on, dropping the oldest, then
+    /// copies the current file to `.bak.1`
+    fn rotate_backups(&self) -> std::io::Result<()> {
+        let stale = (self.backups.max(1)..)
+            .map(|n| backup_path(&self.path, n))
+            .take_while(|backup| backup.exists());
+        for backup in stale.collect::<Vec<_>>() {
+            fs::remove_file(backup)?;
+        }
+        if self.backups == 0 || !self.path.exists() {
+            return Ok(());
+        }
+
+        for n in (1..self.backups).rev() {
+            let backup = backup_path(&self.path, n);
+            if backup.exists() {
+                fs::rename(&backup, backup_path(&self.path, n + 1))?;
+            }
+        }
+        fs::copy(&self.path, backup_path(&self.path, 1))?;
+        Ok(())
+    }
+}
+
+// Changes to single users are left to `flush`, which writes them all
+impl Storage for JsonStorage {
+    /// The users as last written to the file, none if it does not exist yet
+    fn users(&self) -> Result<Vec<User>, StorageError> {
+        match Self::read(&self.path) {
+            Ok((_, users)) => Ok(users),
+            Err(LoadError::Missing(_)) => Ok(Vec::new()),
+            Err(LoadError::Io { error, .. }) => Err(StorageError::Io(error)),
+            Err(LoadError::Corrupt { error, .. }) => Err(StorageError::Json(error)),
+            Err(LoadError::Storage { error, .. }) => Err(error),
+            Err(error @ LoadError::Migration { .. }) => Err(StorageError::Invalid(error.to_string())),
+        }
+    }
+
+    fn put_user(&mut self, _user: &User) -> Result<(), StorageError> {
+        Ok(())
+    }
+
+    fn delete_user(&mut self, _id: &str) -> Result<(), StorageError> {
+        Ok(())
+    }
+
+    fn put_wallet(&mut self, _user_id: &str, _wallet: &Wallet) -> Result<(), StorageError> {
+        Ok(())
+    }
+
+    fn delete_wallet(&mut self, _user_id: &str, _wallet_id: &str) -> Result<(), StorageError> {
+        Ok(())
+    }
+
+    fn add_lots(&mut self, _user_id: &str, _lots: &[Lot]) -> Result<(), StorageError> {
+        Ok(())
+    }
+
+    fn add_ledger_entries(&mut self, _user_id: &str, _entries: &[LedgerEntry]) -> Result<(), StorageError> {
+        Ok(())
+    }
+
+    /// Writes the file atomically after rotating the previous version into
+    /// the backups, creating its directory on first save
+    fn flush(&mut self, users: &HashMap<String, User>) -> Result<(), StorageError> {
+        if let Some(dir) = self.path.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+            fs::create_dir_all(dir)?;
+        }
+        let file = JsonFileRef { version: CURRENT_VERSION, users };
+        let content = serde_json::to_string_pretty(&file)?;
+        self.rotate_backups()?;
+        write_atomic(&self.path, content.as_bytes())?;
+        Ok(())
+    }
+
+    /// The snapshots in the history file, none if it does not exist yet
+    fn snapshots(&self) -> Result<Vec<PortfolioSnapshot>, StorageError> {
+        match fs::read_to_string(History::path_beside(&self.path)) {
+            Ok(content) => Ok(serde_json::from_str::<HistoryFile>(&content)?.snapshots),
+            Err(e) if e.kind() == ErrorKind::NotFound => Ok(Vec::new()),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    fn add_snapshots(&mut self, _snapshots: &[PortfolioSnapshot]) -> Result<(), StorageError> {
+        Ok(())
+    }
+
+    fn delete_snapshots_before(&mut self, _user_id: &str, _cutoff: DateTime<Utc>) -> Result<(), StorageError> {
+        Ok(())
+    }
+
+    fn flush_snapshots(&mut self, snapshots: &[PortfolioSnapshot]) -> Result<(), StorageError> {
+        let path = History::path_beside(&self.path);
+        if let Some(dir) = path.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+            fs::create_dir_all(dir)?;
+        }
+        let content = serde_json::to_string(&HistoryFileRef { snapshots })?;
+        write_atomic(&path, content.as_bytes())?;
+        Ok(())
+    }
+
+    fn keep_backups(&mut self, count: usize) {
+        self.backups = count;
+    }
+
+    /// Edits the user's entry in each backup and in the file set aside as
+    /// corrupt, leaving everything else in them as it was
+    fn reseal_api_key(&mut self, user: &User, stale: &str) -> Result<(), StorageError> {
+        let copies: Vec<_> = (1..)
+            .map(|n| backup_path(&self.path, n))
+            .take_while(|backup| backup.exists())
+            .chain(Some(corrupt_path(&self.path)).filter(|corrupt| corrupt.exists()))
+            .collect();
+        for copy in copies {
+            let content = fs::read_to_string(&copy)?;
+            let resealed = match serde_json::from_str::<Value>(&content) {
+                Ok(mut document) => {
+                    let stored = document.get_mut("users")
+                        .and_then(|users| users.get_mut(&user.id))
+                        .and_then(Value::as_object_mut);
+                    let Some(stored) = stored else {
+                        continue;
+                    };
+                    stored.insert("taptools_api_key".to_string(), json!(user.taptools_api_key));
+                    stored.insert("encrypted_api_key".to_string(), serde_json::to_value(&user.encrypted_api_key)?);
+                    serde_json::to_string_pretty(&document)?
+                },
+                Err(_) if !stale.is_empty() && content.contains(stale) => content.replace(stale, ""),
+                Err(_) => continue,
+            };
+            write_atomic(&copy, resealed.as_bytes())?;
+        }
+        Ok(())
+    }
+}
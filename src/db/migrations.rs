@@ -0,0 +1,83 @@
+use std::fmt;
+use serde_json::{json, Value};
+
+/// Upgrades a database document from version `n` to `n + 1`, where `n` is
+/// the migration's index in `MIGRATIONS`. Migrations work on raw JSON and
+/// spell out the values they write, so they keep producing the same
+/// document when the models change later.
+type Migration = fn(&mut Value) -> Result<(), String>;
+
+const MIGRATIONS: &[Migration] = &[
+    backfill_user_fields,
+];
+
+/// Schema version written by this build
+pub const CURRENT_VERSION: u32 = MIGRATIONS.len() as u32;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum MigrationError {
+    /// The file was written by a newer version of the app
+    TooNew(u32),
+    /// The migration from `from` to the next version could not be applied
+    Failed { from: u32, error: String },
+}
+
+impl fmt::Display for MigrationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MigrationError::TooNew(version) => write!(
+                f,
+                "schema version {} is newer than the supported version {}; upgrade the app",
+                version, CURRENT_VERSION,
+            ),
+            MigrationError::Failed { from, error } => {
+                write!(f, "migration from schema version {} failed: {}", from, error)
+            }
+        }
+    }
+}
+
+impl std::error::Error for MigrationError {}
+
+/// Version of a database document. Files from before versioning have none
+/// and count as version 0.
+pub fn version_of(document: &Value) -> u32 {
+    document.get("version")
+        .and_then(Value::as_u64)
+        .map(|version| version as u32)
+        .unwrap_or(0)
+}
+
+/// Applies every migration from the document's version up to
+/// `CURRENT_VERSION` in order. Returns the version it started from.
+pub fn migrate(document: &mut Value) -> Result<u32, MigrationError> {
+    let from = version_of(document);
+    if from > CURRENT_VERSION {
+        return Err(MigrationError::TooNew(from));
+    }
+    for (version, migration) in MIGRATIONS.iter().enumerate().skip(from as usize) {
+        let version = version as u32;
+        migration(document).map_err(|error| MigrationError::Failed { from: version, error })?;
+        document["version"] = json!(version + 1);
+    }
+    Ok(from)
+}
+
+fn users_mut(document: &mut Value) -> Result<impl Iterator<Item = &mut Value>, String> {
+    document.get_mut("users")
+        .and_then(Value::as_object_mut)
+        .map(|users| users.values_mut())
+        .ok_or_else(|| "missing users".to_string())
+}
+
+/// 0 -> 1: writes out the user fields added before versioning existed,
+/// which older files only had through serde defaults
+fn backfill_user_fields(document: &mut Value) -> Result<(), String> {
+    for user in users_mut(document)? {
+        let user = user.as_object_mut().ok_or("user is not an object")?;
+        user.entry("display_currency").or_insert(json!("ada"));
+        user.entry("cost_basis").or_insert(json!({ "method": "fifo", "lots": [] }));
+        user.entry("ledger").or_insert(json!([]));
+    }
+    Ok(())
+}
@@ -1,67 +1,373 @@
-use serde::{Deserialize, Serialize};
+pub mod history;
+pub mod json;
+pub mod migrations;
+pub mod secret;
+pub mod sqlite;
+pub mod storage;
+
 use std::collections::HashMap;
-use std::fs;
+use std::fmt;
+use std::ffi::OsString;
+use std::fs::{self, File};
+use std::io::Write;
+use std::path::{Path, PathBuf};
 use uuid::Uuid;
-use crate::models::{user::User, wallet::Wallet};
+use crate::db::json::JsonStorage;
+use crate::db::migrations::MigrationError;
+use crate::db::secret::EncryptedSecret;
+use crate::db::sqlite::SqliteStorage;
+use crate::db::storage::{Storage, StorageError};
+use crate::models::{
+    cost_basis::{CostBasisBook, CostBasisMethod, Lot},
+    display_currency::DisplayCurrency,
+    ledger::LedgerEntry,
+    user::User,
+    wallet::Wallet,
+};
+
+pub const DB_FILE_NAME: &str = "database.json";
+/// Overrides the database location when `--database` is not given
+pub const DB_PATH_ENV: &str = "PORTFOLIO_DATABASE";
+/// Directory under the platform data and config directories
+pub const APP_DIR_NAME: &str = "cardano_portfolio_summary";
+/// Previous versions of a JSON database kept on each save
+pub const DEFAULT_BACKUPS: usize = 5;
+
+/// Where the database lives when neither the flag nor the environment
+/// names a file: the platform data directory (`$XDG_DATA_HOME` on Linux),
+/// or the working directory on platforms without one.
+pub fn default_path() -> PathBuf {
+    match dirs::data_dir() {
+        Some(dir) => dir.join(APP_DIR_NAME).join(DB_FILE_NAME),
+        None => PathBuf::from(DB_FILE_NAME),
+    }
+}
+
+/// Picks the database file from the `--database` flag, then the
+/// environment, then the platform default
+pub fn resolve_path<F>(flag: Option<&Path>, env: F) -> PathBuf
+where
+    F: Fn(&str) -> Option<String>,
+{
+    flag.map(Path::to_path_buf)
+        .or_else(|| env(DB_PATH_ENV).filter(|path| !path.is_empty()).map(PathBuf::from))
+        .unwrap_or_else(default_path)
+}
+
+#[derive(Debug)]
+pub enum LoadError {
+    /// No database exists at the path yet
+    Missing(PathBuf),
+    /// The file exists but could not be read
+    Io { path: PathBuf, error: std::io::Error },
+    /// The file was read but is not a valid database
+    Corrupt { path: PathBuf, error: serde_json::Error },
+    /// The file could not be upgraded to the current schema
+    Migration { path: PathBuf, error: MigrationError },
+    /// The storage backend failed to open or read the database
+    Storage { path: PathBuf, error: StorageError },
+}
+
+impl fmt::Display for LoadError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LoadError::Missing(path) => write!(f, "No database at {}", path.display()),
+            LoadError::Io { path, error } => write!(f, "Failed to read {}: {}", path.display(), error),
+            LoadError::Corrupt { path, error } => {
+                write!(f, "Database {} is corrupt: {}", path.display(), error)
+            }
+            LoadError::Migration { path, error } => {
+                write!(f, "Database {} could not be upgraded: {}", path.display(), error)
+            }
+            LoadError::Storage { path, error } => {
+                write!(f, "Failed to open database {}: {}", path.display(), error)
+            }
+        }
+    }
+}
 
-const DB_FILE_PATH: &str = "database.json";
+impl std::error::Error for LoadError {}
 
-#[derive(Debug, Serialize, Deserialize)]
+/// `path` with `suffix` appended to its file name
+pub(crate) fn with_suffix(path: &Path, suffix: &str) -> PathBuf {
+    let mut name = path.file_name().map(OsString::from).unwrap_or_default();
+    name.push(suffix);
+    path.with_file_name(name)
+}
+
+/// The `n`th most recent backup of the database at `path`, counting from 1
+pub fn backup_path(path: &Path, n: usize) -> PathBuf {
+    with_suffix(path, &format!(".bak.{}", n))
+}
+
+/// Replaces `path` with `content` so that a crash leaves either the old or
+/// the new file, never a partial one: the data is written and synced to a
+/// temporary file in the same directory, then renamed over `path`.
+pub(crate) fn write_atomic(path: &Path, content: &[u8]) -> std::io::Result<()> {
+    let temp_path = with_suffix(path, ".tmp");
+    let mut file = File::create(&temp_path)?;
+    file.write_all(content)?;
+    file.sync_all()?;
+    fs::rename(&temp_path, path)
+}
+
+/// File extensions that select the SQLite backend; anything else is JSON
+const SQLITE_EXTENSIONS: [&str; 3] = ["sqlite", "sqlite3", "db"];
+
+pub fn is_sqlite_path(path: &Path) -> bool {
+    path.extension()
+        .and_then(|extension| extension.to_str())
+        .is_some_and(|extension| SQLITE_EXTENSIONS.contains(&extension.to_lowercase().as_str()))
+}
+
+/// Users and their wallets, held in memory and written through to a
+/// `Storage` backend
 pub struct Database {
     users: HashMap<String, User>,
+    storage: Box<dyn Storage>,
+    path: PathBuf,
+    /// The backup this database was restored from when the file was unusable
+    recovered_from: Option<PathBuf>,
+    /// The schema version the file had before it was upgraded on load
+    migrated_from: Option<u32>,
+}
+
+impl Default for Database {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl Database {
     pub fn new() -> Self {
+        Self::at(default_path())
+    }
+
+    /// An empty JSON database that will be saved to `path`
+    pub fn at(path: impl Into<PathBuf>) -> Self {
+        let path = path.into();
         Self {
             users: HashMap::new(),
+            storage: Box::new(JsonStorage::new(&path)),
+            path,
+            recovered_from: None,
+            migrated_from: None,
         }
     }
 
-    pub fn load() -> Result<Self, Box<dyn std::error::Error>> {
-        let db_content = fs::read_to_string(DB_FILE_PATH)?;
-        let db: Database = serde_json::from_str(&db_content)?;
-        Ok(db)
+    /// Uses `storage` as it is, reading every user from it
+    pub fn with_storage(path: impl Into<PathBuf>, storage: Box<dyn Storage>) -> Result<Self, StorageError> {
+        let users = storage.users()?;
+        Ok(Self::with_users(path, storage, users))
     }
 
-    pub fn save(&self) -> Result<(), Box<dyn std::error::Error>> {
-        let db_content = serde_json::to_string_pretty(self)?;
-        fs::write(DB_FILE_PATH, db_content)?;
+    fn with_users(path: impl Into<PathBuf>, storage: Box<dyn Storage>, users: Vec<User>) -> Self {
+        Self {
+            users: users.into_iter().map(|user| (user.id.clone(), user)).collect(),
+            storage,
+            path: path.into(),
+            recovered_from: None,
+            migrated_from: None,
+        }
+    }
+
+    /// Keeps `backups` previous versions of the file on each save
+    pub fn with_backups(mut self, backups: usize) -> Self {
+        self.storage.keep_backups(backups);
+        self
+    }
+
+    /// Opens the database at `path`: a SQLite file, created if missing,
+    /// when the extension is one of `SQLITE_EXTENSIONS`, otherwise a JSON
+    /// file. See `JsonStorage::open` for how JSON files are upgraded and
+    /// recovered from backups.
+    pub fn load(path: impl Into<PathBuf>) -> Result<Self, LoadError> {
+        let path = path.into();
+        let storage_error = |path: &Path| {
+            let path = path.to_path_buf();
+            move |error| LoadError::Storage { path, error }
+        };
+
+        if is_sqlite_path(&path) {
+            let storage = SqliteStorage::open(&path).map_err(storage_error(&path))?;
+            return Self::with_storage(&path, Box::new(storage)).map_err(storage_error(&path));
+        }
+
+        let (storage, users) = JsonStorage::open(&path)?;
+        let recovered_from = storage.recovered_from().map(Path::to_path_buf);
+        let migrated_from = storage.migrated_from();
+        let database = Self::with_users(&path, Box::new(storage), users);
+        Ok(Self { recovered_from, migrated_from, ..database })
+    }
+
+    /// Makes every change durable. JSON files are rewritten atomically
+    /// after rotating the previous version into the backups.
+    pub fn save(&mut self) -> Result<(), Box<dyn std::error::Error>> {
+        self.storage.flush(&self.users)?;
         Ok(())
     }
 
-    pub fn create_user(&mut self, name: String, taptools_api_key: String) -> String {
+    /// Writes every user into `target`, for moving to another backend
+    pub fn copy_to(&self, target: &mut dyn Storage) -> Result<usize, StorageError> {
+        for user in self.users.values() {
+            target.put_user(user)?;
+        }
+        target.flush(&self.users)?;
+        Ok(self.users.len())
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    pub fn recovered_from(&self) -> Option<&Path> {
+        self.recovered_from.as_deref()
+    }
+
+    pub fn migrated_from(&self) -> Option<u32> {
+        self.migrated_from
+    }
+
+    /// Creates a user whose API key is encrypted with `passphrase`
+    pub fn create_user(&mut self, name: String, taptools_api_key: String, passphrase: &str) -> Result<String, String> {
+        let encrypted_api_key = EncryptedSecret::seal(&taptools_api_key, passphrase)
+            .map_err(|e| e.to_string())?;
         let id = Uuid::new_v4().to_string();
         let user = User {
             id: id.clone(),
             name,
-            taptools_api_key,
+            taptools_api_key: String::new(),
+            encrypted_api_key: Some(encrypted_api_key),
             wallets: Vec::new(),
+            display_currency: DisplayCurrency::default(),
+            cost_basis: CostBasisBook::default(),
+            ledger: Vec::new(),
         };
+        self.storage.put_user(&user).map_err(|e| e.to_string())?;
         self.users.insert(id.clone(), user);
-        id
+        Ok(id)
     }
 
     pub fn get_user_by_name(&self, name: &str) -> Option<&User> {
         self.users.values().find(|user| user.name == name)
     }
 
+    pub fn users(&self) -> impl Iterator<Item = &User> {
+        self.users.values()
+    }
+
     pub fn get_user(&self, id: &str) -> Option<&User> {
         self.users.get(id)
     }
 
-    pub fn update_user(&mut self, id: &str, taptools_api_key: String) -> Result<(), String> {
+    pub fn update_user(&mut self, id: &str, taptools_api_key: String, passphrase: &str) -> Result<(), String> {
         if let Some(user) = self.users.get_mut(id) {
-            user.taptools_api_key = taptools_api_key;
+            user.encrypted_api_key = Some(EncryptedSecret::seal(&taptools_api_key, passphrase)
+                .map_err(|e| e.to_string())?);
+            user.taptools_api_key.clear();
+            self.storage.put_user(user).map_err(|e| e.to_string())
+        } else {
+            Err("User not found".to_string())
+        }
+    }
+
+    /// Decrypts a user's API key. Keys from older databases that are still
+    /// in plain text are returned as they are.
+    pub fn unlock_api_key(&self, id: &str, passphrase: &str) -> Result<String, String> {
+        let user = self.users.get(id).ok_or("User not found")?;
+        match &user.encrypted_api_key {
+            Some(secret) => secret.open(passphrase).map_err(|e| e.to_string()),
+            None => Ok(user.taptools_api_key.clone()),
+        }
+    }
+
+    /// Encrypts a plain-text API key left by an older version with
+    /// `passphrase` and saves. The backups kept by `save` and a file set
+    /// aside as corrupt still hold the plain-text key, so it is replaced
+    /// with the encrypted one in each of them too.
+    pub fn encrypt_api_key(&mut self, id: &str, passphrase: &str) -> Result<(), Box<dyn std::error::Error>> {
+        let user = self.users.get(id).ok_or("User not found")?;
+        if user.encrypted_api_key.is_some() {
+            return Err("API key is already encrypted".into());
+        }
+        let api_key = user.taptools_api_key.clone();
+        self.update_user(id, api_key.clone(), passphrase)?;
+        self.save()?;
+        self.storage.reseal_api_key(&self.users[id], &api_key)?;
+        Ok(())
+    }
+
+    /// Re-encrypts a user's API key under a new passphrase and saves. The
+    /// key is re-encrypted in the backups as well, so the old passphrase
+    /// unlocks no copy of it while the backups still restore.
+    pub fn rotate_passphrase(
+        &mut self,
+        id: &str,
+        old_passphrase: &str,
+        new_passphrase: &str,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let user = self.users.get_mut(id).ok_or("User not found")?;
+        let secret = user.encrypted_api_key.as_ref().ok_or("API key is not encrypted yet")?;
+        let stale = secret.ciphertext.clone();
+        user.encrypted_api_key = Some(secret.rotate(old_passphrase, new_passphrase)?);
+        self.storage.put_user(user)?;
+        self.save()?;
+        self.storage.reseal_api_key(&self.users[id], &stale)?;
+        Ok(())
+    }
+
+    pub fn set_display_currency(&mut self, id: &str, currency: DisplayCurrency) -> Result<(), String> {
+        if let Some(user) = self.users.get_mut(id) {
+            user.display_currency = currency;
+            self.storage.put_user(user).map_err(|e| e.to_string())
+        } else {
+            Err("User not found".to_string())
+        }
+    }
+
+    pub fn add_lots(&mut self, id: &str, lots: Vec<Lot>) -> Result<(), String> {
+        if let Some(user) = self.users.get_mut(id) {
+            self.storage.add_lots(id, &lots).map_err(|e| e.to_string())?;
+            for lot in lots {
+                user.cost_basis.add_lot(lot);
+            }
             Ok(())
         } else {
             Err("User not found".to_string())
         }
     }
 
+    /// Appends ledger entries to a user, skipping ones already stored so the
+    /// same export can be imported twice. Returns how many were added.
+    pub fn import_ledger(&mut self, id: &str, entries: Vec<LedgerEntry>) -> Result<usize, String> {
+        if let Some(user) = self.users.get_mut(id) {
+            let mut new_entries: Vec<LedgerEntry> = Vec::new();
+            for entry in entries {
+                if !user.ledger.contains(&entry) && !new_entries.contains(&entry) {
+                    new_entries.push(entry);
+                }
+            }
+            self.storage.add_ledger_entries(id, &new_entries).map_err(|e| e.to_string())?;
+            user.ledger.extend_from_slice(&new_entries);
+            user.ledger.sort_by_key(|entry| entry.timestamp);
+            Ok(new_entries.len())
+        } else {
+            Err("User not found".to_string())
+        }
+    }
+
+    pub fn set_cost_basis_method(&mut self, id: &str, method: CostBasisMethod) -> Result<(), String> {
+        if let Some(user) = self.users.get_mut(id) {
+            user.cost_basis.method = method;
+            self.storage.put_user(user).map_err(|e| e.to_string())
+        } else {
+            Err("User not found".to_string())
+        }
+    }
+
     pub fn delete_user(&mut self, id: &str) -> Result<(), String> {
         if self.users.remove(id).is_some() {
-            Ok(())
+            self.storage.delete_user(id).map_err(|e| e.to_string())
         } else {
             Err("User not found".to_string())
         }
@@ -75,6 +381,7 @@ impl Database {
                 name,
                 addresses,
             };
+            self.storage.put_wallet(user_id, &wallet).map_err(|e| e.to_string())?;
             user.wallets.push(wallet);
             Ok(wallet_id)
         } else {
@@ -82,5 +389,61 @@ impl Database {
         }
     }
 
-    // Add more methods for wallet operations as needed
+    pub fn rename_wallet(&mut self, user_id: &str, wallet_id: &str, name: String) -> Result<(), String> {
+        self.update_wallet(user_id, wallet_id, |wallet| {
+            wallet.name = name;
+            Ok(())
+        })
+    }
+
+    pub fn delete_wallet(&mut self, user_id: &str, wallet_id: &str) -> Result<(), String> {
+        let user = self.users.get_mut(user_id).ok_or("User not found")?;
+        let index = user.wallets.iter()
+            .position(|wallet| wallet.id == wallet_id)
+            .ok_or("Wallet not found")?;
+        self.storage.delete_wallet(user_id, wallet_id).map_err(|e| e.to_string())?;
+        user.wallets.remove(index);
+        Ok(())
+    }
+
+    /// Adds an address to a wallet unless it is already there
+    pub fn add_address(&mut self, user_id: &str, wallet_id: &str, address: String) -> Result<(), String> {
+        self.update_wallet(user_id, wallet_id, |wallet| {
+            if wallet.addresses.contains(&address) {
+                return Err("Address is already in this wallet".to_string());
+            }
+            wallet.addresses.push(address);
+            Ok(())
+        })
+    }
+
+    pub fn remove_address(&mut self, user_id: &str, wallet_id: &str, address: &str) -> Result<(), String> {
+        self.update_wallet(user_id, wallet_id, |wallet| {
+            let index = wallet.addresses.iter()
+                .position(|a| a == address)
+                .ok_or("Address not found")?;
+            wallet.addresses.remove(index);
+            Ok(())
+        })
+    }
+
+    /// Applies `change` to a copy of the wallet and keeps it once storage
+    /// has accepted it
+    fn update_wallet<F>(&mut self, user_id: &str, wallet_id: &str, change: F) -> Result<(), String>
+    where
+        F: FnOnce(&mut Wallet) -> Result<(), String>,
+    {
+        let user = self.users.get_mut(user_id).ok_or("User not found")?;
+        let wallet = user.wallets.iter_mut()
+            .find(|wallet| wallet.id == wallet_id)
+            .ok_or("Wallet not found")?;
+        let mut updated = wallet.clone();
+        change(&mut updated)?;
+        self.storage.put_wallet(user_id, &updated).map_err(|e| e.to_string())?;
+        *wallet = updated;
+        Ok(())
+    }
 }
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,119 @@
+use std::fmt;
+use argon2::{Algorithm, Argon2, Params, Version};
+use base64::{Engine, engine::general_purpose::STANDARD as BASE64};
+use chacha20poly1305::{
+    aead::{Aead, AeadCore, KeyInit, OsRng},
+    XChaCha20Poly1305, XNonce,
+};
+use rand::RngCore;
+use serde::{Deserialize, Serialize};
+
+const SALT_LENGTH: usize = 16;
+const KEY_LENGTH: usize = 32;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum SecretError {
+    /// The passphrase did not decrypt the secret, or the data was tampered with
+    WrongPassphrase,
+    /// The stored salt, nonce or ciphertext is not valid base64 or has the wrong length
+    Corrupt(String),
+    /// Argon2 rejected its parameters
+    Kdf(String),
+}
+
+impl fmt::Display for SecretError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SecretError::WrongPassphrase => write!(f, "Wrong passphrase"),
+            SecretError::Corrupt(e) => write!(f, "Stored secret is corrupt: {}", e),
+            SecretError::Kdf(e) => write!(f, "Key derivation failed: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for SecretError {}
+
+/// Argon2id cost parameters, stored with each secret so they can be raised
+/// later without breaking existing files
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct KdfParams {
+    pub memory_kib: u32,
+    pub iterations: u32,
+    pub parallelism: u32,
+}
+
+impl Default for KdfParams {
+    fn default() -> Self {
+        Self {
+            memory_kib: Params::DEFAULT_M_COST,
+            iterations: Params::DEFAULT_T_COST,
+            parallelism: Params::DEFAULT_P_COST,
+        }
+    }
+}
+
+/// A string encrypted with XChaCha20-Poly1305 under a key derived from a
+/// passphrase with Argon2id. Binary fields are base64 encoded.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct EncryptedSecret {
+    pub kdf: KdfParams,
+    pub salt: String,
+    pub nonce: String,
+    pub ciphertext: String,
+}
+
+impl EncryptedSecret {
+    pub fn seal(plaintext: &str, passphrase: &str) -> Result<Self, SecretError> {
+        Self::seal_with(plaintext, passphrase, KdfParams::default())
+    }
+
+    pub fn seal_with(plaintext: &str, passphrase: &str, kdf: KdfParams) -> Result<Self, SecretError> {
+        let mut salt = [0u8; SALT_LENGTH];
+        OsRng.fill_bytes(&mut salt);
+        let cipher = cipher(passphrase, &salt, kdf)?;
+
+        let nonce = XChaCha20Poly1305::generate_nonce(&mut OsRng);
+        let ciphertext = cipher.encrypt(&nonce, plaintext.as_bytes())
+            .map_err(|e| SecretError::Corrupt(e.to_string()))?;
+
+        Ok(Self {
+            kdf,
+            salt: BASE64.encode(salt),
+            nonce: BASE64.encode(nonce),
+            ciphertext: BASE64.encode(ciphertext),
+        })
+    }
+
+    pub fn open(&self, passphrase: &str) -> Result<String, SecretError> {
+        let salt = decode(&self.salt)?;
+        let nonce = decode(&self.nonce)?;
+        if nonce.len() != XNonce::default().len() {
+            return Err(SecretError::Corrupt("nonce has the wrong length".to_string()));
+        }
+        let ciphertext = decode(&self.ciphertext)?;
+
+        let plaintext = cipher(passphrase, &salt, self.kdf)?
+            .decrypt(XNonce::from_slice(&nonce), ciphertext.as_slice())
+            .map_err(|_| SecretError::WrongPassphrase)?;
+        String::from_utf8(plaintext).map_err(|e| SecretError::Corrupt(e.to_string()))
+    }
+
+    /// Re-encrypts under `new_passphrase` with a fresh salt and nonce
+    pub fn rotate(&self, old_passphrase: &str, new_passphrase: &str) -> Result<Self, SecretError> {
+        Self::seal_with(&self.open(old_passphrase)?, new_passphrase, self.kdf)
+    }
+}
+
+fn cipher(passphrase: &str, salt: &[u8], kdf: KdfParams) -> Result<XChaCha20Poly1305, SecretError> {
+    let params = Params::new(kdf.memory_kib, kdf.iterations, kdf.parallelism, Some(KEY_LENGTH))
+        .map_err(|e| SecretError::Kdf(e.to_string()))?;
+    let mut key = [0u8; KEY_LENGTH];
+    Argon2::new(Algorithm::Argon2id, Version::V0x13, params)
+        .hash_password_into(passphrase.as_bytes(), salt, &mut key)
+        .map_err(|e| SecretError::Kdf(e.to_string()))?;
+    Ok(XChaCha20Poly1305::new(&key.into()))
+}
+
+fn decode(value: &str) -> Result<Vec<u8>, SecretError> {
+    BASE64.decode(value).map_err(|e| SecretError::Corrupt(e.to_string()))
+}
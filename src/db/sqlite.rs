@@ -0,0 +1,493 @@
+use std::collections::HashMap;
+use std::path::Path;
+use chrono::{DateTime, Utc};
+use rusqlite::{params, Connection, OptionalExtension, Row, Transaction};
+use serde::{de::DeserializeOwned, Serialize};
+use serde_json::Value;
+use crate::db::storage::{Storage, StorageError};
+use crate::models::{
+    cost_basis::{CostBasisBook, Lot},
+    ledger::{FiatAmount, LedgerEntry},
+    portfolio_snapshot::{PortfolioSnapshot, PositionSnapshot},
+    user::User,
+    wallet::Wallet,
+};
+
+/// Statements that upgrade the tables from version `n` to `n + 1`, where
+/// `n` is the statement's index. `PRAGMA user_version` holds the version.
+const MIGRATIONS: &[&str] = &[
+    USERS_SCHEMA,
+    SNAPSHOTS_SCHEMA,
+];
+
+/// Version of the tables once every migration has run
+const SCHEMA_VERSION: u32 = MIGRATIONS.len() as u32;
+
+const USERS_SCHEMA: &str = "
+    CREATE TABLE users (
+        id TEXT PRIMARY KEY,
+        name TEXT NOT NULL,
+        taptools_api_key TEXT NOT NULL DEFAULT '',
+        encrypted_api_key TEXT,
+        display_currency TEXT NOT NULL,
+        cost_basis_method TEXT NOT NULL
+    );
+    CREATE TABLE wallets (
+        user_id TEXT NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+        id TEXT NOT NULL,
+        position INTEGER NOT NULL,
+        name TEXT NOT NULL,
+        PRIMARY KEY (user_id, id)
+    );
+    CREATE TABLE wallet_addresses (
+        user_id TEXT NOT NULL,
+        wallet_id TEXT NOT NULL,
+        position INTEGER NOT NULL,
+        address TEXT NOT NULL,
+        PRIMARY KEY (user_id, wallet_id, position),
+        FOREIGN KEY (user_id, wallet_id) REFERENCES wallets(user_id, id) ON DELETE CASCADE
+    );
+    CREATE TABLE lots (
+        id INTEGER PRIMARY KEY,
+        user_id TEXT NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+        unit TEXT NOT NULL,
+        acquired_on TEXT NOT NULL,
+        quantity REAL NOT NULL,
+        cost_ada REAL NOT NULL
+    );
+    CREATE TABLE ledger_entries (
+        id INTEGER PRIMARY KEY,
+        user_id TEXT NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+        timestamp TEXT NOT NULL,
+        wallet_id TEXT,
+        side TEXT NOT NULL,
+        ticker TEXT NOT NULL,
+        unit TEXT,
+        quantity REAL NOT NULL,
+        ada_value REAL,
+        fiat_amount REAL,
+        fiat_currency TEXT,
+        fee_ada REAL NOT NULL,
+        tx_hash TEXT,
+        label TEXT
+    );
+    CREATE INDEX ledger_entries_by_user ON ledger_entries (user_id, timestamp);
+";
+
+/// Snapshots are not tied to a user row, so history outlives a deleted
+/// user as it does in the JSON history file
+const SNAPSHOTS_SCHEMA: &str = "
+    CREATE TABLE snapshots (
+        id INTEGER PRIMARY KEY,
+        user_id TEXT NOT NULL,
+        timestamp TEXT NOT NULL,
+        ada_balance REAL NOT NULL,
+        ada_value REAL NOT NULL,
+        liquid_value REAL NOT NULL,
+        ada_usd_price REAL NOT NULL
+    );
+    CREATE INDEX snapshots_by_user ON snapshots (user_id, timestamp);
+    CREATE TABLE snapshot_positions (
+        snapshot_id INTEGER NOT NULL REFERENCES snapshots(id) ON DELETE CASCADE,
+        position INTEGER NOT NULL,
+        kind TEXT NOT NULL,
+        unit TEXT NOT NULL,
+        ticker TEXT NOT NULL,
+        balance REAL NOT NULL,
+        price REAL,
+        ada_value REAL NOT NULL,
+        PRIMARY KEY (snapshot_id, position)
+    );
+";
+
+/// Users in an embedded SQLite database, with wallets, lots, ledger
+/// entries and snapshots in their own tables. Every change is committed as
+/// it is made.
+pub struct SqliteStorage {
+    connection: Connection,
+}
+
+impl SqliteStorage {
+    /// Opens the database at `path`, creating it and its tables if needed
+    /// and adding the tables of newer versions
+    pub fn open(path: &Path) -> Result<Self, StorageError> {
+        if let Some(dir) = path.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+            std::fs::create_dir_all(dir)?;
+        }
+        Self::init(Connection::open(path)?)
+    }
+
+    /// A database that lives only as long as the value, for tests
+    pub fn in_memory() -> Result<Self, StorageError> {
+        Self::init(Connection::open_in_memory()?)
+    }
+
+    fn init(mut connection: Connection) -> Result<Self, StorageError> {
+        connection.pragma_update(None, "foreign_keys", true)?;
+        let version: u32 = connection.pragma_query_value(None, "user_version", |row| row.get(0))?;
+        if version > SCHEMA_VERSION {
+            return Err(StorageError::Invalid(format!(
+                "schema version {} is newer than the supported version {}; upgrade the app",
+                version, SCHEMA_VERSION,
+            )));
+        }
+        for (from, migration) in MIGRATIONS.iter().enumerate().skip(version as usize) {
+            let transaction = connection.transaction()?;
+            transaction.execute_batch(migration)?;
+            transaction.pragma_update(None, "user_version", from as u32 + 1)?;
+            transaction.commit()?;
+        }
+        Ok(Self { connection })
+    }
+
+    fn user_exists(&self, id: &str) -> Result<(), StorageError> {
+        self.connection
+            .query_row("SELECT 1 FROM users WHERE id = ?1", [id], |_| Ok(()))
+            .optional()?
+            .ok_or_else(|| StorageError::UserNotFound(id.to_string()))
+    }
+
+    fn read_user(&self, row: &Row) -> Result<User, StorageError> {
+        let id: String = row.get("id")?;
+        let encrypted_api_key: Option<String> = row.get("encrypted_api_key")?;
+        Ok(User {
+            name: row.get("name")?,
+            taptools_api_key: row.get("taptools_api_key")?,
+            encrypted_api_key: encrypted_api_key.map(|json| serde_json::from_str(&json)).transpose()?,
+            display_currency: from_text(&row.get::<_, String>("display_currency")?)?,
+            cost_basis: CostBasisBook {
+                method: from_text(&row.get::<_, String>("cost_basis_method")?)?,
+                lots: self.read_lots(&id)?,
+            },
+            wallets: self.read_wallets(&id)?,
+            ledger: self.read_ledger(&id)?,
+            id,
+        })
+    }
+
+    fn read_wallets(&self, user_id: &str) -> Result<Vec<Wallet>, StorageError> {
+        let mut wallets = self.connection
+            .prepare("SELECT id, name FROM wallets WHERE user_id = ?1 ORDER BY position")?
+            .query_map([user_id], |row| {
+                Ok(Wallet { id: row.get(0)?, name: row.get(1)?, addresses: Vec::new() })
+            })?
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let mut addresses = self.connection
+            .prepare("SELECT address FROM wallet_addresses WHERE user_id = ?1 AND wallet_id = ?2 ORDER BY position")?;
+        for wallet in &mut wallets {
+            wallet.addresses = addresses
+                .query_map([user_id, &wallet.id], |row| row.get(0))?
+                .collect::<Result<_, _>>()?;
+        }
+        Ok(wallets)
+    }
+
+    fn read_lots(&self, user_id: &str) -> Result<Vec<Lot>, StorageError> {
+        let lots = self.connection
+            .prepare("SELECT unit, acquired_on, quantity, cost_ada FROM lots WHERE user_id = ?1 ORDER BY id")?
+            .query_map([user_id], |row| {
+                Ok(Lot {
+                    unit: row.get(0)?,
+                    acquired_on: row.get(1)?,
+                    quantity: row.get(2)?,
+                    cost_ada: row.get(3)?,
+                })
+            })?
+            .collect::<Result<_, _>>()?;
+        Ok(lots)
+    }
+
+    fn read_ledger(&self, user_id: &str) -> Result<Vec<LedgerEntry>, StorageError> {
+        let mut statement = self.connection.prepare(
+            "SELECT timestamp, wallet_id, side, ticker, unit, quantity, ada_value,
+                    fiat_amount, fiat_currency, fee_ada, tx_hash, label
+             FROM ledger_entries WHERE user_id = ?1 ORDER BY timestamp, id",
+        )?;
+        let mut rows = statement.query([user_id])?;
+        let mut entries = Vec::new();
+        while let Some(row) = rows.next()? {
+            let fiat_amount: Option<f64> = row.get("fiat_amount")?;
+            let fiat_currency: Option<String> = row.get("fiat_currency")?;
+            let fiat_value = match (fiat_amount, fiat_currency) {
+                (Some(amount), Some(currency)) => Some(FiatAmount { amount, currency: from_text(&currency)? }),
+                _ => None,
+            };
+            entries.push(LedgerEntry {
+                timestamp: row.get("timestamp")?,
+                wallet_id: row.get("wallet_id")?,
+                side: from_text(&row.get::<_, String>("side")?)?,
+                ticker: row.get("ticker")?,
+                unit: row.get("unit")?,
+                quantity: row.get("quantity")?,
+                ada_value: row.get("ada_value")?,
+                fiat_value,
+                fee_ada: row.get("fee_ada")?,
+                tx_hash: row.get("tx_hash")?,
+                label: row.get("label")?,
+            });
+        }
+        Ok(entries)
+    }
+}
+
+impl Storage for SqliteStorage {
+    fn users(&self) -> Result<Vec<User>, StorageError> {
+        let mut statement = self.connection.prepare("SELECT * FROM users")?;
+        let mut rows = statement.query([])?;
+        let mut users = Vec::new();
+        while let Some(row) = rows.next()? {
+            users.push(self.read_user(row)?);
+        }
+        Ok(users)
+    }
+
+    fn put_user(&mut self, user: &User) -> Result<(), StorageError> {
+        let encrypted_api_key = user.encrypted_api_key.as_ref().map(serde_json::to_string).transpose()?;
+        let transaction = self.connection.transaction()?;
+        transaction.execute(
+            "INSERT INTO users (id, name, taptools_api_key, encrypted_api_key, display_currency, cost_basis_method)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6)
+             ON CONFLICT (id) DO UPDATE SET
+                 name = excluded.name,
+                 taptools_api_key = excluded.taptools_api_key,
+                 encrypted_api_key = excluded.encrypted_api_key,
+                 display_currency = excluded.display_currency,
+                 cost_basis_method = excluded.cost_basis_method",
+            params![
+                user.id,
+                user.name,
+                user.taptools_api_key,
+                encrypted_api_key,
+                to_text(&user.display_currency)?,
+                to_text(&user.cost_basis.method)?,
+            ],
+        )?;
+
+        for table in ["wallets", "lots", "ledger_entries"] {
+            transaction.execute(&format!("DELETE FROM {} WHERE user_id = ?1", table), [&user.id])?;
+        }
+        for (position, wallet) in user.wallets.iter().enumerate() {
+            insert_wallet(&transaction, &user.id, position, wallet)?;
+        }
+        insert_lots(&transaction, &user.id, &user.cost_basis.lots)?;
+        insert_ledger_entries(&transaction, &user.id, &user.ledger)?;
+        transaction.commit()?;
+        Ok(())
+    }
+
+    fn delete_user(&mut self, id: &str) -> Result<(), StorageError> {
+        self.connection.execute("DELETE FROM users WHERE id = ?1", [id])?;
+        Ok(())
+    }
+
+    fn put_wallet(&mut self, user_id: &str, wallet: &Wallet) -> Result<(), StorageError> {
+        self.user_exists(user_id)?;
+        let transaction = self.connection.transaction()?;
+        let existing: Option<usize> = transaction
+            .query_row(
+                "SELECT position FROM wallets WHERE user_id = ?1 AND id = ?2",
+                [user_id, &wallet.id],
+                |row| row.get(0),
+            )
+            .optional()?;
+        let position = match existing {
+            Some(position) => {
+                transaction.execute("DELETE FROM wallets WHERE user_id = ?1 AND id = ?2", [user_id, &wallet.id])?;
+                position
+            }
+            None => transaction.query_row(
+                "SELECT COALESCE(MAX(position) + 1, 0) FROM wallets WHERE user_id = ?1",
+                [user_id],
+                |row| row.get(0),
+            )?,
+        };
+        insert_wallet(&transaction, user_id, position, wallet)?;
+        transaction.commit()?;
+        Ok(())
+    }
+
+    fn delete_wallet(&mut self, user_id: &str, wallet_id: &str) -> Result<(), StorageError> {
+        self.connection.execute(
+            "DELETE FROM wallets WHERE user_id = ?1 AND id = ?2",
+            [user_id, wallet_id],
+        )?;
+        Ok(())
+    }
+
+    fn add_lots(&mut self, user_id: &str, lots: &[Lot]) -> Result<(), StorageError> {
+        self.user_exists(user_id)?;
+        let transaction = self.connection.transaction()?;
+        insert_lots(&transaction, user_id, lots)?;
+        transaction.commit()?;
+        Ok(())
+    }
+
+    fn add_ledger_entries(&mut self, user_id: &str, entries: &[LedgerEntry]) -> Result<(), StorageError> {
+        self.user_exists(user_id)?;
+        let transaction = self.connection.transaction()?;
+        insert_ledger_entries(&transaction, user_id, entries)?;
+        transaction.commit()?;
+        Ok(())
+    }
+
+    /// Nothing to do: every change was committed when it was made
+    fn flush(&mut self, _users: &HashMap<String, User>) -> Result<(), StorageError> {
+        Ok(())
+    }
+
+    fn snapshots(&self) -> Result<Vec<PortfolioSnapshot>, StorageError> {
+        let mut positions: HashMap<i64, Vec<PositionSnapshot>> = HashMap::new();
+        let mut statement = self.connection.prepare(
+            "SELECT snapshot_id, kind, unit, ticker, balance, price, ada_value
+             FROM snapshot_positions ORDER BY snapshot_id, position",
+        )?;
+        let mut rows = statement.query([])?;
+        while let Some(row) = rows.next()? {
+            positions.entry(row.get("snapshot_id")?).or_default().push(PositionSnapshot {
+                kind: from_text(&row.get::<_, String>("kind")?)?,
+                unit: row.get("unit")?,
+                ticker: row.get("ticker")?,
+                balance: row.get("balance")?,
+                price: row.get("price")?,
+                ada_value: row.get("ada_value")?,
+            });
+        }
+
+        let snapshots = self.connection
+            .prepare("SELECT * FROM snapshots ORDER BY id")?
+            .query_map([], |row| {
+                let id: i64 = row.get("id")?;
+                Ok(PortfolioSnapshot {
+                    timestamp: row.get("timestamp")?,
+                    user_id: row.get("user_id")?,
+                    ada_balance: row.get("ada_balance")?,
+                    ada_value: row.get("ada_value")?,
+                    liquid_value: row.get("liquid_value")?,
+                    ada_usd_price: row.get("ada_usd_price")?,
+                    positions: positions.remove(&id).unwrap_or_default(),
+                })
+            })?
+            .collect::<Result<_, _>>()?;
+        Ok(snapshots)
+    }
+
+    fn add_snapshots(&mut self, snapshots: &[PortfolioSnapshot]) -> Result<(), StorageError> {
+        let transaction = self.connection.transaction()?;
+        for snapshot in snapshots {
+            insert_snapshot(&transaction, snapshot)?;
+        }
+        transaction.commit()?;
+        Ok(())
+    }
+
+    fn delete_snapshots_before(&mut self, user_id: &str, cutoff: DateTime<Utc>) -> Result<(), StorageError> {
+        self.connection.execute(
+            "DELETE FROM snapshots WHERE user_id = ?1 AND timestamp < ?2",
+            params![user_id, cutoff],
+        )?;
+        Ok(())
+    }
+
+    /// Nothing to do: every snapshot was committed when it was added
+    fn flush_snapshots(&mut self, _snapshots: &[PortfolioSnapshot]) -> Result<(), StorageError> {
+        Ok(())
+    }
+}
+
+fn insert_wallet(transaction: &Transaction, user_id: &str, position: usize, wallet: &Wallet) -> Result<(), StorageError> {
+    transaction.execute(
+        "INSERT INTO wallets (id, user_id, position, name) VALUES (?1, ?2, ?3, ?4)",
+        params![wallet.id, user_id, position, wallet.name],
+    )?;
+    let mut statement = transaction.prepare(
+        "INSERT INTO wallet_addresses (user_id, wallet_id, position, address) VALUES (?1, ?2, ?3, ?4)",
+    )?;
+    for (position, address) in wallet.addresses.iter().enumerate() {
+        statement.execute(params![user_id, wallet.id, position, address])?;
+    }
+    Ok(())
+}
+
+fn insert_lots(transaction: &Transaction, user_id: &str, lots: &[Lot]) -> Result<(), StorageError> {
+    let mut statement = transaction.prepare(
+        "INSERT INTO lots (user_id, unit, acquired_on, quantity, cost_ada) VALUES (?1, ?2, ?3, ?4, ?5)",
+    )?;
+    for lot in lots {
+        statement.execute(params![user_id, lot.unit, lot.acquired_on, lot.quantity, lot.cost_ada])?;
+    }
+    Ok(())
+}
+
+fn insert_ledger_entries(transaction: &Transaction, user_id: &str, entries: &[LedgerEntry]) -> Result<(), StorageError> {
+    let mut statement = transaction.prepare(
+        "INSERT INTO ledger_entries (user_id, timestamp, wallet_id, side, ticker, unit, quantity,
+             ada_value, fiat_amount, fiat_currency, fee_ada, tx_hash, label)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13)",
+    )?;
+    for entry in entries {
+        let fiat_currency = entry.fiat_value.map(|fiat| to_text(&fiat.currency)).transpose()?;
+        statement.execute(params![
+            user_id,
+            entry.timestamp,
+            entry.wallet_id,
+            to_text(&entry.side)?,
+            entry.ticker,
+            entry.unit,
+            entry.quantity,
+            entry.ada_value,
+            entry.fiat_value.map(|fiat| fiat.amount),
+            fiat_currency,
+            entry.fee_ada,
+            entry.tx_hash,
+            entry.label,
+        ])?;
+    }
+    Ok(())
+}
+
+fn insert_snapshot(transaction: &Transaction, snapshot: &PortfolioSnapshot) -> Result<(), StorageError> {
+    transaction.execute(
+        "INSERT INTO snapshots (user_id, timestamp, ada_balance, ada_value, liquid_value, ada_usd_price)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+        params![
+            snapshot.user_id,
+            snapshot.timestamp,
+            snapshot.ada_balance,
+            snapshot.ada_value,
+            snapshot.liquid_value,
+            snapshot.ada_usd_price,
+        ],
+    )?;
+    let snapshot_id = transaction.last_insert_rowid();
+    let mut statement = transaction.prepare(
+        "INSERT INTO snapshot_positions (snapshot_id, position, kind, unit, ticker, balance, price, ada_value)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+    )?;
+    for (position, held) in snapshot.positions.iter().enumerate() {
+        statement.execute(params![
+            snapshot_id,
+            position,
+            to_text(&held.kind)?,
+            held.unit,
+            held.ticker,
+            held.balance,
+            held.price,
+            held.ada_value,
+        ])?;
+    }
+    Ok(())
+}
+
+/// Stores a unit-like enum as the same string it has in the JSON file
+fn to_text<T: Serialize>(value: &T) -> Result<String, StorageError> {
+    match serde_json::to_value(value)? {
+        Value::String(text) => Ok(text),
+        other => Err(StorageError::Invalid(format!("expected a string, got {}", other))),
+    }
+}
+
+fn from_text<T: DeserializeOwned>(text: &str) -> Result<T, StorageError> {
+    serde_json::from_value(Value::String(text.to_string()))
+        .map_err(|_| StorageError::Invalid(format!("unknown value '{}'", text)))
+}
@@ -0,0 +1,101 @@
+use std::collections::HashMap;
+use std::fmt;
+use chrono::{DateTime, Utc};
+use crate::models::{
+    cost_basis::Lot,
+    ledger::LedgerEntry,
+    portfolio_snapshot::PortfolioSnapshot,
+    user::User,
+    wallet::Wallet,
+};
+
+#[derive(Debug)]
+pub enum StorageError {
+    Io(std::io::Error),
+    Json(serde_json::Error),
+    Sqlite(rusqlite::Error),
+    /// A value read back from storage could not be decoded
+    Invalid(String),
+    UserNotFound(String),
+}
+
+impl fmt::Display for StorageError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            StorageError::Io(e) => write!(f, "I/O error: {}", e),
+            StorageError::Json(e) => write!(f, "JSON error: {}", e),
+            StorageError::Sqlite(e) => write!(f, "SQLite error: {}", e),
+            StorageError::Invalid(e) => write!(f, "Invalid stored value: {}", e),
+            StorageError::UserNotFound(id) => write!(f, "User {} not found in storage", id),
+        }
+    }
+}
+
+impl std::error::Error for StorageError {}
+
+impl From<std::io::Error> for StorageError {
+    fn from(error: std::io::Error) -> Self {
+        StorageError::Io(error)
+    }
+}
+
+impl From<serde_json::Error> for StorageError {
+    fn from(error: serde_json::Error) -> Self {
+        StorageError::Json(error)
+    }
+}
+
+impl From<rusqlite::Error> for StorageError {
+    fn from(error: rusqlite::Error) -> Self {
+        StorageError::Sqlite(error)
+    }
+}
+
+/// Where `Database` persists users and `History` its snapshots. Each holds
+/// the only copy of its data in memory. Backends that store records
+/// separately write each change through as it is made, while backends that
+/// store everything in one document ignore the individual changes and
+/// write all of it on `flush` and `flush_snapshots`.
+pub trait Storage: Send {
+    /// Every stored user with their wallets, lots and ledger
+    fn users(&self) -> Result<Vec<User>, StorageError>;
+
+    /// Inserts a user or replaces a stored one, including everything they own
+    fn put_user(&mut self, user: &User) -> Result<(), StorageError>;
+
+    fn delete_user(&mut self, id: &str) -> Result<(), StorageError>;
+
+    /// Inserts a wallet for a user or replaces the one with the same id
+    fn put_wallet(&mut self, user_id: &str, wallet: &Wallet) -> Result<(), StorageError>;
+
+    fn delete_wallet(&mut self, user_id: &str, wallet_id: &str) -> Result<(), StorageError>;
+
+    fn add_lots(&mut self, user_id: &str, lots: &[Lot]) -> Result<(), StorageError>;
+
+    fn add_ledger_entries(&mut self, user_id: &str, entries: &[LedgerEntry]) -> Result<(), StorageError>;
+
+    /// Makes every change durable; `users` is the complete current set
+    fn flush(&mut self, users: &HashMap<String, User>) -> Result<(), StorageError>;
+
+    /// Every recorded portfolio snapshot in the order they were added
+    fn snapshots(&self) -> Result<Vec<PortfolioSnapshot>, StorageError>;
+
+    fn add_snapshots(&mut self, snapshots: &[PortfolioSnapshot]) -> Result<(), StorageError>;
+
+    /// Deletes the user's snapshots taken before `cutoff`
+    fn delete_snapshots_before(&mut self, user_id: &str, cutoff: DateTime<Utc>) -> Result<(), StorageError>;
+
+    /// Makes snapshot changes durable; `snapshots` is the complete current set
+    fn flush_snapshots(&mut self, snapshots: &[PortfolioSnapshot]) -> Result<(), StorageError>;
+
+    /// Backends that rewrite a whole file keep this many previous versions
+    fn keep_backups(&mut self, _count: usize) {}
+
+    /// Rewrites the API key of `user` to the one it holds now in every
+    /// older copy of the data the backend keeps, such as backups. `stale` is
+    /// the stored value being replaced, the plain-text key or an old
+    /// ciphertext, and is blanked in copies that cannot be parsed.
+    fn reseal_api_key(&mut self, _user: &User, _stale: &str) -> Result<(), StorageError> {
+        Ok(())
+    }
+}
@@ -0,0 +1,175 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use crate::db::{self, Database, LoadError, DB_FILE_NAME, DB_PATH_ENV};
+use crate::tests::temp_dir;
+
+#[test]
+fn test_resolve_path_precedence() {
+    let env = |name: &str| (name == DB_PATH_ENV).then(|| "/env/db.json".to_string());
+
+    assert_eq!(db::resolve_path(Some(Path::new("flag.json")), env), PathBuf::from("flag.json"));
+    assert_eq!(db::resolve_path(None, env), PathBuf::from("/env/db.json"));
+    assert_eq!(db::resolve_path(None, |_| None), db::default_path());
+    assert_eq!(db::resolve_path(None, |_| Some(String::new())), db::default_path());
+}
+
+#[test]
+fn test_default_path_is_not_relative_to_working_directory() {
+    let path = db::default_path();
+
+    assert!(path.ends_with(DB_FILE_NAME));
+    if dirs::data_dir().is_some() {
+        assert!(path.is_absolute());
+    }
+}
+
+#[test]
+fn test_load_missing_file() {
+    let dir = temp_dir("db_missing");
+
+    let result = Database::load(dir.join(DB_FILE_NAME));
+    assert!(matches!(result, Err(LoadError::Missing(path)) if path == dir.join(DB_FILE_NAME)));
+}
+
+#[test]
+fn test_load_corrupt_file_is_an_error() {
+    let dir = temp_dir("db_corrupt");
+    fs::create_dir_all(&dir).unwrap();
+    let path = dir.join(DB_FILE_NAME);
+    fs::write(&path, "{ not json").unwrap();
+
+    assert!(matches!(Database::load(&path), Err(LoadError::Corrupt { .. })));
+    assert_eq!(fs::read_to_string(&path).unwrap(), "{ not json");
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn test_save_creates_directory_and_reloads() {
+    let dir = temp_dir("db_save");
+    let path = dir.join("nested").join(DB_FILE_NAME);
+    let mut database = Database::at(&path);
+    let user_id = database.create_user("alice".to_string(), "key".to_string(), "passphrase").unwrap();
+
+    database.save().unwrap();
+    let loaded = Database::load(&path).unwrap();
+
+    assert_eq!(loaded.path(), path);
+    assert_eq!(loaded.get_user(&user_id).unwrap().name, "alice");
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+fn saved_names(path: &Path) -> Vec<String> {
+    let database = Database::load(path).unwrap();
+    database.users().map(|user| user.name.clone()).collect()
+}
+
+fn save_generations(path: &Path, names: &[&str], backups: usize) {
+    for name in names {
+        let mut database = Database::at(path).with_backups(backups);
+        database.create_user(name.to_string(), "key".to_string(), "passphrase").unwrap();
+        database.save().unwrap();
+    }
+}
+
+#[test]
+fn test_save_leaves_no_temporary_file() {
+    let dir = temp_dir("db_atomic");
+    let path = dir.join(DB_FILE_NAME);
+    save_generations(&path, &["alice"], 2);
+
+    let files: Vec<_> = fs::read_dir(&dir).unwrap()
+        .map(|entry| entry.unwrap().file_name().into_string().unwrap())
+        .collect();
+    assert_eq!(files, vec![DB_FILE_NAME.to_string()]);
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn test_save_rotates_backups() {
+    let dir = temp_dir("db_rotate");
+    let path = dir.join(DB_FILE_NAME);
+    save_generations(&path, &["v1", "v2", "v3", "v4"], 2);
+
+    assert_eq!(saved_names(&path), vec!["v4"]);
+    assert_eq!(saved_names(&db::backup_path(&path, 1)), vec!["v3"]);
+    assert_eq!(saved_names(&db::backup_path(&path, 2)), vec!["v2"]);
+    assert!(!db::backup_path(&path, 3).exists());
+
+    // Lowering the count drops the older backups on the next save
+    save_generations(&path, &["v5"], 1);
+    assert_eq!(saved_names(&db::backup_path(&path, 1)), vec!["v4"]);
+    assert!(!db::backup_path(&path, 2).exists());
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn test_load_recovers_from_newest_valid_backup() {
+    let dir = temp_dir("db_recover");
+    let path = dir.join(DB_FILE_NAME);
+    save_generations(&path, &["v1", "v2", "v3"], 3);
+    fs::write(&path, "{ truncated").unwrap();
+    fs::write(db::backup_path(&path, 1), "").unwrap();
+
+    let database = Database::load(&path).unwrap();
+
+    assert_eq!(database.recovered_from(), Some(db::backup_path(&path, 2).as_path()));
+    assert_eq!(database.path(), path);
+    assert_eq!(database.users().next().unwrap().name, "v1");
+    assert_eq!(fs::read_to_string(dir.join("database.json.corrupt")).unwrap(), "{ truncated");
+    assert!(!path.exists());
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn test_saving_after_recovery_restores_the_file() {
+    let dir = temp_dir("db_recover_save");
+    let path = dir.join(DB_FILE_NAME);
+    save_generations(&path, &["v1", "v2"], 3);
+    fs::write(&path, "{ truncated").unwrap();
+
+    let mut database = Database::load(&path).unwrap();
+    database.save().unwrap();
+
+    let reloaded = Database::load(&path).unwrap();
+    assert_eq!(reloaded.recovered_from(), None);
+    assert_eq!(reloaded.users().next().unwrap().name, "v1");
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn test_load_without_valid_backup_reports_corruption() {
+    let dir = temp_dir("db_unrecoverable");
+    fs::create_dir_all(&dir).unwrap();
+    let path = dir.join(DB_FILE_NAME);
+    fs::write(&path, "{ truncated").unwrap();
+    fs::write(db::backup_path(&path, 1), "also broken").unwrap();
+
+    assert!(matches!(Database::load(&path), Err(LoadError::Corrupt { .. })));
+    assert!(path.exists());
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn test_wallet_crud() {
+    let mut database = Database::at(temp_dir("db_wallets").join(DB_FILE_NAME));
+    let user_id = database.create_user("alice".to_string(), "key".to_string(), "passphrase").unwrap();
+    let wallet_id = database.add_wallet(&user_id, "Main".to_string(), vec!["addr1".to_string()]).unwrap();
+    let other_id = database.add_wallet(&user_id, "Cold".to_string(), vec![]).unwrap();
+
+    database.rename_wallet(&user_id, &wallet_id, "Daily".to_string()).unwrap();
+    database.add_address(&user_id, &wallet_id, "addr2".to_string()).unwrap();
+    database.remove_address(&user_id, &wallet_id, "addr1").unwrap();
+    database.delete_wallet(&user_id, &other_id).unwrap();
+
+    let wallets = &database.get_user(&user_id).unwrap().wallets;
+    assert_eq!(wallets.len(), 1);
+    assert_eq!(wallets[0].name, "Daily");
+    assert_eq!(wallets[0].addresses, vec!["addr2"]);
+
+    assert!(database.add_address(&user_id, &wallet_id, "addr2".to_string()).is_err());
+    assert!(database.remove_address(&user_id, &wallet_id, "addr1").is_err());
+    assert!(database.delete_wallet(&user_id, &other_id).is_err());
+    assert!(database.rename_wallet("nobody", &wallet_id, "x".to_string()).is_err());
+    // Failed changes leave the wallet as it was
+    assert_eq!(database.get_user(&user_id).unwrap().wallets[0].addresses, vec!["addr2"]);
+}
@@ -0,0 +1,139 @@
+use std::fs;
+use chrono::{Duration, TimeZone, Utc};
+use crate::db::history::{History, RetentionPolicy};
+use crate::models::portfolio_snapshot::{PortfolioSnapshot, PositionKind, PositionSnapshot};
+use crate::models::portfolio_summary::PortfolioSummary;
+use crate::tests::temp_dir;
+
+fn create_snapshot(user_id: &str, days_ago: i64, ada_value: f64) -> PortfolioSnapshot {
+    let now = Utc.with_ymd_and_hms(2026, 1, 31, 12, 0, 0).unwrap();
+    let summary = PortfolioSummary {
+        ada_value,
+        ..Default::default()
+    };
+    PortfolioSnapshot::from_summary(user_id, &summary, 0.5, now - Duration::days(days_ago))
+}
+
+#[test]
+fn test_record_keeps_snapshots_in_order_per_user() {
+    let mut history = History::new();
+    let retention = RetentionPolicy::default();
+
+    history.record(create_snapshot("alice", 2, 100.0), &retention).unwrap();
+    history.record(create_snapshot("bob", 1, 999.0), &retention).unwrap();
+    history.record(create_snapshot("alice", 0, 150.0), &retention).unwrap();
+
+    let values: Vec<f64> = history.snapshots_for("alice").map(|s| s.ada_value).collect();
+    assert_eq!(values, vec![100.0, 150.0]);
+    assert_eq!(history.snapshots_for("bob").count(), 1);
+}
+
+#[test]
+fn test_record_prunes_by_age() {
+    let mut history = History::new();
+    let retention = RetentionPolicy {
+        max_age_days: Some(7),
+        max_snapshots_per_user: None,
+    };
+
+    history.record(create_snapshot("alice", 30, 1.0), &retention).unwrap();
+    history.record(create_snapshot("bob", 30, 2.0), &retention).unwrap();
+    history.record(create_snapshot("alice", 0, 3.0), &retention).unwrap();
+
+    let values: Vec<f64> = history.snapshots_for("alice").map(|s| s.ada_value).collect();
+    assert_eq!(values, vec![3.0]);
+    // Other users are only pruned when they record their own snapshot
+    assert_eq!(history.snapshots_for("bob").count(), 1);
+}
+
+#[test]
+fn test_record_prunes_oldest_beyond_count() {
+    let mut history = History::new();
+    let retention = RetentionPolicy {
+        max_age_days: None,
+        max_snapshots_per_user: Some(2),
+    };
+
+    for (days_ago, value) in [(3, 1.0), (2, 2.0), (1, 3.0)] {
+        history.record(create_snapshot("alice", days_ago, value), &retention).unwrap();
+    }
+
+    let values: Vec<f64> = history.snapshots_for("alice").map(|s| s.ada_value).collect();
+    assert_eq!(values, vec![2.0, 3.0]);
+}
+
+#[test]
+fn test_snapshot_from_summary_includes_positions() {
+    let summary: PortfolioSummary = serde_json::from_str(r#"{
+        "adaBalance": 10.0,
+        "adaValue": 20.0,
+        "liquidValue": 20.0,
+        "numFTs": 1,
+        "numNFTs": 0,
+        "positionsFt": [{
+            "balance": 5.0,
+            "liquidBalance": 5.0,
+            "adaValue": 10.0,
+            "liquidValue": 10.0,
+            "price": 2.0,
+            "ticker": "TKN",
+            "unit": "unit_a",
+            "fingerprint": "asset1",
+            "24h": null,
+            "7d": null,
+            "30d": null
+        }],
+        "positionsNft": [],
+        "positionsLp": []
+    }"#).unwrap();
+
+    let snapshot = PortfolioSnapshot::from_summary("alice", &summary, 0.5, Utc::now());
+
+    assert_eq!(snapshot.usd_value(), 10.0);
+    assert_eq!(snapshot.positions.len(), 1);
+    assert_eq!(snapshot.positions[0].kind, PositionKind::Ft);
+    assert_eq!(snapshot.positions[0].price, Some(2.0));
+}
+
+#[test]
+fn test_history_persists_through_each_backend() {
+    let dir = temp_dir("history_storage");
+    let retention = RetentionPolicy {
+        max_age_days: None,
+        max_snapshots_per_user: Some(2),
+    };
+
+    for database in [dir.join("database.json"), dir.join("portfolio.sqlite")] {
+        let mut history = History::load(&database).unwrap();
+        for (days_ago, value) in [(3, 1.0), (2, 2.0), (1, 3.0)] {
+            history.record(create_snapshot("alice", days_ago, value), &retention).unwrap();
+        }
+        let mut snapshot = create_snapshot("bob", 0, 4.0);
+        snapshot.positions.push(PositionSnapshot {
+            kind: PositionKind::Lp,
+            unit: "lp_unit".to_string(),
+            ticker: "ADA/TKN".to_string(),
+            balance: 1.5,
+            price: None,
+            ada_value: 4.0,
+        });
+        history.record(snapshot, &retention).unwrap();
+        history.save().unwrap();
+        drop(history);
+
+        let history = History::load(&database).unwrap();
+        let values: Vec<f64> = history.snapshots_for("alice").map(|s| s.ada_value).collect();
+        assert_eq!(values, vec![2.0, 3.0], "{}", database.display());
+        let bob: Vec<_> = history.snapshots_for("bob").collect();
+        assert_eq!(bob.len(), 1);
+        assert_eq!(bob[0].positions[0].kind, PositionKind::Lp);
+        assert_eq!(bob[0].positions[0].price, None);
+        assert_eq!(bob[0].timestamp, create_snapshot("bob", 0, 4.0).timestamp);
+    }
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn test_history_without_storage_cannot_be_saved() {
+    assert!(History::new().save().is_err());
+}
@@ -0,0 +1,98 @@
+use std::fs;
+use serde_json::{json, Value};
+use crate::db::{Database, LoadError, DB_FILE_NAME};
+use crate::db::migrations::{migrate, version_of, MigrationError, CURRENT_VERSION};
+use crate::models::{cost_basis::CostBasisMethod, display_currency::DisplayCurrency};
+use crate::tests::temp_dir;
+
+// A database as written before the schema was versioned
+fn version_0() -> Value {
+    json!({
+        "users": {
+            "u1": {
+                "id": "u1",
+                "name": "alice",
+                "taptools_api_key": "legacy-key",
+                "wallets": [{ "id": "w1", "name": "Main", "addresses": ["addr1"] }]
+            },
+            "u2": {
+                "id": "u2",
+                "name": "bob",
+                "taptools_api_key": "other-key",
+                "wallets": [],
+                "display_currency": "usd"
+            }
+        }
+    })
+}
+
+#[test]
+fn test_unversioned_file_is_version_0() {
+    assert_eq!(version_of(&version_0()), 0);
+    assert_eq!(version_of(&json!({ "version": 3, "users": {} })), 3);
+}
+
+#[test]
+fn test_migration_0_to_1_backfills_user_fields() {
+    let mut document = version_0();
+
+    assert_eq!(migrate(&mut document).unwrap(), 0);
+
+    assert_eq!(document["version"], json!(CURRENT_VERSION));
+    let alice = &document["users"]["u1"];
+    assert_eq!(alice["display_currency"], json!("ada"));
+    assert_eq!(alice["cost_basis"], json!({ "method": "fifo", "lots": [] }));
+    assert_eq!(alice["ledger"], json!([]));
+    assert_eq!(alice["wallets"][0]["addresses"], json!(["addr1"]));
+    // Values that were already there are kept
+    assert_eq!(document["users"]["u2"]["display_currency"], json!("usd"));
+}
+
+#[test]
+fn test_migration_0_to_1_rejects_missing_users() {
+    let mut document = json!({ "accounts": {} });
+
+    assert_eq!(
+        migrate(&mut document),
+        Err(MigrationError::Failed { from: 0, error: "missing users".to_string() }),
+    );
+}
+
+#[test]
+fn test_current_version_is_left_alone() {
+    let mut document = json!({ "version": CURRENT_VERSION, "users": { "u1": { "id": "u1" } } });
+    let before = document.clone();
+
+    assert_eq!(migrate(&mut document).unwrap(), CURRENT_VERSION);
+    assert_eq!(document, before);
+}
+
+#[test]
+fn test_newer_version_is_rejected() {
+    let mut document = json!({ "version": CURRENT_VERSION + 1, "users": {} });
+
+    assert_eq!(migrate(&mut document), Err(MigrationError::TooNew(CURRENT_VERSION + 1)));
+}
+
+#[test]
+fn test_load_upgrades_old_file() {
+    let dir = temp_dir("db_migrate");
+    fs::create_dir_all(&dir).unwrap();
+    let path = dir.join(DB_FILE_NAME);
+    fs::write(&path, version_0().to_string()).unwrap();
+
+    let mut database = Database::load(&path).unwrap();
+    assert_eq!(database.migrated_from(), Some(0));
+    let bob = database.get_user("u2").unwrap();
+    assert_eq!(bob.display_currency, DisplayCurrency::Usd);
+    assert_eq!(bob.cost_basis.method, CostBasisMethod::Fifo);
+
+    database.save().unwrap();
+    let saved: Value = serde_json::from_str(&fs::read_to_string(&path).unwrap()).unwrap();
+    assert_eq!(saved["version"], json!(CURRENT_VERSION));
+    assert!(Database::load(&path).unwrap().migrated_from().is_none());
+
+    fs::write(&path, json!({ "version": CURRENT_VERSION + 1, "users": {} }).to_string()).unwrap();
+    assert!(matches!(Database::load(&path), Err(LoadError::Migration { .. })));
+    fs::remove_dir_all(&dir).unwrap();
+}
@@ -0,0 +1,10 @@
+#[cfg(test)]
+mod database_test;
+#[cfg(test)]
+mod history_test;
+#[cfg(test)]
+mod migrations_test;
+#[cfg(test)]
+mod secret_test;
+#[cfg(test)]
+mod storage_test;
@@ -0,0 +1,117 @@
+use std::fs;
+use crate::db::{self, Database, DB_FILE_NAME};
+use crate::db::secret::{EncryptedSecret, KdfParams, SecretError};
+use crate::tests::temp_dir;
+
+// Cheap parameters keep the tests fast; the defaults are exercised through Database
+const TEST_KDF: KdfParams = KdfParams { memory_kib: 64, iterations: 1, parallelism: 1 };
+
+#[test]
+fn test_seal_and_open_round_trip() {
+    let secret = EncryptedSecret::seal_with("api-key", "hunter2", TEST_KDF).unwrap();
+
+    assert!(!secret.ciphertext.contains("api-key"));
+    assert_eq!(secret.open("hunter2").unwrap(), "api-key");
+}
+
+#[test]
+fn test_open_with_wrong_passphrase() {
+    let secret = EncryptedSecret::seal_with("api-key", "hunter2", TEST_KDF).unwrap();
+
+    assert_eq!(secret.open("hunter3"), Err(SecretError::WrongPassphrase));
+}
+
+#[test]
+fn test_open_rejects_corrupt_nonce() {
+    let mut secret = EncryptedSecret::seal_with("api-key", "hunter2", TEST_KDF).unwrap();
+    secret.nonce = "AAAA".to_string();
+
+    assert!(matches!(secret.open("hunter2"), Err(SecretError::Corrupt(_))));
+}
+
+#[test]
+fn test_rotate_changes_passphrase() {
+    let secret = EncryptedSecret::seal_with("api-key", "old", TEST_KDF).unwrap();
+    let rotated = secret.rotate("old", "new").unwrap();
+
+    assert_ne!(rotated.salt, secret.salt);
+    assert_eq!(rotated.open("new").unwrap(), "api-key");
+    assert_eq!(rotated.open("old"), Err(SecretError::WrongPassphrase));
+    assert_eq!(secret.rotate("wrong", "new"), Err(SecretError::WrongPassphrase));
+}
+
+const LEGACY_FILE: &str = r#"{
+    "users": {
+        "u1": { "id": "u1", "name": "alice", "taptools_api_key": "legacy-key", "wallets": [] }
+    }
+}"#;
+
+#[test]
+fn test_plain_text_key_is_encrypted_on_migration() {
+    let dir = temp_dir("secret_migration");
+    fs::create_dir_all(&dir).unwrap();
+    let path = dir.join(DB_FILE_NAME);
+    fs::write(&path, LEGACY_FILE).unwrap();
+    let mut db = Database::load(&path).unwrap();
+
+    assert_eq!(db.unlock_api_key("u1", "anything").unwrap(), "legacy-key");
+    db.encrypt_api_key("u1", "passphrase").unwrap();
+
+    assert_eq!(db.unlock_api_key("u1", "passphrase").unwrap(), "legacy-key");
+    assert!(db.unlock_api_key("u1", "wrong").is_err());
+    assert!(db.encrypt_api_key("u1", "passphrase").is_err());
+
+    db.save().unwrap();
+    let json = fs::read_to_string(&path).unwrap();
+    assert!(!json.contains("legacy-key"));
+    assert!(!json.contains("taptools_api_key"));
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn test_migration_leaves_no_plain_text_copy() {
+    let dir = temp_dir("secret_copies");
+    fs::create_dir_all(&dir).unwrap();
+    let path = dir.join(DB_FILE_NAME);
+    let corrupt = dir.join("database.json.corrupt");
+    fs::write(&path, LEGACY_FILE).unwrap();
+    fs::write(db::backup_path(&path, 1), LEGACY_FILE).unwrap();
+    fs::write(&corrupt, r#"{ "users": { "u1": { "taptools_api_key": "legacy-key", "#).unwrap();
+
+    let mut db = Database::load(&path).unwrap();
+    db.encrypt_api_key("u1", "passphrase").unwrap();
+
+    // The file being replaced became the newest backup
+    for copy in [&path, &db::backup_path(&path, 1), &db::backup_path(&path, 2), &corrupt] {
+        assert!(!fs::read_to_string(copy).unwrap().contains("legacy-key"), "{}", copy.display());
+    }
+    // Backups still restore, now with the encrypted key
+    let backup = Database::load(db::backup_path(&path, 2)).unwrap();
+    assert_eq!(backup.unlock_api_key("u1", "passphrase").unwrap(), "legacy-key");
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn test_rotation_reseals_backups_instead_of_deleting_them() {
+    let dir = temp_dir("secret_rotation");
+    let path = dir.join(DB_FILE_NAME);
+    let corrupt = dir.join("database.json.corrupt");
+    let mut db = Database::at(&path).with_backups(3);
+    let user_id = db.create_user("alice".to_string(), "api-key".to_string(), "old").unwrap();
+    db.save().unwrap();
+    db.save().unwrap();
+    let old_ciphertext = db.get_user(&user_id).unwrap().encrypted_api_key.as_ref().unwrap().ciphertext.clone();
+    fs::write(&corrupt, format!(r#"{{ "ciphertext": "{}", "#, old_ciphertext)).unwrap();
+
+    db.rotate_passphrase(&user_id, "old", "new").unwrap();
+
+    // Every backup still restores, and only the new passphrase opens it
+    for n in 1..=2 {
+        let backup = Database::load(db::backup_path(&path, n)).unwrap();
+        assert_eq!(backup.unlock_api_key(&user_id, "new").unwrap(), "api-key");
+        assert!(backup.unlock_api_key(&user_id, "old").is_err());
+    }
+    assert!(!fs::read_to_string(&corrupt).unwrap().contains(&old_ciphertext));
+    assert!(db.rotate_passphrase(&user_id, "old", "newer").is_err());
+    fs::remove_dir_all(&dir).unwrap();
+}
@@ -0,0 +1,235 @@
+use std::collections::HashMap;
+use std::fs;
+use chrono::{NaiveDate, TimeZone, Utc};
+use crate::db::{Database, DB_FILE_NAME};
+use crate::db::history::{History, RetentionPolicy};
+use crate::db::json::JsonStorage;
+use crate::db::secret::{EncryptedSecret, KdfParams};
+use crate::db::sqlite::SqliteStorage;
+use crate::db::storage::{Storage, StorageError};
+use crate::models::cost_basis::{CostBasisBook, CostBasisMethod, Lot};
+use crate::models::display_currency::DisplayCurrency;
+use crate::models::ledger::{FiatAmount, LedgerEntry, LedgerSide};
+use crate::models::portfolio_snapshot::PortfolioSnapshot;
+use crate::models::portfolio_summary::PortfolioSummary;
+use crate::models::user::User;
+use crate::models::wallet::Wallet;
+use crate::services::price::QuoteCurrency;
+use crate::tests::temp_dir;
+
+fn wallet(id: &str, addresses: &[&str]) -> Wallet {
+    Wallet {
+        id: id.to_string(),
+        name: format!("Wallet {}", id),
+        addresses: addresses.iter().map(|a| a.to_string()).collect(),
+    }
+}
+
+fn lot(unit: &str, quantity: f64) -> Lot {
+    Lot {
+        unit: unit.to_string(),
+        acquired_on: NaiveDate::from_ymd_opt(2024, 3, 1).unwrap(),
+        quantity,
+        cost_ada: quantity * 2.0,
+    }
+}
+
+fn entry(day: u32, ticker: &str) -> LedgerEntry {
+    LedgerEntry {
+        timestamp: Utc.with_ymd_and_hms(2024, 1, day, 12, 0, 0).unwrap(),
+        wallet_id: Some("w1".to_string()),
+        side: LedgerSide::Acquire,
+        ticker: ticker.to_string(),
+        unit: Some(format!("{}_unit", ticker)),
+        quantity: 10.0,
+        ada_value: Some(5.0),
+        fiat_value: Some(FiatAmount { amount: 2.5, currency: QuoteCurrency::Eur }),
+        fee_ada: 0.17,
+        tx_hash: Some("abc".to_string()),
+        label: None,
+    }
+}
+
+fn user(id: &str) -> User {
+    let kdf = KdfParams { memory_kib: 64, iterations: 1, parallelism: 1 };
+    User {
+        id: id.to_string(),
+        name: format!("user {}", id),
+        taptools_api_key: String::new(),
+        encrypted_api_key: Some(EncryptedSecret::seal_with("key", "passphrase", kdf).unwrap()),
+        wallets: vec![wallet("w1", &["addr1", "addr2"]), wallet("w2", &[])],
+        display_currency: DisplayCurrency::Eur,
+        cost_basis: CostBasisBook { method: CostBasisMethod::Lifo, lots: vec![lot("tkn", 3.0)] },
+        ledger: vec![entry(2, "TKN")],
+    }
+}
+
+/// Users as JSON, sorted by id, so backends can be compared without `PartialEq`
+fn snapshot(storage: &dyn Storage) -> serde_json::Value {
+    let mut users = storage.users().unwrap();
+    users.sort_by(|a, b| a.id.cmp(&b.id));
+    serde_json::to_value(users).unwrap()
+}
+
+/// Runs the same operations against a backend and checks what it reads back
+fn exercise(storage: &mut dyn Storage) {
+    let users = [user("u1"), user("u2")];
+    storage.put_user(&users[0]).unwrap();
+    storage.put_user(&users[1]).unwrap();
+    assert_eq!(snapshot(storage), serde_json::to_value(&users).unwrap());
+
+    // Replacing a user replaces what they own
+    let mut renamed = users[0].clone();
+    renamed.name = "alice".to_string();
+    renamed.wallets.truncate(1);
+    renamed.ledger.clear();
+    storage.put_user(&renamed).unwrap();
+
+    storage.put_wallet("u1", &wallet("w1", &["addr3"])).unwrap();
+    storage.put_wallet("u1", &wallet("w3", &["addr4"])).unwrap();
+    storage.add_lots("u1", &[lot("other", 1.0)]).unwrap();
+    storage.add_ledger_entries("u1", &[entry(9, "LATE"), entry(1, "EARLY")]).unwrap();
+    storage.delete_user("u2").unwrap();
+
+    let users = storage.users().unwrap();
+    assert_eq!(users.len(), 1);
+    let alice = &users[0];
+    assert_eq!(alice.name, "alice");
+    assert_eq!(alice.encrypted_api_key.as_ref().unwrap().open("passphrase").unwrap(), "key");
+    let wallets: Vec<_> = alice.wallets.iter().map(|w| (w.id.as_str(), w.addresses.clone())).collect();
+    assert_eq!(wallets, vec![("w1", vec!["addr3".to_string()]), ("w3", vec!["addr4".to_string()])]);
+    assert_eq!(alice.cost_basis.method, CostBasisMethod::Lifo);
+    assert_eq!(alice.cost_basis.lots, vec![lot("tkn", 3.0), lot("other", 1.0)]);
+    assert_eq!(alice.ledger, vec![entry(1, "EARLY"), entry(9, "LATE")]);
+
+    storage.delete_wallet("u1", "w1").unwrap();
+    assert_eq!(storage.users().unwrap()[0].wallets.len(), 1);
+
+    assert!(matches!(storage.put_wallet("nobody", &wallet("w9", &[])), Err(StorageError::UserNotFound(_))));
+    assert!(matches!(storage.add_lots("nobody", &[]), Err(StorageError::UserNotFound(_))));
+}
+
+#[test]
+fn test_json_storage_writes_the_users_it_is_given() {
+    let dir = temp_dir("storage_json");
+    let path = dir.join(DB_FILE_NAME);
+    let mut storage = JsonStorage::new(&path);
+    assert!(storage.users().unwrap().is_empty());
+
+    let users = [user("u1"), user("u2")];
+    let by_id: HashMap<_, _> = users.iter().map(|user| (user.id.clone(), user.clone())).collect();
+    storage.flush(&by_id).unwrap();
+
+    let (reopened, mut read) = JsonStorage::open(&path).unwrap();
+    read.sort_by(|a, b| a.id.cmp(&b.id));
+    assert_eq!(serde_json::to_value(read).unwrap(), serde_json::to_value(&users).unwrap());
+    assert_eq!(snapshot(&reopened), snapshot(&storage));
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn test_json_database_persists_on_save() {
+    let dir = temp_dir("storage_json_database");
+    let path = dir.join(DB_FILE_NAME);
+
+    let mut database = Database::at(&path);
+    let user_id = database.create_user("alice".to_string(), "key".to_string(), "passphrase").unwrap();
+    database.add_wallet(&user_id, "Main".to_string(), vec!["addr1".to_string()]).unwrap();
+    database.import_ledger(&user_id, vec![entry(3, "TKN")]).unwrap();
+    database.save().unwrap();
+    database.set_display_currency(&user_id, DisplayCurrency::Gbp).unwrap();
+    drop(database);
+
+    // Only what was saved is in the file
+    let database = Database::load(&path).unwrap();
+    let alice = database.get_user(&user_id).unwrap();
+    assert_eq!(alice.wallets[0].addresses, vec!["addr1"]);
+    assert_eq!(alice.ledger.len(), 1);
+    assert_eq!(alice.display_currency, DisplayCurrency::default());
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn test_sqlite_storage() {
+    let mut storage = SqliteStorage::in_memory().unwrap();
+    exercise(&mut storage);
+}
+
+#[test]
+fn test_sqlite_database_persists_without_save() {
+    let dir = temp_dir("storage_sqlite");
+    let path = dir.join("portfolio.sqlite");
+
+    let mut database = Database::load(&path).unwrap();
+    let user_id = database.create_user("alice".to_string(), "key".to_string(), "passphrase").unwrap();
+    database.add_wallet(&user_id, "Main".to_string(), vec!["addr1".to_string()]).unwrap();
+    database.set_display_currency(&user_id, DisplayCurrency::Gbp).unwrap();
+    database.import_ledger(&user_id, vec![entry(3, "TKN"), entry(3, "TKN")]).unwrap();
+    drop(database);
+
+    let database = Database::load(&path).unwrap();
+    let alice = database.get_user(&user_id).unwrap();
+    assert_eq!(alice.wallets[0].addresses, vec!["addr1"]);
+    assert_eq!(alice.display_currency, DisplayCurrency::Gbp);
+    assert_eq!(alice.ledger.len(), 1);
+    assert_eq!(database.unlock_api_key(&user_id, "passphrase").unwrap(), "key");
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn test_copy_json_database_to_sqlite() {
+    let dir = temp_dir("storage_copy");
+    let mut database = Database::at(dir.join(DB_FILE_NAME));
+    database.create_user("alice".to_string(), "key".to_string(), "passphrase").unwrap();
+    database.create_user("bob".to_string(), "key".to_string(), "passphrase").unwrap();
+    database.save().unwrap();
+
+    let mut target = SqliteStorage::open(&dir.join("portfolio.db")).unwrap();
+    assert_eq!(database.copy_to(&mut target).unwrap(), 2);
+
+    let copied = Database::load(dir.join("portfolio.db")).unwrap();
+    let mut names: Vec<_> = copied.users().map(|user| user.name.as_str()).collect();
+    names.sort();
+    assert_eq!(names, vec!["alice", "bob"]);
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn test_copy_json_history_to_sqlite() {
+    let dir = temp_dir("storage_copy_history");
+    let mut history = History::load(&dir.join(DB_FILE_NAME)).unwrap();
+    for (user_id, ada_value) in [("u1", 1.0), ("u2", 2.0), ("u1", 3.0)] {
+        let summary = PortfolioSummary { ada_value, ..Default::default() };
+        let snapshot = PortfolioSnapshot::from_summary(user_id, &summary, 0.5, Utc::now());
+        history.record(snapshot, &RetentionPolicy::default()).unwrap();
+    }
+    history.save().unwrap();
+
+    let target = dir.join("portfolio.db");
+    let mut storage = SqliteStorage::open(&target).unwrap();
+    assert_eq!(history.copy_to(&mut storage).unwrap(), 3);
+
+    let copied = History::load(&target).unwrap();
+    let values: Vec<f64> = copied.snapshots_for("u1").map(|s| s.ada_value).collect();
+    assert_eq!(values, vec![1.0, 3.0]);
+    assert_eq!(copied.snapshots_for("u2").count(), 1);
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn test_sqlite_storage_adds_snapshot_tables_to_older_databases() {
+    let dir = temp_dir("storage_sqlite_upgrade");
+    let path = dir.join("portfolio.sqlite");
+    drop(SqliteStorage::open(&path).unwrap());
+    // Roll back to the first version, which had no snapshots
+    let connection = rusqlite::Connection::open(&path).unwrap();
+    connection.execute_batch("DROP TABLE snapshot_positions; DROP TABLE snapshots; PRAGMA user_version = 1;").unwrap();
+    drop(connection);
+
+    let mut storage = SqliteStorage::open(&path).unwrap();
+    assert!(storage.snapshots().unwrap().is_empty());
+    let snapshot = PortfolioSnapshot::from_summary("u1", &PortfolioSummary::default(), 0.5, Utc::now());
+    storage.add_snapshots(&[snapshot]).unwrap();
+    assert_eq!(storage.snapshots().unwrap().len(), 1);
+    fs::remove_dir_all(&dir).unwrap();
+}
@@ -0,0 +1,43 @@
+use std::io::Read;
+use serde::Deserialize;
+use crate::models::ledger::LedgerEntry;
+use super::{amount, non_empty, parse_timestamp, read_rows, ImportContext, ImportError, Movement};
+
+/// A row of the CoinTracking CSV layout with named amount columns
+#[derive(Debug, Deserialize)]
+struct CoinTrackingRow {
+    #[serde(rename = "Type")]
+    kind: String,
+    #[serde(rename = "Buy Amount")]
+    buy_amount: Option<f64>,
+    #[serde(rename = "Buy Currency")]
+    buy_currency: Option<String>,
+    #[serde(rename = "Sell Amount")]
+    sell_amount: Option<f64>,
+    #[serde(rename = "Sell Currency")]
+    sell_currency: Option<String>,
+    #[serde(rename = "Fee", default)]
+    fee: Option<f64>,
+    #[serde(rename = "Fee Currency", default)]
+    fee_currency: Option<String>,
+    #[serde(rename = "Date")]
+    date: String,
+    #[serde(rename = "Tx-ID", default)]
+    tx_id: Option<String>,
+}
+
+pub(super) fn read<R: Read>(reader: &mut csv::Reader<R>, context: &ImportContext) -> Result<Vec<LedgerEntry>, ImportError> {
+    read_rows(reader, |row: CoinTrackingRow, line| {
+        let movement = Movement {
+            timestamp: parse_timestamp(&row.date, line)?,
+            wallet_id: context.wallet_id(None)?,
+            sent: amount(row.sell_amount, row.sell_currency),
+            received: amount(row.buy_amount, row.buy_currency),
+            fee: amount(row.fee, row.fee_currency),
+            fiat_value: None,
+            tx_hash: non_empty(row.tx_id),
+            label: non_empty(Some(row.kind)),
+        };
+        Ok(movement.into_entries(context))
+    })
+}
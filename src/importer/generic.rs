@@ -0,0 +1,63 @@
+use std::io::Read;
+use serde::Deserialize;
+use crate::models::ledger::{LedgerEntry, LedgerSide};
+use super::{fiat_amount, non_empty, parse_timestamp, read_rows, ImportContext, ImportError};
+
+/// A row of our own layout:
+/// `date,type,ticker,unit,quantity,ada_value,fiat_value,fiat_currency,fee_ada,wallet,tx_hash,label`.
+/// Only `date`, `type`, `ticker` and `quantity` are required.
+#[derive(Debug, Deserialize)]
+struct GenericRow {
+    date: String,
+    #[serde(rename = "type")]
+    kind: String,
+    ticker: String,
+    #[serde(default)]
+    unit: Option<String>,
+    quantity: f64,
+    #[serde(default)]
+    ada_value: Option<f64>,
+    #[serde(default)]
+    fiat_value: Option<f64>,
+    #[serde(default)]
+    fiat_currency: Option<String>,
+    #[serde(default)]
+    fee_ada: Option<f64>,
+    #[serde(default)]
+    wallet: Option<String>,
+    #[serde(default)]
+    tx_hash: Option<String>,
+    #[serde(default)]
+    label: Option<String>,
+}
+
+fn side(kind: &str) -> Option<LedgerSide> {
+    match kind.to_lowercase().as_str() {
+        "buy" | "deposit" | "receive" | "income" => Some(LedgerSide::Acquire),
+        "sell" | "withdrawal" | "send" | "fee" => Some(LedgerSide::Dispose),
+        _ => None,
+    }
+}
+
+pub(super) fn read<R: Read>(reader: &mut csv::Reader<R>, context: &ImportContext) -> Result<Vec<LedgerEntry>, ImportError> {
+    read_rows(reader, |row: GenericRow, line| {
+        let side = side(&row.kind).ok_or_else(|| ImportError::Row {
+            line,
+            error: format!("Unknown type '{}'", row.kind),
+        })?;
+        let unit = non_empty(row.unit).or_else(|| context.unit_for(&row.ticker));
+        Ok(vec![LedgerEntry {
+            timestamp: parse_timestamp(&row.date, line)?,
+            wallet_id: context.wallet_id(row.wallet.as_deref())?,
+            side,
+            ticker: row.ticker,
+            unit,
+            quantity: row.quantity,
+            ada_value: row.ada_value,
+            fiat_value: fiat_amount(row.fiat_value, row.fiat_currency.as_deref()),
+            fee_ada: row.fee_ada.unwrap_or_default(),
+            tx_hash: non_empty(row.tx_hash),
+            label: non_empty(row.label),
+        }])
+    })
+}
@@ -0,0 +1,47 @@
+use std::io::Read;
+use serde::Deserialize;
+use crate::models::ledger::LedgerEntry;
+use super::{amount, fiat_amount, non_empty, parse_timestamp, read_rows, ImportContext, ImportError, Movement};
+
+/// A row of the Koinly universal CSV layout
+#[derive(Debug, Deserialize)]
+struct KoinlyRow {
+    #[serde(rename = "Date")]
+    date: String,
+    #[serde(rename = "Sent Amount")]
+    sent_amount: Option<f64>,
+    #[serde(rename = "Sent Currency")]
+    sent_currency: Option<String>,
+    #[serde(rename = "Received Amount")]
+    received_amount: Option<f64>,
+    #[serde(rename = "Received Currency")]
+    received_currency: Option<String>,
+    #[serde(rename = "Fee Amount", default)]
+    fee_amount: Option<f64>,
+    #[serde(rename = "Fee Currency", default)]
+    fee_currency: Option<String>,
+    #[serde(rename = "Net Worth Amount", default)]
+    net_worth_amount: Option<f64>,
+    #[serde(rename = "Net Worth Currency", default)]
+    net_worth_currency: Option<String>,
+    #[serde(rename = "Label", default)]
+    label: Option<String>,
+    #[serde(rename = "TxHash", default)]
+    tx_hash: Option<String>,
+}
+
+pub(super) fn read<R: Read>(reader: &mut csv::Reader<R>, context: &ImportContext) -> Result<Vec<LedgerEntry>, ImportError> {
+    read_rows(reader, |row: KoinlyRow, line| {
+        let movement = Movement {
+            timestamp: parse_timestamp(&row.date, line)?,
+            wallet_id: context.wallet_id(None)?,
+            sent: amount(row.sent_amount, row.sent_currency),
+            received: amount(row.received_amount, row.received_currency),
+            fee: amount(row.fee_amount, row.fee_currency),
+            fiat_value: fiat_amount(row.net_worth_amount, row.net_worth_currency.as_deref()),
+            tx_hash: non_empty(row.tx_hash),
+            label: non_empty(row.label),
+        };
+        Ok(movement.into_entries(context))
+    })
+}
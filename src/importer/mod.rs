@@ -0,0 +1,290 @@
+mod cointracking;
+mod generic;
+mod koinly;
+
+use std::collections::HashMap;
+use std::fmt;
+use std::io::Read;
+use std::str::FromStr;
+use chrono::{DateTime, NaiveDate, NaiveDateTime, Utc};
+use crate::models::ledger::{FiatAmount, LedgerEntry, LedgerSide, ADA_UNIT};
+use crate::models::wallet::Wallet;
+use crate::services::price::QuoteCurrency;
+
+/// Layout of a ledger CSV file
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LedgerFormat {
+    /// Our own layout: one asset movement per row
+    Generic,
+    /// Koinly universal export
+    Koinly,
+    /// CoinTracking CSV import/export layout
+    CoinTracking,
+}
+
+impl LedgerFormat {
+    /// Guesses the format from the header row
+    pub fn detect(headers: &csv::StringRecord) -> Option<Self> {
+        let has = |name: &str| headers.iter().any(|h| h.trim().eq_ignore_ascii_case(name));
+        if has("Sent Amount") && has("Received Amount") {
+            Some(LedgerFormat::Koinly)
+        } else if has("Buy Amount") && has("Sell Amount") {
+            Some(LedgerFormat::CoinTracking)
+        } else if has("ticker") && has("quantity") {
+            Some(LedgerFormat::Generic)
+        } else {
+            None
+        }
+    }
+}
+
+impl FromStr for LedgerFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "generic" => Ok(LedgerFormat::Generic),
+            "koinly" => Ok(LedgerFormat::Koinly),
+            "cointracking" => Ok(LedgerFormat::CoinTracking),
+            _ => Err(format!("Unknown ledger format '{}', expected generic, koinly or cointracking", s)),
+        }
+    }
+}
+
+impl fmt::Display for LedgerFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            LedgerFormat::Generic => "generic",
+            LedgerFormat::Koinly => "Koinly",
+            LedgerFormat::CoinTracking => "CoinTracking",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+#[derive(Debug)]
+pub enum ImportError {
+    Csv(csv::Error),
+    /// The header row matches none of the supported formats
+    UnknownFormat,
+    /// A row names a wallet the user does not have
+    UnknownWallet(String),
+    /// A row could not be turned into ledger entries
+    Row { line: u64, error: String },
+}
+
+impl fmt::Display for ImportError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ImportError::Csv(e) => write!(f, "Could not read CSV: {}", e),
+            ImportError::UnknownFormat => write!(f, "Unrecognised CSV header, pass the format explicitly"),
+            ImportError::UnknownWallet(name) => write!(f, "Unknown wallet '{}'", name),
+            ImportError::Row { line, error } => write!(f, "Line {}: {}", line, error),
+        }
+    }
+}
+
+impl std::error::Error for ImportError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            ImportError::Csv(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<csv::Error> for ImportError {
+    fn from(e: csv::Error) -> Self {
+        ImportError::Csv(e)
+    }
+}
+
+/// What the importer needs to link rows to the user's wallets and token units
+#[derive(Debug, Clone, Default)]
+pub struct ImportContext {
+    wallets: Vec<Wallet>,
+    default_wallet_id: Option<String>,
+    /// Token unit by upper-case ticker
+    units: HashMap<String, String>,
+}
+
+impl ImportContext {
+    /// `default_wallet_id` is used for rows that do not name a wallet
+    pub fn new(wallets: Vec<Wallet>, default_wallet_id: Option<String>) -> Self {
+        let mut units = HashMap::new();
+        units.insert("ADA".to_string(), ADA_UNIT.to_string());
+        Self { wallets, default_wallet_id, units }
+    }
+
+    /// Adds known ticker to unit mappings, e.g. from the latest snapshot
+    pub fn with_units<'a>(mut self, units: impl IntoIterator<Item = (&'a str, &'a str)>) -> Self {
+        for (ticker, unit) in units {
+            self.units.entry(ticker.to_uppercase()).or_insert_with(|| unit.to_string());
+        }
+        self
+    }
+
+    fn unit_for(&self, ticker: &str) -> Option<String> {
+        self.units.get(&ticker.trim().to_uppercase()).cloned()
+    }
+
+    /// Resolves a wallet by name or id, falling back to the default wallet
+    fn wallet_id(&self, wallet: Option<&str>) -> Result<Option<String>, ImportError> {
+        match wallet.map(str::trim).filter(|w| !w.is_empty()) {
+            Some(wallet) => self.wallets.iter()
+                .find(|w| w.name == wallet || w.id == wallet)
+                .map(|w| Some(w.id.clone()))
+                .ok_or_else(|| ImportError::UnknownWallet(wallet.to_string())),
+            None => Ok(self.default_wallet_id.clone()),
+        }
+    }
+}
+
+/// Reads a ledger CSV into entries, detecting the format from the header
+/// row unless one is given
+pub fn import_ledger<R: Read>(
+    reader: R,
+    format: Option<LedgerFormat>,
+    context: &ImportContext,
+) -> Result<Vec<LedgerEntry>, ImportError> {
+    let mut reader = csv::ReaderBuilder::new().trim(csv::Trim::All).from_reader(reader);
+    let format = match format {
+        Some(format) => format,
+        None => LedgerFormat::detect(reader.headers()?).ok_or(ImportError::UnknownFormat)?,
+    };
+
+    match format {
+        LedgerFormat::Generic => generic::read(&mut reader, context),
+        LedgerFormat::Koinly => koinly::read(&mut reader, context),
+        LedgerFormat::CoinTracking => cointracking::read(&mut reader, context),
+    }
+}
+
+/// Deserializes every row as `T` and converts it, passing along the line
+/// number for error messages
+fn read_rows<R, T>(
+    reader: &mut csv::Reader<R>,
+    mut convert: impl FnMut(T, u64) -> Result<Vec<LedgerEntry>, ImportError>,
+) -> Result<Vec<LedgerEntry>, ImportError>
+where
+    R: Read,
+    T: serde::de::DeserializeOwned,
+{
+    let headers = reader.headers()?.clone();
+    let mut entries = Vec::new();
+    for record in reader.records() {
+        let record = record?;
+        let line = record.position().map(|p| p.line()).unwrap_or_default();
+        let row: T = record.deserialize(Some(&headers))?;
+        entries.extend(convert(row, line)?);
+    }
+    Ok(entries)
+}
+
+/// A row of a tax tool export: something sent, something received and a fee
+struct Movement {
+    timestamp: DateTime<Utc>,
+    wallet_id: Option<String>,
+    sent: Option<(f64, String)>,
+    received: Option<(f64, String)>,
+    fee: Option<(f64, String)>,
+    fiat_value: Option<FiatAmount>,
+    tx_hash: Option<String>,
+    label: Option<String>,
+}
+
+impl Movement {
+    /// Splits the movement into one entry per asset. When one side of a
+    /// trade is ADA it becomes the ADA value of the other side; ADA fees
+    /// are attached to the token entry, other fees become disposals.
+    fn into_entries(self, context: &ImportContext) -> Vec<LedgerEntry> {
+        let is_ada = |ticker: &str| context.unit_for(ticker).as_deref() == Some(ADA_UNIT);
+        let ada_counter_value = [&self.sent, &self.received].into_iter()
+            .flatten()
+            .find(|(_, ticker)| is_ada(ticker))
+            .map(|(quantity, _)| *quantity);
+
+        let entry = |side: LedgerSide, quantity: f64, ticker: &str| {
+            let unit = context.unit_for(ticker);
+            let ada_value = if unit.as_deref() == Some(ADA_UNIT) { Some(quantity) } else { ada_counter_value };
+            LedgerEntry {
+                timestamp: self.timestamp,
+                wallet_id: self.wallet_id.clone(),
+                side,
+                ticker: ticker.to_string(),
+                unit,
+                quantity,
+                ada_value,
+                fiat_value: self.fiat_value,
+                fee_ada: 0.0,
+                tx_hash: self.tx_hash.clone(),
+                label: self.label.clone(),
+            }
+        };
+
+        let mut entries: Vec<LedgerEntry> = Vec::new();
+        if let Some((quantity, ticker)) = &self.sent {
+            entries.push(entry(LedgerSide::Dispose, *quantity, ticker));
+        }
+        if let Some((quantity, ticker)) = &self.received {
+            entries.push(entry(LedgerSide::Acquire, *quantity, ticker));
+        }
+
+        if let Some((fee, ticker)) = &self.fee {
+            let token_entry = entries.iter().position(|e| !e.is_ada()).or((!entries.is_empty()).then_some(0));
+            match token_entry {
+                Some(index) if is_ada(ticker) => entries[index].fee_ada += fee,
+                _ => {
+                    let mut fee_entry = entry(LedgerSide::Dispose, *fee, ticker);
+                    // The trade's values describe the traded assets, not the fee
+                    fee_entry.ada_value = is_ada(ticker).then_some(*fee);
+                    fee_entry.fiat_value = None;
+                    fee_entry.label = Some("fee".to_string());
+                    entries.push(fee_entry);
+                }
+            }
+        }
+        entries
+    }
+}
+
+/// Turns an optional amount and currency pair into a movement side
+fn amount(quantity: Option<f64>, ticker: Option<String>) -> Option<(f64, String)> {
+    match (quantity, ticker) {
+        (Some(quantity), Some(ticker)) if quantity != 0.0 && !ticker.is_empty() => Some((quantity.abs(), ticker)),
+        _ => None,
+    }
+}
+
+fn fiat_amount(amount: Option<f64>, currency: Option<&str>) -> Option<FiatAmount> {
+    let currency = QuoteCurrency::from_code(currency?)?;
+    Some(FiatAmount { amount: amount?, currency })
+}
+
+fn non_empty(value: Option<String>) -> Option<String> {
+    value.filter(|v| !v.is_empty())
+}
+
+/// Accepts RFC 3339 plus the date layouts used by Koinly and CoinTracking
+fn parse_timestamp(value: &str, line: u64) -> Result<DateTime<Utc>, ImportError> {
+    let value = value.trim();
+    if let Ok(timestamp) = DateTime::parse_from_rfc3339(value) {
+        return Ok(timestamp.with_timezone(&Utc));
+    }
+
+    let value = value.trim_end_matches(" UTC");
+    for format in ["%Y-%m-%d %H:%M:%S", "%Y-%m-%d %H:%M", "%d.%m.%Y %H:%M:%S", "%d.%m.%Y %H:%M"] {
+        if let Ok(timestamp) = NaiveDateTime::parse_from_str(value, format) {
+            return Ok(timestamp.and_utc());
+        }
+    }
+    for format in ["%Y-%m-%d", "%d.%m.%Y"] {
+        if let Ok(date) = NaiveDate::parse_from_str(value, format) {
+            return Ok(date.and_hms_opt(0, 0, 0).unwrap_or_default().and_utc());
+        }
+    }
+    Err(ImportError::Row { line, error: format!("Unrecognised date '{}'", value) })
+}
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,137 @@
+use crate::db::Database;
+use crate::importer::{import_ledger, ImportContext, ImportError, LedgerFormat};
+use crate::models::ledger::{LedgerSide, ADA_UNIT};
+use crate::models::wallet::Wallet;
+use crate::services::price::QuoteCurrency;
+
+const HOSKY_UNIT: &str = "a0028f350aaabe0545fdcb56b039bfb08e4bb4d8c4d7c3c7d481c235484f534b59";
+
+fn create_test_context() -> ImportContext {
+    let wallets = vec![
+        Wallet { id: "w1".to_string(), name: "Main".to_string(), addresses: vec![] },
+        Wallet { id: "w2".to_string(), name: "Cold".to_string(), addresses: vec![] },
+    ];
+    ImportContext::new(wallets, Some("w1".to_string())).with_units([("hosky", HOSKY_UNIT)])
+}
+
+#[test]
+fn test_koinly_trade_links_units_and_ada_value() {
+    let csv = "\
+Date,Sent Amount,Sent Currency,Received Amount,Received Currency,Fee Amount,Fee Currency,Net Worth Amount,Net Worth Currency,Label,Description,TxHash
+2024-01-05 14:00:00 UTC,100,ADA,1000000,HOSKY,0.2,ADA,55.5,USD,,swap,abc123
+";
+    let entries = import_ledger(csv.as_bytes(), None, &create_test_context()).unwrap();
+    assert_eq!(entries.len(), 2);
+
+    let ada = &entries[0];
+    assert_eq!(ada.side, LedgerSide::Dispose);
+    assert_eq!(ada.unit.as_deref(), Some(ADA_UNIT));
+    assert_eq!(ada.ada_value, Some(100.0));
+
+    let hosky = &entries[1];
+    assert_eq!(hosky.side, LedgerSide::Acquire);
+    assert_eq!(hosky.unit.as_deref(), Some(HOSKY_UNIT));
+    assert_eq!(hosky.quantity, 1_000_000.0);
+    assert_eq!(hosky.ada_value, Some(100.0));
+    assert_eq!(hosky.fee_ada, 0.2);
+    assert_eq!(hosky.fiat_value.map(|f| f.currency), Some(QuoteCurrency::Usd));
+    assert_eq!(hosky.wallet_id.as_deref(), Some("w1"));
+    assert_eq!(hosky.tx_hash.as_deref(), Some("abc123"));
+
+    // The ADA fee becomes part of the lot's cost
+    let lot = hosky.to_lot().unwrap();
+    assert_eq!(lot.cost_ada, 100.2);
+    assert!(ada.to_lot().is_none());
+}
+
+#[test]
+fn test_cointracking_sell_with_token_fee() {
+    let csv = "\
+Type,Buy Amount,Buy Currency,Sell Amount,Sell Currency,Fee,Fee Currency,Exchange,Trade-Group,Comment,Date,Tx-ID
+Trade,40,ADA,500000,HOSKY,10,HOSKY,Minswap,,,06.02.2024 09:30:00,
+";
+    let entries = import_ledger(csv.as_bytes(), None, &create_test_context()).unwrap();
+    assert_eq!(entries.len(), 3);
+    assert_eq!(entries[0].side, LedgerSide::Dispose);
+    assert_eq!(entries[0].ticker, "HOSKY");
+    assert_eq!(entries[0].ada_value, Some(40.0));
+    assert_eq!(entries[1].side, LedgerSide::Acquire);
+    assert_eq!(entries[1].unit.as_deref(), Some(ADA_UNIT));
+    assert_eq!(entries[2].label.as_deref(), Some("fee"));
+    assert_eq!(entries[2].quantity, 10.0);
+    assert_eq!(entries[0].timestamp.to_rfc3339(), "2024-02-06T09:30:00+00:00");
+}
+
+#[test]
+fn test_token_fee_is_not_valued_like_the_trade() {
+    let csv = "\
+Date,Sent Amount,Sent Currency,Received Amount,Received Currency,Fee Amount,Fee Currency,Net Worth Amount,Net Worth Currency,Label,Description,TxHash
+2024-01-05 14:00:00 UTC,100,ADA,1000000,HOSKY,5000,HOSKY,55.5,USD,,swap,abc123
+";
+    let entries = import_ledger(csv.as_bytes(), None, &create_test_context()).unwrap();
+    assert_eq!(entries.len(), 3);
+    assert_eq!(entries[1].ada_value, Some(100.0));
+
+    // The fee disposes of 5000 HOSKY, not of the 100 ADA the trade was worth
+    let fee = &entries[2];
+    assert_eq!(fee.label.as_deref(), Some("fee"));
+    assert_eq!(fee.side, LedgerSide::Dispose);
+    assert_eq!(fee.quantity, 5000.0);
+    assert_eq!(fee.ada_value, None);
+    assert_eq!(fee.fiat_value, None);
+}
+
+#[test]
+fn test_generic_rows_resolve_wallets_by_name() {
+    let csv = "\
+date,type,ticker,unit,quantity,ada_value,wallet
+2024-03-01,buy,SNEK,snekunit,250,25,Cold
+2024-03-02T10:00:00Z,sell,HOSKY,,10,1,
+";
+    let entries = import_ledger(csv.as_bytes(), Some(LedgerFormat::Generic), &create_test_context()).unwrap();
+    assert_eq!(entries[0].wallet_id.as_deref(), Some("w2"));
+    assert_eq!(entries[0].unit.as_deref(), Some("snekunit"));
+    assert_eq!(entries[1].wallet_id.as_deref(), Some("w1"));
+    assert_eq!(entries[1].unit.as_deref(), Some(HOSKY_UNIT));
+}
+
+#[test]
+fn test_unknown_wallet_and_bad_date_are_reported() {
+    let context = create_test_context();
+
+    let csv = "date,type,ticker,quantity,wallet\n2024-03-01,buy,SNEK,1,Nope\n";
+    assert!(matches!(
+        import_ledger(csv.as_bytes(), None, &context),
+        Err(ImportError::UnknownWallet(name)) if name == "Nope"
+    ));
+
+    let csv = "date,type,ticker,quantity\n2024-03-01,buy,SNEK,1\nyesterday,buy,SNEK,1\n";
+    assert!(matches!(
+        import_ledger(csv.as_bytes(), None, &context),
+        Err(ImportError::Row { line: 3, .. })
+    ));
+}
+
+#[test]
+fn test_unrecognised_header() {
+    let csv = "foo,bar\n1,2\n";
+    assert!(matches!(
+        import_ledger(csv.as_bytes(), None, &create_test_context()),
+        Err(ImportError::UnknownFormat)
+    ));
+}
+
+#[test]
+fn test_reimport_skips_existing_entries() {
+    let csv = "date,type,ticker,quantity,ada_value\n2024-03-01,buy,HOSKY,10,1\n";
+    let entries = import_ledger(csv.as_bytes(), None, &create_test_context()).unwrap();
+
+    let mut db = Database::new();
+    let user_id = db.create_user("alice".to_string(), "key".to_string(), "passphrase").unwrap();
+    assert_eq!(db.import_ledger(&user_id, entries.clone()), Ok(1));
+    assert_eq!(db.import_ledger(&user_id, entries), Ok(0));
+
+    let user = db.get_user(&user_id).unwrap();
+    assert_eq!(user.ledger.len(), 1);
+    assert_eq!(user.effective_cost_basis().lots_for(HOSKY_UNIT).len(), 1);
+}
@@ -2,4 +2,12 @@ pub mod ui;
 pub mod models;
 pub mod db;
 pub mod services;
-pub mod utils;
\ No newline at end of file
+pub mod utils;
+pub mod cli;
+pub mod commands;
+pub mod importer;
+pub mod reports;
+pub mod config;
+
+#[cfg(test)]
+mod tests;
\ No newline at end of file
@@ -1,91 +1,208 @@
-mod db;
-mod services;
-mod ui;
-mod utils;
-
+use std::path::Path;
 use std::sync::Arc;
 use tokio::sync::Mutex;
+use clap::Parser;
 use cardano_portfolio_summary::{
-    db::Database,
-    models,
-    services::{price::{fetch_ada_price, fetch_btc_price}, user_service::UserService},
+    cli::Cli,
+    commands,
+    config::{self, AppConfig, CONFIG_FILE_NAME},
+    db::{self, Database, LoadError, DB_FILE_NAME, history::History},
+    models::address::CardanoAddress,
+    services::user_service::UserService,
     ui::{App, run_app},
-    utils::{spinner::Spinner, ascii_art::render_landing_page},
+    utils::{
+        spinner::Spinner,
+        ascii_art::render_landing_page,
+        passphrase::{prompt_new_passphrase, read_passphrase},
+    },
 };
 
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn std::error::Error>> {
-    let database = Database::load().unwrap_or_else(|_| {
-        let db = Database::new();
-        db.save().expect("Failed to save new database");
-        db
-    });
-
-    let user_service = UserService::new(database);
-    let user_id = prompt_for_user_name(user_service.get_database()).await;
-    user_service.login(&user_id).await?;
+    let cli = Cli::parse();
+    let config_path = match &cli.config {
+        Some(path) => path.clone(),
+        None => {
+            let path = config::default_path();
+            if !path.exists() && Path::new(CONFIG_FILE_NAME).exists() && path != Path::new(CONFIG_FILE_NAME) {
+                eprintln!(
+                    "Note: {} in the current directory is no longer used by default. \
+                     Move it to {} or pass --config {}.",
+                    CONFIG_FILE_NAME, path.display(), CONFIG_FILE_NAME,
+                );
+            }
+            path
+        },
+    };
+    let config = AppConfig::load(&config_path)?;
+
+    let db_path = db::resolve_path(cli.database.as_deref(), |name| std::env::var(name).ok());
+    let database = match Database::load(&db_path) {
+        Ok(database) => database,
+        Err(LoadError::Missing(path)) => {
+            if Path::new(DB_FILE_NAME).exists() && path != Path::new(DB_FILE_NAME) {
+                eprintln!(
+                    "Note: {} in the current directory is no longer used by default. \
+                     Move it to {} or pass --database {}.",
+                    DB_FILE_NAME, path.display(), DB_FILE_NAME,
+                );
+            }
+            let mut database = Database::at(path);
+            database.save()?;
+            database
+        },
+        // Never replace a database that exists but cannot be read
+        Err(e) => return Err(e.into()),
+    };
+    let mut database = database.with_backups(config.database_backups);
+    if let Some(backup) = database.recovered_from() {
+        eprintln!(
+            "Warning: {} was missing or corrupt, restored it from {}. \
+             Changes made after that backup are lost.",
+            database.path().display(), backup.display(),
+        );
+    }
+    if let Some(version) = database.migrated_from() {
+        eprintln!("Upgraded {} from schema version {}.", database.path().display(), version);
+    }
+    // Saving a recovered database puts the restored data back in place, so
+    // the next start does not recover again, and saving an upgraded one
+    // keeps the file as it was before the upgrade in the backups
+    if database.recovered_from().is_some() || database.migrated_from().is_some() {
+        database.save()?;
+    }
+
+    let retention = config.history_retention;
+    let history = History::load(database.path())?;
+    let user_service = UserService::with_config(database, config.clone(), cli.fixture_mode())
+        .with_history(history, retention);
+
+    if let Some(command) = cli.command {
+        let context = commands::Context {
+            user: cli.user.as_deref(),
+            wallet: cli.wallet.as_deref(),
+            format: cli.format,
+            user_service: &user_service,
+            config: &config,
+        };
+        return commands::run(command, &context).await;
+    }
+
+    let (user_id, new_passphrase) = match &cli.user {
+        Some(name) => {
+            let user_id = user_service.get_database().lock().await
+                .get_user_by_name(name)
+                .map(|user| user.id.clone())
+                .ok_or_else(|| format!("User '{}' not found", name))?;
+            (user_id, None)
+        },
+        None => prompt_for_user_name(user_service.get_database()).await?,
+    };
+    let passphrase = match new_passphrase {
+        Some(passphrase) => passphrase,
+        None => prompt_for_passphrase(user_service.get_database(), &user_id).await?,
+    };
+    user_service.login(&user_id, &passphrase).await?;
     let user = user_service.get_current_user().await
-        .expect("User should be logged in")
-        .clone();
+        .ok_or("Not logged in")?;
 
     render_landing_page();
-    let (portfolio_data, ada_price, btc_price) = Spinner::spin_while(
+    let (portfolio_data, prices) = Spinner::spin_while(
         "Loading portfolio data...",
         async {
             let portfolio = user_service.fetch_portfolio_data().await?;
-            let ada_price = fetch_ada_price().await?;
-            let btc_price = fetch_btc_price().await?;
-            Ok::<_, Box<dyn std::error::Error>>((portfolio, ada_price, btc_price))
+            let prices = user_service.fetch_prices().await?;
+            Ok::<_, Box<dyn std::error::Error>>((portfolio, prices))
         }
     ).await?;
+    if let Err(e) = user_service.record_snapshot(&portfolio_data, &prices).await {
+        eprintln!("Failed to record portfolio snapshot: {}", e);
+    }
 
-    let mut app = App::new(portfolio_data, user, user_service, ada_price, btc_price);
+    let mut app = App::new(portfolio_data, user, user_service, prices)
+        .with_export_dir(config.export_dir());
+    app.load_users().await;
     run_app(&mut app).await?;
 
     Ok(())
 }
 
-async fn prompt_for_user_name(database: Arc<Mutex<Database>>) -> String {
+/// Returns the id of the named user, creating them if needed. For a new
+/// user the passphrase chosen for their API key is returned as well.
+async fn prompt_for_user_name(database: Arc<Mutex<Database>>) -> Result<(String, Option<String>), Box<dyn std::error::Error>> {
     println!("Enter your name:");
-    let mut name = String::new();
-    std::io::stdin().read_line(&mut name).unwrap();
-    let name = name.trim().to_string();
+    let name = read_answer()?;
 
     let db = database.lock().await;
     if let Some(user) = db.get_user_by_name(&name) {
-        user.id.clone()
+        Ok((user.id.clone(), None))
     } else {
         println!("User not found. Creating a new user.");
-        let api_key = prompt_for_api_key();
+        let api_key = prompt_for_api_key()?;
         drop(db);
-        let wallet_info = prompt_for_wallet();
+        let passphrase = prompt_new_passphrase("Choose a passphrase to encrypt your API key:")?;
+        let wallet_info = prompt_for_wallet()?;
         
         let mut db = database.lock().await;
-        let user_id = db.create_user(name, api_key);
-        db.add_wallet(&user_id, wallet_info.0, vec![wallet_info.1])
-            .expect("Failed to add wallet");
-        db.save().expect("Failed to save database");
-        user_id
+        let user_id = db.create_user(name, api_key, &passphrase)?;
+        db.add_wallet(&user_id, wallet_info.0, vec![wallet_info.1])?;
+        db.save()?;
+        Ok((user_id, Some(passphrase)))
     }
 }
 
-fn prompt_for_api_key() -> String {
+/// Asks for the passphrase that unlocks the user's API key. A key still
+/// stored in plain text is encrypted first, under a new passphrase that has
+/// to be entered twice.
+async fn prompt_for_passphrase(database: Arc<Mutex<Database>>, user_id: &str) -> Result<String, Box<dyn std::error::Error>> {
+    let mut db = database.lock().await;
+    let encrypted = db.get_user(user_id)
+        .is_some_and(|user| user.encrypted_api_key.is_some());
+    if encrypted {
+        return Ok(read_passphrase("Enter your passphrase:")?);
+    }
+
+    let passphrase = prompt_new_passphrase("Your API key is stored unencrypted. Choose a passphrase to encrypt it:")?;
+    db.encrypt_api_key(user_id, &passphrase)?;
+    println!("API key encrypted.");
+    Ok(passphrase)
+}
+
+/// Reads one line from stdin without its line ending. Stdin closing is an
+/// error rather than an empty answer, so prompts that repeat until the
+/// answer is valid cannot loop forever.
+fn read_answer() -> std::io::Result<String> {
+    let mut line = String::new();
+    if std::io::stdin().read_line(&mut line)? == 0 {
+        return Err(std::io::Error::new(std::io::ErrorKind::UnexpectedEof, "Input ended before an answer was given"));
+    }
+    Ok(line.trim().to_string())
+}
+
+fn prompt_for_api_key() -> std::io::Result<String> {
     println!("Enter your Taptools API key:");
-    let mut api_key = String::new();
-    std::io::stdin().read_line(&mut api_key).unwrap();
-    api_key.trim().to_string()
+    read_answer()
 }
 
-fn prompt_for_wallet() -> (String, String) {
+fn prompt_for_wallet() -> std::io::Result<(String, String)> {
     println!("Enter your wallet name:");
-    let mut wallet_name = String::new();
-    std::io::stdin().read_line(&mut wallet_name).unwrap();
-    let wallet_name = wallet_name.trim().to_string();
+    let wallet_name = read_answer()?;
+
+    let address = loop {
+        println!("Enter your wallet address (addr1... or stake1...):");
+        match CardanoAddress::parse(&read_answer()?) {
+            Ok(address) => break address,
+            Err(e) => println!("{}", e),
+        }
+    };
 
-    println!("Enter your wallet address:");
-    let mut wallet_address = String::new();
-    std::io::stdin().read_line(&mut wallet_address).unwrap();
-    let wallet_address = wallet_address.trim().to_string();
+    // Tracking the stake address covers every address the wallet derives
+    if let Some(stake_address) = address.stake_address().filter(|_| !address.is_stake()) {
+        println!("Track every address of its stake key {} instead? [y/N]", stake_address);
+        if read_answer()?.eq_ignore_ascii_case("y") {
+            return Ok((wallet_name, stake_address.to_string()));
+        }
+    }
 
-    (wallet_name, wallet_address)
+    Ok((wallet_name, address.to_string()))
 }
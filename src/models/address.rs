@@ -0,0 +1,173 @@
+use std::fmt;
+use std::str::FromStr;
+use bech32::{Bech32, Hrp};
+
+/// Length of a key or script hash in an address (blake2b-224)
+const CREDENTIAL_LENGTH: usize = 28;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Network {
+    Mainnet,
+    Testnet,
+}
+
+impl Network {
+    fn from_id(id: u8) -> Self {
+        if id == 1 { Network::Mainnet } else { Network::Testnet }
+    }
+
+    fn id(&self) -> u8 {
+        match self {
+            Network::Mainnet => 1,
+            Network::Testnet => 0,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum AddressError {
+    Empty,
+    /// Legacy base58 addresses, which the portfolio API cannot look up
+    Byron,
+    /// A prefix other than `addr`, `addr_test`, `stake` or `stake_test`
+    UnknownPrefix(String),
+    /// Not bech32 at all, or the checksum does not match
+    Bech32(String),
+    /// Valid bech32 whose payload is not a Cardano address
+    Malformed(String),
+    /// The prefix names a different network than the address header
+    NetworkMismatch,
+}
+
+impl fmt::Display for AddressError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AddressError::Empty => write!(f, "Address is empty"),
+            AddressError::Byron => write!(f, "Byron addresses are not supported; use a Shelley address starting with addr1"),
+            AddressError::UnknownPrefix(prefix) => write!(
+                f,
+                "Unknown address prefix '{}'; expected addr1, addr_test1, stake1 or stake_test1",
+                prefix,
+            ),
+            AddressError::Bech32(e) => write!(f, "Not a valid bech32 address ({}); check for typos or missing characters", e),
+            AddressError::Malformed(e) => write!(f, "Not a valid Cardano address: {}", e),
+            AddressError::NetworkMismatch => write!(f, "Address prefix does not match the network in the address"),
+        }
+    }
+}
+
+impl std::error::Error for AddressError {}
+
+/// A Shelley payment or stake address, checked for a valid bech32 checksum,
+/// a known prefix and a payload of the right shape
+#[derive(Debug, Clone, PartialEq)]
+pub struct CardanoAddress {
+    /// Lower-case bech32 form
+    encoded: String,
+    network: Network,
+    /// Header byte: address type in the high nibble, network id in the low one
+    header: u8,
+    payload: Vec<u8>,
+}
+
+impl CardanoAddress {
+    pub fn parse(input: &str) -> Result<Self, AddressError> {
+        let input = input.trim();
+        if input.is_empty() {
+            return Err(AddressError::Empty);
+        }
+        if input.starts_with("Ae2") || input.starts_with("DdzFF") {
+            return Err(AddressError::Byron);
+        }
+        // Checked before decoding so a typo in the prefix is reported as such
+        let prefix = input.rsplit_once('1').map_or(input, |(prefix, _)| prefix).to_lowercase();
+        let (is_stake, network) = match prefix.as_str() {
+            "addr" => (false, Network::Mainnet),
+            "addr_test" => (false, Network::Testnet),
+            "stake" => (true, Network::Mainnet),
+            "stake_test" => (true, Network::Testnet),
+            _ => return Err(AddressError::UnknownPrefix(prefix)),
+        };
+
+        let (_, payload) = bech32::decode(input).map_err(|e| AddressError::Bech32(e.to_string()))?;
+        let header = *payload.first().ok_or_else(|| AddressError::Malformed("no header".to_string()))?;
+        let address_type = header >> 4;
+        let expected_length = match address_type {
+            // Base: payment and stake credential
+            0..=3 => Some(1 + 2 * CREDENTIAL_LENGTH),
+            // Pointer: payment credential and a variable-length pointer
+            4 | 5 => None,
+            // Enterprise and reward (stake) addresses: a single credential
+            6 | 7 | 14 | 15 => Some(1 + CREDENTIAL_LENGTH),
+            _ => return Err(AddressError::Malformed(format!("unsupported address type {}", address_type))),
+        };
+        let length_ok = match expected_length {
+            Some(length) => payload.len() == length,
+            None => payload.len() > 1 + CREDENTIAL_LENGTH,
+        };
+        if !length_ok {
+            return Err(AddressError::Malformed(format!("unexpected length of {} bytes", payload.len())));
+        }
+        if is_stake != (address_type >= 14) {
+            let expected = if is_stake { "a stake" } else { "a payment" };
+            return Err(AddressError::Malformed(format!("the prefix says {} address but the content does not", expected)));
+        }
+        if Network::from_id(header & 0x0f) != network {
+            return Err(AddressError::NetworkMismatch);
+        }
+
+        Ok(Self { encoded: input.to_lowercase(), network, header, payload })
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.encoded
+    }
+
+    pub fn network(&self) -> Network {
+        self.network
+    }
+
+    pub fn is_stake(&self) -> bool {
+        self.header >> 4 >= 14
+    }
+
+    /// The stake address this address delegates through: itself for a stake
+    /// address, the one built from the stake credential of a base address,
+    /// and `None` for enterprise and pointer addresses
+    pub fn stake_address(&self) -> Option<CardanoAddress> {
+        let address_type = self.header >> 4;
+        if self.is_stake() {
+            return Some(self.clone());
+        }
+        if address_type > 3 {
+            return None;
+        }
+        // Types 2 and 3 have a script as stake credential
+        let stake_type: u8 = if address_type >= 2 { 15 } else { 14 };
+        let header = (stake_type << 4) | self.network.id();
+        let mut payload = vec![header];
+        payload.extend_from_slice(&self.payload[1 + CREDENTIAL_LENGTH..]);
+
+        let hrp = match self.network {
+            Network::Mainnet => "stake",
+            Network::Testnet => "stake_test",
+        };
+        let hrp = Hrp::parse(hrp).expect("valid human-readable part");
+        let encoded = bech32::encode::<Bech32>(hrp, &payload).expect("stake addresses fit in bech32");
+        Some(Self { encoded, network: self.network, header, payload })
+    }
+}
+
+impl FromStr for CardanoAddress {
+    type Err = AddressError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::parse(s)
+    }
+}
+
+impl fmt::Display for CardanoAddress {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.encoded)
+    }
+}
@@ -0,0 +1,129 @@
+use std::fmt;
+use std::str::FromStr;
+use chrono::NaiveDate;
+use serde::{Deserialize, Serialize};
+
+/// Which acquisitions are considered still held when the current balance
+/// is smaller than the total quantity ever acquired
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CostBasisMethod {
+    /// Oldest lots were sold first, so the newest lots are still held
+    #[default]
+    Fifo,
+    /// Newest lots were sold first, so the oldest lots are still held
+    Lifo,
+    /// Every unit carries the average cost of all lots
+    AverageCost,
+}
+
+impl FromStr for CostBasisMethod {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "fifo" => Ok(CostBasisMethod::Fifo),
+            "lifo" => Ok(CostBasisMethod::Lifo),
+            "average" | "average-cost" | "average_cost" => Ok(CostBasisMethod::AverageCost),
+            _ => Err(format!("Unknown cost basis method '{}', expected fifo, lifo or average", s)),
+        }
+    }
+}
+
+impl fmt::Display for CostBasisMethod {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            CostBasisMethod::Fifo => "FIFO",
+            CostBasisMethod::Lifo => "LIFO",
+            CostBasisMethod::AverageCost => "Average cost",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+/// A single acquisition of a token
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Lot {
+    pub unit: String,
+    pub acquired_on: NaiveDate,
+    pub quantity: f64,
+    /// Total paid for the lot, in ADA
+    pub cost_ada: f64,
+}
+
+/// Portion of the current balance that is covered by recorded lots
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct HeldCost {
+    pub quantity: f64,
+    pub cost_ada: f64,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct CostBasisBook {
+    pub method: CostBasisMethod,
+    pub lots: Vec<Lot>,
+}
+
+impl CostBasisBook {
+    pub fn add_lot(&mut self, lot: Lot) {
+        self.lots.push(lot);
+    }
+
+    /// Lots of `unit`, oldest first
+    pub fn lots_for(&self, unit: &str) -> Vec<&Lot> {
+        let mut lots: Vec<&Lot> = self.lots.iter().filter(|lot| lot.unit == unit).collect();
+        lots.sort_by_key(|lot| lot.acquired_on);
+        lots
+    }
+
+    /// Cost of the `balance` units still held, according to `method`.
+    /// Returns `None` when no lots are recorded for `unit`.
+    pub fn held_cost(&self, unit: &str, balance: f64) -> Option<HeldCost> {
+        let lots = self.lots_for(unit);
+        if lots.is_empty() {
+            return None;
+        }
+
+        let total_quantity: f64 = lots.iter().map(|lot| lot.quantity).sum();
+        let held = balance.min(total_quantity).max(0.0);
+
+        let cost_ada = match self.method {
+            CostBasisMethod::AverageCost => {
+                let total_cost: f64 = lots.iter().map(|lot| lot.cost_ada).sum();
+                if total_quantity > 0.0 { total_cost / total_quantity * held } else { 0.0 }
+            },
+            CostBasisMethod::Fifo => take_cost(lots.iter().rev().copied(), held),
+            CostBasisMethod::Lifo => take_cost(lots.iter().copied(), held),
+        };
+
+        Some(HeldCost { quantity: held, cost_ada })
+    }
+
+    /// Unrealized gain in ADA on the covered part of the balance, valued at
+    /// the position's current ADA value
+    pub fn unrealized_pnl(&self, unit: &str, balance: f64, ada_value: f64) -> Option<f64> {
+        let held = self.held_cost(unit, balance)?;
+        if balance <= 0.0 {
+            return Some(0.0);
+        }
+        let value_per_unit = ada_value / balance;
+        Some(value_per_unit * held.quantity - held.cost_ada)
+    }
+}
+
+/// Sums the cost of `quantity` units taken from `lots` in iteration order
+fn take_cost<'a>(lots: impl Iterator<Item = &'a Lot>, quantity: f64) -> f64 {
+    let mut remaining = quantity;
+    let mut cost = 0.0;
+    for lot in lots {
+        if remaining <= 0.0 {
+            break;
+        }
+        let taken = remaining.min(lot.quantity);
+        if lot.quantity > 0.0 {
+            cost += lot.cost_ada * taken / lot.quantity;
+        }
+        remaining -= taken;
+    }
+    cost
+}
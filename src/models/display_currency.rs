@@ -0,0 +1,69 @@
+use std::fmt;
+use serde::{Deserialize, Serialize};
+use crate::services::price::QuoteCurrency;
+
+/// Currency that value columns are rendered in
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DisplayCurrency {
+    #[default]
+    Ada,
+    Usd,
+    Eur,
+    Gbp,
+    Jpy,
+    Btc,
+}
+
+impl DisplayCurrency {
+    pub const ALL: [DisplayCurrency; 6] = [
+        DisplayCurrency::Ada,
+        DisplayCurrency::Usd,
+        DisplayCurrency::Eur,
+        DisplayCurrency::Gbp,
+        DisplayCurrency::Jpy,
+        DisplayCurrency::Btc,
+    ];
+
+    /// Quote currency to convert ADA amounts with, `None` for ADA itself
+    pub fn quote_currency(&self) -> Option<QuoteCurrency> {
+        match self {
+            DisplayCurrency::Ada => None,
+            DisplayCurrency::Usd => Some(QuoteCurrency::Usd),
+            DisplayCurrency::Eur => Some(QuoteCurrency::Eur),
+            DisplayCurrency::Gbp => Some(QuoteCurrency::Gbp),
+            DisplayCurrency::Jpy => Some(QuoteCurrency::Jpy),
+            DisplayCurrency::Btc => Some(QuoteCurrency::Btc),
+        }
+    }
+
+    pub fn symbol(&self) -> &'static str {
+        match self {
+            DisplayCurrency::Ada => "₳",
+            DisplayCurrency::Usd => "$",
+            DisplayCurrency::Eur => "€",
+            DisplayCurrency::Gbp => "£",
+            DisplayCurrency::Jpy => "¥",
+            DisplayCurrency::Btc => "₿",
+        }
+    }
+
+    pub fn next(&self) -> DisplayCurrency {
+        let index = Self::ALL.iter().position(|c| c == self).unwrap_or(0);
+        Self::ALL[(index + 1) % Self::ALL.len()]
+    }
+}
+
+impl fmt::Display for DisplayCurrency {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let code = match self {
+            DisplayCurrency::Ada => "ADA",
+            DisplayCurrency::Usd => "USD",
+            DisplayCurrency::Eur => "EUR",
+            DisplayCurrency::Gbp => "GBP",
+            DisplayCurrency::Jpy => "JPY",
+            DisplayCurrency::Btc => "BTC",
+        };
+        write!(f, "{}", code)
+    }
+}
@@ -0,0 +1,63 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use crate::models::cost_basis::Lot;
+use crate::services::price::QuoteCurrency;
+
+/// Unit Taptools uses for ADA itself
+pub const ADA_UNIT: &str = "lovelace";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LedgerSide {
+    /// Tokens came into the wallet: a buy, deposit or income
+    Acquire,
+    /// Tokens left the wallet: a sell, withdrawal or fee
+    Dispose,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct FiatAmount {
+    pub amount: f64,
+    pub currency: QuoteCurrency,
+}
+
+/// One movement of a single asset
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LedgerEntry {
+    pub timestamp: DateTime<Utc>,
+    /// Wallet the movement belongs to, when known
+    pub wallet_id: Option<String>,
+    pub side: LedgerSide,
+    pub ticker: String,
+    /// Token unit, `None` when the ticker could not be matched to one
+    pub unit: Option<String>,
+    pub quantity: f64,
+    /// What the movement was worth in ADA, e.g. the ADA paid in a buy
+    pub ada_value: Option<f64>,
+    /// What the movement was worth in fiat at the time, as reported by the source
+    pub fiat_value: Option<FiatAmount>,
+    /// Network or exchange fee paid in ADA for this movement
+    #[serde(default)]
+    pub fee_ada: f64,
+    pub tx_hash: Option<String>,
+    pub label: Option<String>,
+}
+
+impl LedgerEntry {
+    pub fn is_ada(&self) -> bool {
+        self.unit.as_deref() == Some(ADA_UNIT)
+    }
+
+    /// Cost basis lot for an acquisition of a token with a known ADA cost
+    pub fn to_lot(&self) -> Option<Lot> {
+        if self.side != LedgerSide::Acquire || self.is_ada() {
+            return None;
+        }
+        Some(Lot {
+            unit: self.unit.clone()?,
+            acquired_on: self.timestamp.date_naive(),
+            quantity: self.quantity,
+            cost_ada: self.ada_value? + self.fee_ada,
+        })
+    }
+}
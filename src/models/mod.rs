@@ -4,4 +4,13 @@ pub mod lp_position;
 pub mod nft_position;
 pub mod user;
 pub mod wallet;
-pub mod market_cap_token;
\ No newline at end of file
+pub mod market_cap_token;
+pub mod user_portfolio;
+pub mod display_currency;
+pub mod portfolio_snapshot;
+pub mod cost_basis;
+pub mod ledger;
+pub mod address;
+
+#[cfg(test)]
+mod tests;
\ No newline at end of file
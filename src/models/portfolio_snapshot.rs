@@ -0,0 +1,97 @@
+use std::str::FromStr;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use super::portfolio_summary::PortfolioSummary;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PositionKind {
+    Ft,
+    Nft,
+    Lp,
+}
+
+impl FromStr for PositionKind {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "ft" => Ok(PositionKind::Ft),
+            "nft" => Ok(PositionKind::Nft),
+            "lp" => Ok(PositionKind::Lp),
+            _ => Err(format!("Unknown position type '{}', expected ft, nft or lp", s)),
+        }
+    }
+}
+
+/// One position at the time of a snapshot. `unit` is the token unit for
+/// FTs and LPs and the policy id for NFT collections.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PositionSnapshot {
+    pub kind: PositionKind,
+    pub unit: String,
+    pub ticker: String,
+    pub balance: f64,
+    pub price: Option<f64>,
+    pub ada_value: f64,
+}
+
+/// Portfolio totals and positions of a user at a point in time
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PortfolioSnapshot {
+    pub timestamp: DateTime<Utc>,
+    pub user_id: String,
+    pub ada_balance: f64,
+    pub ada_value: f64,
+    pub liquid_value: f64,
+    pub ada_usd_price: f64,
+    pub positions: Vec<PositionSnapshot>,
+}
+
+impl PortfolioSnapshot {
+    pub fn from_summary(
+        user_id: &str,
+        summary: &PortfolioSummary,
+        ada_usd_price: f64,
+        timestamp: DateTime<Utc>,
+    ) -> Self {
+        let ft = summary.positions_ft.iter().map(|p| PositionSnapshot {
+            kind: PositionKind::Ft,
+            unit: p.unit.clone(),
+            ticker: p.ticker.clone(),
+            balance: p.balance,
+            price: p.price,
+            ada_value: p.ada_value,
+        });
+        let nft = summary.positions_nft.iter().map(|p| PositionSnapshot {
+            kind: PositionKind::Nft,
+            unit: p.policy.clone(),
+            ticker: p.name.clone(),
+            balance: p.balance as f64,
+            price: Some(p.floor_price),
+            ada_value: p.ada_value,
+        });
+        let lp = summary.positions_lp.iter().map(|p| PositionSnapshot {
+            kind: PositionKind::Lp,
+            unit: p.unit.clone(),
+            ticker: p.ticker.clone(),
+            balance: p.amount_lp as f64,
+            price: None,
+            ada_value: p.ada_value,
+        });
+
+        Self {
+            timestamp,
+            user_id: user_id.to_string(),
+            ada_balance: summary.ada_balance,
+            ada_value: summary.ada_value,
+            liquid_value: summary.liquid_value,
+            ada_usd_price,
+            positions: ft.chain(nft).chain(lp).collect(),
+        }
+    }
+
+    pub fn usd_value(&self) -> f64 {
+        self.ada_value * self.ada_usd_price
+    }
+}
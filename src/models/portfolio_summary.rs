@@ -4,7 +4,7 @@ use super::nft_position::NftPosition;
 use super::ft_position::FtPosition;
 use super::lp_position::LpPosition;
 
-#[derive(Deserialize, Serialize, Debug)]
+#[derive(Deserialize, Serialize, Debug, Clone, Default)]
 pub struct PortfolioSummary {
     #[serde(rename = "adaBalance")]
     pub ada_balance: f64,
@@ -24,6 +24,56 @@ pub struct PortfolioSummary {
     pub positions_nft: Vec<NftPosition>,
 }
 
+impl PortfolioSummary {
+    /// Folds another summary into this one. Fungible tokens and liquidity
+    /// positions are combined by `unit`, NFT collections by `policy`.
+    pub fn merge(&mut self, other: PortfolioSummary) {
+        self.ada_balance += other.ada_balance;
+        self.ada_value += other.ada_value;
+        self.liquid_value += other.liquid_value;
+        self.num_nfts += other.num_nfts;
+
+        for position in other.positions_ft {
+            match self.positions_ft.iter_mut().find(|p| p.unit == position.unit) {
+                Some(existing) => {
+                    existing.balance += position.balance;
+                    existing.liquid_balance += position.liquid_balance;
+                    existing.ada_value += position.ada_value;
+                    existing.liquid_value += position.liquid_value;
+                }
+                None => self.positions_ft.push(position),
+            }
+        }
+
+        for position in other.positions_nft {
+            match self.positions_nft.iter_mut().find(|p| p.policy == position.policy) {
+                Some(existing) => {
+                    existing.balance += position.balance;
+                    existing.ada_value += position.ada_value;
+                    existing.liquid_value += position.liquid_value;
+                }
+                None => self.positions_nft.push(position),
+            }
+        }
+
+        for position in other.positions_lp {
+            match self.positions_lp.iter_mut().find(|p| p.unit == position.unit) {
+                Some(existing) => {
+                    existing.amount_lp += position.amount_lp;
+                    existing.ada_value += position.ada_value;
+                    existing.liquid_value += position.liquid_value;
+                    existing.token_a_amount += position.token_a_amount;
+                    existing.token_b_amount += position.token_b_amount;
+                }
+                None => self.positions_lp.push(position),
+            }
+        }
+
+        // Token counts are distinct units, so recount after de-duplication
+        self.num_fts = self.positions_ft.len() as u32;
+    }
+}
+
 impl fmt::Display for PortfolioSummary {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         write!(f, "PortfolioSummary {{ ada_balance: {:?}, ada_value: {:?}, liquid_value: {:?}, num_fts: {:?}, num_nfts: {:?} }}",
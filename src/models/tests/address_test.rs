@@ -0,0 +1,74 @@
+use crate::models::address::{AddressError, CardanoAddress, Network};
+use crate::models::wallet::Wallet;
+
+// Test vectors from CIP-19, all built from the same payment and stake keys
+const BASE: &str = "addr1qx2fxv2umyhttkxyxp8x0dlpdt3k6cwng5pxj3jhsydzer3n0d3vllmyqwsx5wktcd8cc3sq835lu7drv2xwl2wywfgse35a3x";
+const BASE_TEST: &str = "addr_test1qz2fxv2umyhttkxyxp8x0dlpdt3k6cwng5pxj3jhsydzer3n0d3vllmyqwsx5wktcd8cc3sq835lu7drv2xwl2wywfgs68faae";
+const SCRIPT_PAYMENT: &str = "addr1z8phkx6acpnf78fuvxn0mkew3l0fd058hzquvz7w36x4gten0d3vllmyqwsx5wktcd8cc3sq835lu7drv2xwl2wywfgs9yc0hh";
+const ENTERPRISE: &str = "addr1vx2fxv2umyhttkxyxp8x0dlpdt3k6cwng5pxj3jhsydzers66hrl8";
+const STAKE: &str = "stake1uyehkck0lajq8gr28t9uxnuvgcqrc6070x3k9r8048z8y5gh6ffgw";
+const STAKE_TEST: &str = "stake_test1uqehkck0lajq8gr28t9uxnuvgcqrc6070x3k9r8048z8y5gssrtvn";
+
+#[test]
+fn test_parses_shelley_addresses() {
+    for address in [BASE, SCRIPT_PAYMENT, ENTERPRISE, STAKE] {
+        let parsed = CardanoAddress::parse(address).unwrap();
+        assert_eq!(parsed.network(), Network::Mainnet);
+        assert_eq!(parsed.as_str(), address);
+    }
+    assert_eq!(CardanoAddress::parse(BASE_TEST).unwrap().network(), Network::Testnet);
+    assert!(CardanoAddress::parse(STAKE).unwrap().is_stake());
+    assert!(!CardanoAddress::parse(BASE).unwrap().is_stake());
+    // Surrounding whitespace from a paste and upper case are accepted
+    let upper = format!("  {}\n", BASE.to_uppercase());
+    assert_eq!(CardanoAddress::parse(&upper).unwrap().as_str(), BASE);
+}
+
+#[test]
+fn test_resolves_stake_address() {
+    let stake = |address: &str| CardanoAddress::parse(address).unwrap().stake_address().map(|s| s.to_string());
+    assert_eq!(stake(BASE).as_deref(), Some(STAKE));
+    assert_eq!(stake(SCRIPT_PAYMENT).as_deref(), Some(STAKE));
+    assert_eq!(stake(BASE_TEST).as_deref(), Some(STAKE_TEST));
+    assert_eq!(stake(STAKE).as_deref(), Some(STAKE));
+    assert_eq!(stake(ENTERPRISE), None);
+}
+
+#[test]
+fn test_rejects_invalid_addresses() {
+    assert_eq!(CardanoAddress::parse("  "), Err(AddressError::Empty));
+    assert_eq!(
+        CardanoAddress::parse("DdzFFzCqrhsw3prhfMFDNFowbzUku3QmrMwarfjUbWXRisodn97R436SHc1rimp4MhPNmbdYb1aTdqtGSJixMVMi5MkArDQJ6Sc1n3Ez"),
+        Err(AddressError::Byron),
+    );
+    assert_eq!(
+        CardanoAddress::parse("bc1qar0srrr7xfkvy5l643lydnw9re59gtzzwf5mdq"),
+        Err(AddressError::UnknownPrefix("bc".to_string())),
+    );
+
+    // One character changed breaks the checksum
+    let typo = BASE.replacen("qx2f", "qx3f", 1);
+    assert!(matches!(CardanoAddress::parse(&typo), Err(AddressError::Bech32(_))));
+    assert!(matches!(CardanoAddress::parse("addr1"), Err(AddressError::Bech32(_))));
+
+    // A stake address re-encoded under a payment prefix
+    let (_, payload) = bech32::decode(STAKE).unwrap();
+    let relabelled = bech32::encode::<bech32::Bech32>(bech32::Hrp::parse("addr").unwrap(), &payload).unwrap();
+    assert!(matches!(CardanoAddress::parse(&relabelled), Err(AddressError::Malformed(_))));
+    let (_, payload) = bech32::decode(BASE_TEST).unwrap();
+    let relabelled = bech32::encode::<bech32::Bech32>(bech32::Hrp::parse("addr").unwrap(), &payload).unwrap();
+    assert_eq!(CardanoAddress::parse(&relabelled), Err(AddressError::NetworkMismatch));
+}
+
+#[test]
+fn test_stake_address_covers_its_payment_addresses() {
+    let wallet = Wallet {
+        id: "w".to_string(),
+        name: "Ledger".to_string(),
+        addresses: vec![BASE.to_string(), ENTERPRISE.to_string(), STAKE.to_string(), "legacy".to_string()],
+    };
+    assert_eq!(wallet.tracked_addresses(), vec![ENTERPRISE, STAKE, "legacy"]);
+
+    let without_stake = Wallet { addresses: vec![BASE.to_string()], ..wallet };
+    assert_eq!(without_stake.tracked_addresses(), vec![BASE]);
+}
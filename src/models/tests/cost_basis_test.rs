@@ -0,0 +1,73 @@
+use chrono::NaiveDate;
+use crate::models::cost_basis::{CostBasisBook, CostBasisMethod, Lot};
+
+fn lot(unit: &str, day: u32, quantity: f64, cost_ada: f64) -> Lot {
+    Lot {
+        unit: unit.to_string(),
+        acquired_on: NaiveDate::from_ymd_opt(2024, 1, day).unwrap(),
+        quantity,
+        cost_ada,
+    }
+}
+
+// Two lots of 100 tokens: the older one at 1 ADA each, the newer at 3 ADA each
+fn create_test_book(method: CostBasisMethod) -> CostBasisBook {
+    CostBasisBook {
+        method,
+        // Deliberately out of order to check lots are sorted by date
+        lots: vec![lot("tkn", 20, 100.0, 300.0), lot("tkn", 10, 100.0, 100.0), lot("other", 1, 5.0, 5.0)],
+    }
+}
+
+#[test]
+fn test_fifo_keeps_newest_lots() {
+    let held = create_test_book(CostBasisMethod::Fifo).held_cost("tkn", 150.0).unwrap();
+    assert_eq!(held.quantity, 150.0);
+    // All of the 3 ADA lot plus half of the 1 ADA lot
+    assert_eq!(held.cost_ada, 350.0);
+}
+
+#[test]
+fn test_lifo_keeps_oldest_lots() {
+    let held = create_test_book(CostBasisMethod::Lifo).held_cost("tkn", 150.0).unwrap();
+    assert_eq!(held.cost_ada, 250.0);
+}
+
+#[test]
+fn test_average_cost() {
+    let held = create_test_book(CostBasisMethod::AverageCost).held_cost("tkn", 150.0).unwrap();
+    assert_eq!(held.cost_ada, 300.0);
+}
+
+#[test]
+fn test_balance_above_recorded_lots_is_only_partly_covered() {
+    let book = create_test_book(CostBasisMethod::Fifo);
+    let held = book.held_cost("tkn", 300.0).unwrap();
+    assert_eq!(held.quantity, 200.0);
+    assert_eq!(held.cost_ada, 400.0);
+
+    // 300 tokens worth 900 ADA, of which the 200 covered ones cost 400
+    assert_eq!(book.unrealized_pnl("tkn", 300.0, 900.0), Some(200.0));
+}
+
+#[test]
+fn test_unknown_unit_has_no_pnl() {
+    let book = create_test_book(CostBasisMethod::Fifo);
+    assert!(book.held_cost("missing", 10.0).is_none());
+    assert!(book.unrealized_pnl("missing", 10.0, 10.0).is_none());
+}
+
+#[test]
+fn test_unrealized_loss() {
+    let book = create_test_book(CostBasisMethod::Lifo);
+    // 100 tokens now worth 0.5 ADA each, bought at 1 ADA each
+    assert_eq!(book.unrealized_pnl("tkn", 100.0, 50.0), Some(-50.0));
+}
+
+#[test]
+fn test_method_parsing() {
+    assert_eq!("FIFO".parse::<CostBasisMethod>(), Ok(CostBasisMethod::Fifo));
+    assert_eq!("lifo".parse::<CostBasisMethod>(), Ok(CostBasisMethod::Lifo));
+    assert_eq!("average".parse::<CostBasisMethod>(), Ok(CostBasisMethod::AverageCost));
+    assert!("hifo".parse::<CostBasisMethod>().is_err());
+}
@@ -0,0 +1,6 @@
+#[cfg(test)]
+mod portfolio_summary_test;
+#[cfg(test)]
+mod cost_basis_test;
+#[cfg(test)]
+mod address_test;
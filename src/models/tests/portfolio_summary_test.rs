@@ -0,0 +1,108 @@
+use crate::models::portfolio_summary::PortfolioSummary;
+use crate::models::user_portfolio::{UserPortfolio, WalletPortfolio};
+
+// Helper function to build a summary holding one FT, one NFT collection and one LP
+fn create_test_summary(ada_balance: f64, ft_unit: &str, ft_balance: f64) -> PortfolioSummary {
+    let portfolio_json = format!(r#"{{
+        "adaBalance": {ada_balance},
+        "adaValue": {ada_balance},
+        "liquidValue": {ada_balance},
+        "numFTs": 1,
+        "numNFTs": 2,
+        "positionsFt": [{{
+            "balance": {ft_balance},
+            "liquidBalance": {ft_balance},
+            "adaValue": 10.0,
+            "liquidValue": 10.0,
+            "price": 0.5,
+            "ticker": "TKN",
+            "unit": "{ft_unit}",
+            "fingerprint": "asset1",
+            "24h": 0.01,
+            "7d": 0.02,
+            "30d": 0.03
+        }}],
+        "positionsNft": [{{
+            "balance": 2,
+            "adaValue": 100.0,
+            "liquidValue": 90.0,
+            "floorPrice": 50.0,
+            "listings": 7,
+            "name": "Collection",
+            "policy": "policy_1",
+            "24h": null,
+            "7d": null,
+            "30d": null
+        }}],
+        "positionsLp": [{{
+            "amount_lp": 5,
+            "ada_value": 20.0,
+            "liquid_value": 20.0,
+            "ticker": "TKN/ADA",
+            "exchange": "Minswap",
+            "unit": "lp_unit",
+            "tokenA": "",
+            "tokenAAmount": 10.0,
+            "tokenAName": "ADA",
+            "tokenB": "tkn",
+            "tokenBAmount": 20.0,
+            "tokenBName": "TKN"
+        }}]
+    }}"#);
+
+    serde_json::from_str(&portfolio_json).unwrap()
+}
+
+#[test]
+fn test_merge_sums_matching_positions() {
+    let mut summary = create_test_summary(100.0, "unit_a", 5.0);
+    summary.merge(create_test_summary(50.0, "unit_a", 3.0));
+
+    assert_eq!(summary.ada_balance, 150.0);
+    assert_eq!(summary.num_fts, 1);
+    assert_eq!(summary.num_nfts, 4);
+
+    assert_eq!(summary.positions_ft.len(), 1);
+    assert_eq!(summary.positions_ft[0].balance, 8.0);
+    assert_eq!(summary.positions_ft[0].ada_value, 20.0);
+
+    assert_eq!(summary.positions_nft.len(), 1);
+    assert_eq!(summary.positions_nft[0].balance, 4);
+
+    assert_eq!(summary.positions_lp.len(), 1);
+    assert_eq!(summary.positions_lp[0].amount_lp, 10);
+    assert_eq!(summary.positions_lp[0].token_b_amount, 40.0);
+}
+
+#[test]
+fn test_merge_keeps_distinct_units() {
+    let mut summary = create_test_summary(100.0, "unit_a", 5.0);
+    summary.merge(create_test_summary(50.0, "unit_b", 3.0));
+
+    assert_eq!(summary.num_fts, 2);
+    assert_eq!(summary.positions_ft.len(), 2);
+    assert_eq!(summary.positions_ft[1].unit, "unit_b");
+}
+
+#[test]
+fn test_user_portfolio_from_wallets() {
+    let wallets = vec![
+        WalletPortfolio {
+            wallet_id: "wallet_1".to_string(),
+            wallet_name: "Hardware".to_string(),
+            summary: create_test_summary(100.0, "unit_a", 5.0),
+        },
+        WalletPortfolio {
+            wallet_id: "wallet_2".to_string(),
+            wallet_name: "Hot".to_string(),
+            summary: create_test_summary(25.0, "unit_a", 1.0),
+        },
+    ];
+
+    let portfolio = UserPortfolio::from_wallets(wallets);
+
+    assert_eq!(portfolio.wallets.len(), 2);
+    assert_eq!(portfolio.wallets[0].summary.ada_balance, 100.0);
+    assert_eq!(portfolio.total.ada_balance, 125.0);
+    assert_eq!(portfolio.total.positions_ft[0].balance, 6.0);
+}
@@ -1,10 +1,38 @@
 use serde::{Serialize, Deserialize};
-use crate::models::wallet::Wallet;
+use crate::db::secret::EncryptedSecret;
+use crate::models::{
+    cost_basis::CostBasisBook,
+    display_currency::DisplayCurrency,
+    ledger::LedgerEntry,
+    wallet::Wallet,
+};
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct User {
     pub id: String,
     pub name: String,
+    /// Plain-text Taptools key. Only found in databases written before keys
+    /// were encrypted; in memory it holds the key unlocked at login.
+    #[serde(default, skip_serializing_if = "String::is_empty")]
     pub taptools_api_key: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub encrypted_api_key: Option<EncryptedSecret>,
     pub wallets: Vec<Wallet>,
-}
\ No newline at end of file
+    #[serde(default)]
+    pub display_currency: DisplayCurrency,
+    #[serde(default)]
+    pub cost_basis: CostBasisBook,
+    #[serde(default)]
+    pub ledger: Vec<LedgerEntry>,
+}
+
+impl User {
+    /// Manually entered lots together with the acquisitions in the ledger
+    pub fn effective_cost_basis(&self) -> CostBasisBook {
+        let mut book = self.cost_basis.clone();
+        for lot in self.ledger.iter().filter_map(|entry| entry.to_lot()) {
+            book.add_lot(lot);
+        }
+        book
+    }
+}
@@ -0,0 +1,27 @@
+use serde::{Deserialize, Serialize};
+use super::portfolio_summary::PortfolioSummary;
+
+/// Holdings of a single wallet, merged across all of its addresses.
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct WalletPortfolio {
+    pub wallet_id: String,
+    pub wallet_name: String,
+    pub summary: PortfolioSummary,
+}
+
+/// Holdings of a user across every wallet, plus the per-wallet breakdown.
+#[derive(Deserialize, Serialize, Debug, Clone, Default)]
+pub struct UserPortfolio {
+    pub total: PortfolioSummary,
+    pub wallets: Vec<WalletPortfolio>,
+}
+
+impl UserPortfolio {
+    pub fn from_wallets(wallets: Vec<WalletPortfolio>) -> Self {
+        let mut total = PortfolioSummary::default();
+        for wallet in &wallets {
+            total.merge(wallet.summary.clone());
+        }
+        Self { total, wallets }
+    }
+}
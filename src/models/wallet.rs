@@ -1,8 +1,31 @@
 use serde::{Serialize, Deserialize};
+use super::address::CardanoAddress;
 
+/// Addresses may be payment or stake addresses. A stake address stands for
+/// every payment address derived under its key.
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct Wallet {
     pub id: String,
     pub name: String,
     pub addresses: Vec<String>,
-}
\ No newline at end of file
+}
+
+impl Wallet {
+    /// Addresses to look positions up for, leaving out payment addresses
+    /// already covered by a stake address in the wallet
+    pub fn tracked_addresses(&self) -> Vec<&str> {
+        let stake_addresses: Vec<CardanoAddress> = self.addresses.iter()
+            .filter_map(|address| CardanoAddress::parse(address).ok())
+            .filter(CardanoAddress::is_stake)
+            .collect();
+        self.addresses.iter()
+            .filter(|address| match CardanoAddress::parse(address) {
+                Ok(parsed) if !parsed.is_stake() => {
+                    !parsed.stake_address().is_some_and(|stake| stake_addresses.contains(&stake))
+                },
+                _ => true,
+            })
+            .map(String::as_str)
+            .collect()
+    }
+}
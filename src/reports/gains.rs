@@ -0,0 +1,202 @@
+use std::collections::{BTreeMap, BTreeSet, HashMap, VecDeque};
+use std::io::Write;
+use chrono::{Datelike, NaiveDate};
+use serde::Serialize;
+use crate::models::cost_basis::CostBasisMethod;
+use crate::models::ledger::{LedgerEntry, LedgerSide};
+use crate::reports::{write_records, ReportFormat};
+use crate::services::price::QuoteCurrency;
+
+/// Part of a disposal matched against one acquisition lot
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct GainRow {
+    pub ticker: String,
+    pub unit: Option<String>,
+    pub quantity: f64,
+    /// `None` when the disposal exceeds every recorded acquisition
+    pub acquired_on: Option<NaiveDate>,
+    pub disposed_on: NaiveDate,
+    pub proceeds: f64,
+    pub cost: f64,
+    pub gain: f64,
+}
+
+/// Realized gains of one calendar year, valued in a single fiat currency
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct GainsReport {
+    pub year: i32,
+    pub currency: QuoteCurrency,
+    pub method: CostBasisMethod,
+    pub rows: Vec<GainRow>,
+    pub total_proceeds: f64,
+    pub total_cost: f64,
+    pub total_gain: f64,
+}
+
+/// Remaining part of an acquisition, with its fiat cost
+struct OpenLot {
+    acquired_on: NaiveDate,
+    quantity: f64,
+    cost: f64,
+}
+
+/// Dates whose ADA price in `currency` is needed to value the ledger up to
+/// the end of `year`, keyed as `ada_rates` is in [`GainsReport::build`]
+pub fn rate_dates(ledger: &[LedgerEntry], year: i32, currency: QuoteCurrency) -> BTreeSet<NaiveDate> {
+    ledger.iter()
+        .filter(|entry| entry.timestamp.year() <= year)
+        .filter(|entry| {
+            let has_fiat = entry.fiat_value.is_some_and(|fiat| fiat.currency == currency);
+            (!has_fiat && entry.ada_value.is_some()) || entry.fee_ada > 0.0
+        })
+        .map(|entry| entry.timestamp.date_naive())
+        .collect()
+}
+
+impl GainsReport {
+    /// Replays every acquisition and disposal up to the end of `year`,
+    /// matching disposals against lots with `method`, and reports the
+    /// disposals that happened in `year`. Entries without a value in
+    /// `currency` are valued through their ADA value and `ada_rates`.
+    pub fn build(
+        ledger: &[LedgerEntry],
+        year: i32,
+        currency: QuoteCurrency,
+        method: CostBasisMethod,
+        ada_rates: &HashMap<NaiveDate, f64>,
+    ) -> Result<Self, String> {
+        let mut by_asset: BTreeMap<String, Vec<&LedgerEntry>> = BTreeMap::new();
+        for entry in ledger.iter().filter(|entry| entry.timestamp.year() <= year) {
+            let key = entry.unit.clone().unwrap_or_else(|| entry.ticker.to_uppercase());
+            by_asset.entry(key).or_default().push(entry);
+        }
+
+        let mut rows = Vec::new();
+        for entries in by_asset.values_mut() {
+            entries.sort_by_key(|entry| entry.timestamp);
+            let mut lots: VecDeque<OpenLot> = VecDeque::new();
+
+            for entry in entries.iter() {
+                let date = entry.timestamp.date_naive();
+                let fee = if entry.fee_ada > 0.0 { entry.fee_ada * ada_rate(ada_rates, date, currency)? } else { 0.0 };
+
+                match entry.side {
+                    LedgerSide::Acquire => {
+                        lots.push_back(OpenLot {
+                            acquired_on: date,
+                            quantity: entry.quantity,
+                            cost: value(entry, currency, ada_rates)? + fee,
+                        });
+                    },
+                    LedgerSide::Dispose => {
+                        // Fees paid in a token have no proceeds
+                        let proceeds = match value(entry, currency, ada_rates) {
+                            Ok(value) => value - fee,
+                            Err(_) if entry.label.as_deref() == Some("fee") => 0.0,
+                            Err(e) => return Err(e),
+                        };
+                        let disposal_rows = dispose(&mut lots, entry, proceeds, method);
+                        if date.year() == year {
+                            rows.extend(disposal_rows);
+                        }
+                    },
+                }
+            }
+        }
+
+        rows.sort_by(|a, b| a.disposed_on.cmp(&b.disposed_on).then_with(|| a.ticker.cmp(&b.ticker)));
+        Ok(Self {
+            year,
+            currency,
+            method,
+            total_proceeds: rows.iter().map(|row| row.proceeds).sum(),
+            total_cost: rows.iter().map(|row| row.cost).sum(),
+            total_gain: rows.iter().map(|row| row.gain).sum(),
+            rows,
+        })
+    }
+
+    pub fn write<W: Write>(&self, writer: W, format: ReportFormat) -> Result<(), Box<dyn std::error::Error>> {
+        match format {
+            ReportFormat::Csv => write_records(writer, format, &self.rows),
+            ReportFormat::Json => Ok(serde_json::to_writer_pretty(writer, self)?),
+        }
+    }
+}
+
+/// Takes the disposed quantity out of `lots`, oldest first for FIFO and
+/// average cost and newest first for LIFO
+fn dispose(lots: &mut VecDeque<OpenLot>, entry: &LedgerEntry, proceeds: f64, method: CostBasisMethod) -> Vec<GainRow> {
+    if method == CostBasisMethod::AverageCost {
+        let quantity: f64 = lots.iter().map(|lot| lot.quantity).sum();
+        let cost: f64 = lots.iter().map(|lot| lot.cost).sum();
+        if quantity > 0.0 {
+            for lot in lots.iter_mut() {
+                lot.cost = cost / quantity * lot.quantity;
+            }
+        }
+    }
+
+    let disposed_on = entry.timestamp.date_naive();
+    let row = |quantity: f64, acquired_on: Option<NaiveDate>, cost: f64| {
+        let proceeds = if entry.quantity > 0.0 { proceeds * quantity / entry.quantity } else { 0.0 };
+        GainRow {
+            ticker: entry.ticker.clone(),
+            unit: entry.unit.clone(),
+            quantity,
+            acquired_on,
+            disposed_on,
+            proceeds,
+            cost,
+            gain: proceeds - cost,
+        }
+    };
+
+    let mut rows = Vec::new();
+    let mut remaining = entry.quantity;
+    while remaining > 0.0 {
+        let lot = match method {
+            CostBasisMethod::Lifo => lots.back_mut(),
+            CostBasisMethod::Fifo | CostBasisMethod::AverageCost => lots.front_mut(),
+        };
+        let Some(lot) = lot else {
+            // Nothing recorded to match against, so the cost is unknown
+            rows.push(row(remaining, None, 0.0));
+            break;
+        };
+
+        let taken = remaining.min(lot.quantity);
+        let cost = if lot.quantity > 0.0 { lot.cost * taken / lot.quantity } else { 0.0 };
+        rows.push(row(taken, Some(lot.acquired_on), cost));
+
+        lot.quantity -= taken;
+        lot.cost -= cost;
+        remaining -= taken;
+        if lot.quantity <= f64::EPSILON {
+            match method {
+                CostBasisMethod::Lifo => lots.pop_back(),
+                CostBasisMethod::Fifo | CostBasisMethod::AverageCost => lots.pop_front(),
+            };
+        }
+    }
+    rows
+}
+
+/// Fiat value of an entry, from its own fiat value when it is in
+/// `currency` and from its ADA value otherwise
+fn value(entry: &LedgerEntry, currency: QuoteCurrency, ada_rates: &HashMap<NaiveDate, f64>) -> Result<f64, String> {
+    if let Some(fiat) = entry.fiat_value.filter(|fiat| fiat.currency == currency) {
+        return Ok(fiat.amount);
+    }
+    let date = entry.timestamp.date_naive();
+    match entry.ada_value {
+        Some(ada) => Ok(ada * ada_rate(ada_rates, date, currency)?),
+        None => Err(format!("No {} or ADA value for {} {} on {}", currency, entry.quantity, entry.ticker, date)),
+    }
+}
+
+fn ada_rate(ada_rates: &HashMap<NaiveDate, f64>, date: NaiveDate, currency: QuoteCurrency) -> Result<f64, String> {
+    ada_rates.get(&date)
+        .copied()
+        .ok_or_else(|| format!("No ADA/{} price for {}", currency, date))
+}
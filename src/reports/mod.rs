@@ -0,0 +1,57 @@
+pub mod gains;
+
+use std::fmt;
+use std::io::Write;
+use std::str::FromStr;
+use serde::Serialize;
+
+/// File format a report is exported in
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ReportFormat {
+    #[default]
+    Csv,
+    Json,
+}
+
+impl FromStr for ReportFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "csv" => Ok(ReportFormat::Csv),
+            "json" => Ok(ReportFormat::Json),
+            _ => Err(format!("Unknown report format '{}', expected csv or json", s)),
+        }
+    }
+}
+
+impl fmt::Display for ReportFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ReportFormat::Csv => write!(f, "csv"),
+            ReportFormat::Json => write!(f, "json"),
+        }
+    }
+}
+
+/// Writes `rows` as CSV records or as a JSON array
+pub fn write_records<W: Write, T: Serialize>(
+    writer: W,
+    format: ReportFormat,
+    rows: &[T],
+) -> Result<(), Box<dyn std::error::Error>> {
+    match format {
+        ReportFormat::Csv => {
+            let mut writer = csv::Writer::from_writer(writer);
+            for row in rows {
+                writer.serialize(row)?;
+            }
+            writer.flush()?;
+        },
+        ReportFormat::Json => serde_json::to_writer_pretty(writer, rows)?,
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,117 @@
+use std::collections::HashMap;
+use chrono::{NaiveDate, TimeZone, Utc};
+use crate::models::cost_basis::CostBasisMethod;
+use crate::models::ledger::{FiatAmount, LedgerEntry, LedgerSide};
+use crate::reports::{ReportFormat, gains::{rate_dates, GainsReport}};
+use crate::services::price::QuoteCurrency;
+
+fn entry(side: LedgerSide, year: i32, month: u32, quantity: f64, usd: f64) -> LedgerEntry {
+    LedgerEntry {
+        timestamp: Utc.with_ymd_and_hms(year, month, 1, 12, 0, 0).unwrap(),
+        wallet_id: None,
+        side,
+        ticker: "TKN".to_string(),
+        unit: Some("tkn".to_string()),
+        quantity,
+        ada_value: None,
+        fiat_value: Some(FiatAmount { amount: usd, currency: QuoteCurrency::Usd }),
+        fee_ada: 0.0,
+        tx_hash: None,
+        label: None,
+    }
+}
+
+// 100 at $1, then 100 at $3, then 150 sold for $600 in 2024
+fn create_test_ledger() -> Vec<LedgerEntry> {
+    vec![
+        entry(LedgerSide::Acquire, 2023, 1, 100.0, 100.0),
+        entry(LedgerSide::Acquire, 2023, 6, 100.0, 300.0),
+        entry(LedgerSide::Dispose, 2024, 3, 150.0, 600.0),
+    ]
+}
+
+fn build(ledger: &[LedgerEntry], year: i32, method: CostBasisMethod) -> GainsReport {
+    GainsReport::build(ledger, year, QuoteCurrency::Usd, method, &HashMap::new()).unwrap()
+}
+
+#[test]
+fn test_fifo_matches_oldest_lot_first() {
+    let report = build(&create_test_ledger(), 2024, CostBasisMethod::Fifo);
+
+    assert_eq!(report.rows.len(), 2);
+    assert_eq!(report.rows[0].acquired_on, NaiveDate::from_ymd_opt(2023, 1, 1));
+    assert_eq!(report.rows[0].quantity, 100.0);
+    assert_eq!(report.rows[0].proceeds, 400.0);
+    assert_eq!(report.rows[0].cost, 100.0);
+    assert_eq!(report.rows[1].quantity, 50.0);
+    assert_eq!(report.rows[1].cost, 150.0);
+    assert_eq!(report.total_cost, 250.0);
+    assert_eq!(report.total_gain, 350.0);
+}
+
+#[test]
+fn test_lifo_and_average_cost() {
+    let lifo = build(&create_test_ledger(), 2024, CostBasisMethod::Lifo);
+    assert_eq!(lifo.rows[0].acquired_on, NaiveDate::from_ymd_opt(2023, 6, 1));
+    assert_eq!(lifo.total_cost, 350.0);
+
+    let average = build(&create_test_ledger(), 2024, CostBasisMethod::AverageCost);
+    assert_eq!(average.total_cost, 300.0);
+    assert_eq!(average.total_gain, 300.0);
+}
+
+#[test]
+fn test_only_disposals_of_the_year_are_reported() {
+    let mut ledger = create_test_ledger();
+    ledger.push(entry(LedgerSide::Dispose, 2025, 1, 50.0, 50.0));
+
+    assert!(build(&ledger, 2023, CostBasisMethod::Fifo).rows.is_empty());
+    assert_eq!(build(&ledger, 2024, CostBasisMethod::Fifo).rows.len(), 2);
+
+    // The 2024 disposal used up the first lot, so 2025 matches the second
+    let report = build(&ledger, 2025, CostBasisMethod::Fifo);
+    assert_eq!(report.rows.len(), 1);
+    assert_eq!(report.rows[0].cost, 150.0);
+}
+
+#[test]
+fn test_unmatched_disposal_has_unknown_acquisition() {
+    let ledger = vec![entry(LedgerSide::Dispose, 2024, 1, 10.0, 20.0)];
+    let report = build(&ledger, 2024, CostBasisMethod::Fifo);
+
+    assert_eq!(report.rows[0].acquired_on, None);
+    assert_eq!(report.rows[0].gain, 20.0);
+}
+
+#[test]
+fn test_ada_values_use_daily_rates() {
+    let mut buy = entry(LedgerSide::Acquire, 2024, 1, 10.0, 0.0);
+    buy.fiat_value = None;
+    buy.ada_value = Some(100.0);
+    buy.fee_ada = 1.0;
+    let ledger = vec![buy, entry(LedgerSide::Dispose, 2024, 2, 10.0, 80.0)];
+
+    let dates = rate_dates(&ledger, 2024, QuoteCurrency::Usd);
+    assert_eq!(dates.len(), 1);
+
+    // Without the rate the buy cannot be valued
+    let missing = GainsReport::build(&ledger, 2024, QuoteCurrency::Usd, CostBasisMethod::Fifo, &HashMap::new());
+    assert!(missing.is_err());
+
+    let rates: HashMap<_, _> = dates.into_iter().map(|date| (date, 0.5)).collect();
+    let report = GainsReport::build(&ledger, 2024, QuoteCurrency::Usd, CostBasisMethod::Fifo, &rates).unwrap();
+    assert_eq!(report.rows[0].cost, 50.5);
+    assert_eq!(report.rows[0].gain, 29.5);
+}
+
+#[test]
+fn test_csv_export() {
+    let report = build(&create_test_ledger(), 2024, CostBasisMethod::Fifo);
+    let mut output = Vec::new();
+    report.write(&mut output, ReportFormat::Csv).unwrap();
+    let csv = String::from_utf8(output).unwrap();
+
+    let mut lines = csv.lines();
+    assert_eq!(lines.next(), Some("ticker,unit,quantity,acquired_on,disposed_on,proceeds,cost,gain"));
+    assert_eq!(lines.next(), Some("TKN,tkn,100.0,2023-01-01,2024-03-01,400.0,100.0,300.0"));
+}
@@ -0,0 +1,97 @@
+use std::fmt;
+use std::path::PathBuf;
+use reqwest::{Response, StatusCode};
+use serde::de::DeserializeOwned;
+
+const SNIPPET_LENGTH: usize = 200;
+
+#[derive(Debug)]
+pub enum ApiError {
+    /// The request never produced a response (DNS, TLS, connection reset, ...)
+    Network(reqwest::Error),
+    /// The API rejected the API key
+    Unauthorized,
+    /// Too many requests; `retry_after` is in seconds when the API supplies it
+    RateLimited { retry_after: Option<u64> },
+    /// Any other non-success status
+    Http { status: StatusCode, body: String },
+    /// The response body could not be deserialized into the expected type
+    MalformedBody { error: String, snippet: String },
+    /// A recorded fixture could not be read or written
+    Fixture { path: PathBuf, error: String },
+}
+
+impl fmt::Display for ApiError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ApiError::Network(e) => write!(f, "Network error: {}", e),
+            ApiError::Unauthorized => write!(f, "API key was rejected"),
+            ApiError::RateLimited { retry_after: Some(seconds) } => {
+                write!(f, "Rate limited, retry in {}s", seconds)
+            },
+            ApiError::RateLimited { retry_after: None } => write!(f, "Rate limited"),
+            ApiError::Http { status, body } => write!(f, "API request failed: {} - {}", status, body),
+            ApiError::MalformedBody { error, snippet } => {
+                write!(f, "Malformed response ({}): {}", error, snippet)
+            },
+            ApiError::Fixture { path, error } => write!(f, "Fixture {}: {}", path.display(), error),
+        }
+    }
+}
+
+impl std::error::Error for ApiError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            ApiError::Network(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<reqwest::Error> for ApiError {
+    fn from(error: reqwest::Error) -> Self {
+        ApiError::Network(error)
+    }
+}
+
+/// Maps non-success statuses to an `ApiError` and returns the response body otherwise.
+pub async fn read_body(response: Response) -> Result<String, ApiError> {
+    let status = response.status();
+    if status.is_success() {
+        return Ok(response.text().await?);
+    }
+
+    match status {
+        StatusCode::UNAUTHORIZED | StatusCode::FORBIDDEN => Err(ApiError::Unauthorized),
+        StatusCode::TOO_MANY_REQUESTS => {
+            Err(ApiError::RateLimited { retry_after: retry_after_seconds(&response) })
+        },
+        _ => Err(ApiError::Http {
+            status,
+            body: snippet(&response.text().await.unwrap_or_default()),
+        }),
+    }
+}
+
+/// Seconds from a `Retry-After` header, when it is given in delta-seconds form
+pub fn retry_after_seconds(response: &Response) -> Option<u64> {
+    response.headers()
+        .get(reqwest::header::RETRY_AFTER)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.trim().parse().ok())
+}
+
+pub fn parse_body<T: DeserializeOwned>(body: &str) -> Result<T, ApiError> {
+    serde_json::from_str(body).map_err(|e| ApiError::MalformedBody {
+        error: e.to_string(),
+        snippet: snippet(body),
+    })
+}
+
+pub(crate) fn snippet(body: &str) -> String {
+    if body.chars().count() > SNIPPET_LENGTH {
+        format!("{}...", body.chars().take(SNIPPET_LENGTH).collect::<String>())
+    } else {
+        body.to_string()
+    }
+}
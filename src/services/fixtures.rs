@@ -0,0 +1,135 @@
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use async_trait::async_trait;
+use serde::{de::DeserializeOwned, Serialize};
+use crate::models::{market_cap_token::MarketCapToken, portfolio_summary::PortfolioSummary};
+use crate::services::api_error::ApiError;
+use crate::services::portfolio_provider::PortfolioProvider;
+
+const MARKET_CAPS_FIXTURE: &str = "market_caps";
+const TOKEN_PRICES_FIXTURE: &str = "token_prices";
+pub const PRICES_FIXTURE: &str = "prices";
+/// Historical ADA prices by date, one fixture per quote currency
+pub const ADA_RATES_FIXTURE: &str = "ada_rates";
+
+/// Where API responses come from: the network, the network with every
+/// response written to disk, or previously written fixtures only.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub enum FixtureMode {
+    #[default]
+    Live,
+    Record(PathBuf),
+    Replay(PathBuf),
+}
+
+/// Directory of JSON fixtures, one file per response
+pub struct FixtureStore {
+    dir: PathBuf,
+}
+
+impl FixtureStore {
+    pub fn new(dir: &Path) -> Self {
+        Self {
+            dir: dir.to_path_buf(),
+        }
+    }
+
+    pub fn load<T: DeserializeOwned>(&self, name: &str) -> Result<T, ApiError> {
+        let path = self.path(name);
+        let content = fs::read_to_string(&path).map_err(|e| ApiError::Fixture {
+            path: path.clone(),
+            error: e.to_string(),
+        })?;
+        serde_json::from_str(&content).map_err(|e| ApiError::Fixture {
+            path,
+            error: e.to_string(),
+        })
+    }
+
+    pub fn save<T: Serialize>(&self, name: &str, value: &T) -> Result<(), ApiError> {
+        let path = self.path(name);
+        let fixture_error = |error: String| ApiError::Fixture { path: path.clone(), error };
+        fs::create_dir_all(&self.dir).map_err(|e| fixture_error(e.to_string()))?;
+        let content = serde_json::to_string_pretty(value).map_err(|e| fixture_error(e.to_string()))?;
+        fs::write(&path, content).map_err(|e| fixture_error(e.to_string()))
+    }
+
+    fn path(&self, name: &str) -> PathBuf {
+        self.dir.join(format!("{}.json", name))
+    }
+}
+
+fn positions_fixture(address: &str) -> String {
+    format!("positions_{}", address)
+}
+
+/// Passes requests through to another provider and saves every response
+pub struct RecordingProvider {
+    inner: Box<dyn PortfolioProvider>,
+    store: FixtureStore,
+}
+
+impl RecordingProvider {
+    pub fn new(inner: Box<dyn PortfolioProvider>, dir: &Path) -> Self {
+        Self {
+            inner,
+            store: FixtureStore::new(dir),
+        }
+    }
+}
+
+#[async_trait]
+impl PortfolioProvider for RecordingProvider {
+    async fn get_positions(&self, address: &str) -> Result<PortfolioSummary, ApiError> {
+        let summary = self.inner.get_positions(address).await?;
+        self.store.save(&positions_fixture(address), &summary)?;
+        Ok(summary)
+    }
+
+    async fn get_top_market_caps(&self) -> Result<Vec<MarketCapToken>, ApiError> {
+        let tokens = self.inner.get_top_market_caps().await?;
+        self.store.save(MARKET_CAPS_FIXTURE, &tokens)?;
+        Ok(tokens)
+    }
+
+    async fn get_token_prices(&self, units: &[String]) -> Result<HashMap<String, f64>, ApiError> {
+        let prices = self.inner.get_token_prices(units).await?;
+        // Accumulate across calls so replay can answer any previously seen unit
+        let mut recorded: HashMap<String, f64> = self.store.load(TOKEN_PRICES_FIXTURE).unwrap_or_default();
+        recorded.extend(prices.clone());
+        self.store.save(TOKEN_PRICES_FIXTURE, &recorded)?;
+        Ok(prices)
+    }
+}
+
+/// Answers every request from fixtures written by `RecordingProvider`
+pub struct ReplayProvider {
+    store: FixtureStore,
+}
+
+impl ReplayProvider {
+    pub fn new(dir: &Path) -> Self {
+        Self {
+            store: FixtureStore::new(dir),
+        }
+    }
+}
+
+#[async_trait]
+impl PortfolioProvider for ReplayProvider {
+    async fn get_positions(&self, address: &str) -> Result<PortfolioSummary, ApiError> {
+        self.store.load(&positions_fixture(address))
+    }
+
+    async fn get_top_market_caps(&self) -> Result<Vec<MarketCapToken>, ApiError> {
+        self.store.load(MARKET_CAPS_FIXTURE)
+    }
+
+    async fn get_token_prices(&self, units: &[String]) -> Result<HashMap<String, f64>, ApiError> {
+        let recorded: HashMap<String, f64> = self.store.load(TOKEN_PRICES_FIXTURE)?;
+        Ok(recorded.into_iter()
+            .filter(|(unit, _)| units.contains(unit))
+            .collect())
+    }
+}
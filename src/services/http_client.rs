@@ -0,0 +1,75 @@
+use std::time::Duration;
+use rand::Rng;
+use reqwest::{Client, RequestBuilder, Response, StatusCode};
+use crate::services::api_error::{ApiError, retry_after_seconds};
+
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(10);
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(30);
+const MAX_RETRIES: u32 = 3;
+const BASE_RETRY_DELAY: Duration = Duration::from_millis(500);
+const MAX_RETRY_DELAY: Duration = Duration::from_secs(30);
+
+/// Shared HTTP client. Cloning is cheap and reuses the same connection pool.
+#[derive(Debug, Clone)]
+pub struct HttpClient {
+    client: Client,
+    max_retries: u32,
+}
+
+impl Default for HttpClient {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl HttpClient {
+    pub fn new() -> Self {
+        let client = Client::builder()
+            .connect_timeout(CONNECT_TIMEOUT)
+            .timeout(REQUEST_TIMEOUT)
+            .build()
+            .expect("Failed to build HTTP client");
+
+        Self {
+            client,
+            max_retries: MAX_RETRIES,
+        }
+    }
+
+    /// Sends the request built by `build`, retrying 5xx and 429 responses
+    /// with jittered exponential backoff. A `Retry-After` header takes
+    /// precedence over the computed delay.
+    pub async fn send<F>(&self, build: F) -> Result<Response, ApiError>
+    where
+        F: Fn(&Client) -> RequestBuilder,
+    {
+        let mut attempt = 0;
+        loop {
+            let response = build(&self.client).send().await?;
+            let status = response.status();
+            let retryable = status.is_server_error() || status == StatusCode::TOO_MANY_REQUESTS;
+
+            if !retryable || attempt >= self.max_retries {
+                return Ok(response);
+            }
+
+            let retry_after = retry_after_seconds(&response).map(Duration::from_secs);
+            let jitter = rand::thread_rng().gen_range(0.0..1.0);
+
+            tokio::time::sleep(retry_delay(attempt, retry_after, jitter)).await;
+            attempt += 1;
+        }
+    }
+}
+
+/// Backoff before retry number `attempt + 1`. `jitter` is in `[0, 1)` and
+/// adds up to one extra base interval so concurrent clients spread out.
+pub fn retry_delay(attempt: u32, retry_after: Option<Duration>, jitter: f64) -> Duration {
+    if let Some(delay) = retry_after {
+        return delay.min(MAX_RETRY_DELAY);
+    }
+
+    let backoff = BASE_RETRY_DELAY.saturating_mul(2u32.saturating_pow(attempt));
+    let jitter = BASE_RETRY_DELAY.mul_f64(jitter);
+    (backoff + jitter).min(MAX_RETRY_DELAY)
+}
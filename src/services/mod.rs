@@ -1,3 +1,10 @@
+pub mod api_error;
+pub mod fixtures;
+pub mod http_client;
 pub mod portfolio_api;
+pub mod portfolio_provider;
 pub mod user_service;
 pub mod price;
+
+#[cfg(test)]
+mod tests;
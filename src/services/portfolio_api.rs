@@ -1,58 +1,107 @@
-use reqwest::Client;
-use serde::{Deserialize, Serialize};
-use crate::models::market_cap_token::MarketCapToken;
+use std::collections::HashMap;
+use async_trait::async_trait;
+use crate::models::{market_cap_token::MarketCapToken, portfolio_summary::PortfolioSummary};
+use crate::services::api_error::{ApiError, read_body, parse_body};
+use crate::services::portfolio_provider::PortfolioProvider;
+use crate::services::http_client::HttpClient;
+use crate::config::DEFAULT_TAPTOOLS_URL;
 
-const PORTFOLIO_API_HOST: &str = "https://openapi.taptools.io/api/v1";
 const WALLET_POSITIONS_URL: &str = "/wallet/portfolio/positions";
 const MARKET_CAP_URL: &str = "/token/top/mcap";
+const TOKEN_PRICES_URL: &str = "/token/prices";
 
-#[derive(Debug, Serialize, Deserialize)]
+/// Taptools implementation of `PortfolioProvider`
+#[derive(Debug)]
 pub struct PortfolioApiConfig {
     pub api_key: String,
+    pub base_url: String,
+    http: HttpClient,
 }
 
 impl PortfolioApiConfig {
     pub fn new(api_key: &str) -> Self {
+        Self::with_client(api_key, DEFAULT_TAPTOOLS_URL, HttpClient::new())
+    }
+
+    pub fn with_client(api_key: &str, base_url: &str, http: HttpClient) -> Self {
         Self {
             api_key: api_key.to_string(),
+            base_url: base_url.to_string(),
+            http,
         }
     }
 
     // Fetch portfolio data
-    pub async fn get_portfolio_data(&self, address: &str) -> Result<String, reqwest::Error> {
+    pub async fn get_portfolio_data(&self, address: &str) -> Result<PortfolioSummary, ApiError> {
         let response = self.make_portfolio_api_request(address).await?;
-        let text = response.text().await?;
-        Ok(text)
+        let body = read_body(response).await?;
+        parse_body(&body)
     }
 
     // Internal function to make PortfolioApi API requests
-    async fn make_portfolio_api_request(&self, address: &str) -> Result<reqwest::Response, reqwest::Error> {
-        let url = format!("{}{}?address={}", PORTFOLIO_API_HOST, WALLET_POSITIONS_URL, address);
+    async fn make_portfolio_api_request(&self, address: &str) -> Result<reqwest::Response, ApiError> {
+        let url = format!("{}{}?address={}", self.base_url, WALLET_POSITIONS_URL, address);
         
-        let client = Client::new();
-        let response = client
-            .get(&url)
-            .header("x-api-key", &self.api_key)
-            .header("Content-Type", "application/json")
-            .header("Accept", "application/json")
-            .send()
+        let response = self.http
+            .send(|client| {
+                client
+                    .get(&url)
+                    .header("x-api-key", &self.api_key)
+                    .header("Content-Type", "application/json")
+                    .header("Accept", "application/json")
+            })
             .await?;
         
         Ok(response)
     }
 
-    pub async fn get_market_cap_data(&self) -> Result<Vec<MarketCapToken>, reqwest::Error> {
-        let url = format!("{}{}", PORTFOLIO_API_HOST, MARKET_CAP_URL);
+    pub async fn get_market_cap_data(&self) -> Result<Vec<MarketCapToken>, ApiError> {
+        let url = format!("{}{}", self.base_url, MARKET_CAP_URL);
         
-        let client = Client::new();
-        let response = client
-            .get(&url)
-            .header("x-api-key", &self.api_key)
-            .header("Content-Type", "application/json")
-            .header("Accept", "application/json")
-            .send()
+        let response = self.http
+            .send(|client| {
+                client
+                    .get(&url)
+                    .header("x-api-key", &self.api_key)
+                    .header("Content-Type", "application/json")
+                    .header("Accept", "application/json")
+            })
             .await?;
             
-        response.json::<Vec<MarketCapToken>>().await
+        let body = read_body(response).await?;
+        parse_body(&body)
+    }
+
+    pub async fn get_token_prices(&self, units: &[String]) -> Result<HashMap<String, f64>, ApiError> {
+        let url = format!("{}{}", self.base_url, TOKEN_PRICES_URL);
+
+        let response = self.http
+            .send(|client| {
+                client
+                    .post(&url)
+                    .header("x-api-key", &self.api_key)
+                    .header("Content-Type", "application/json")
+                    .header("Accept", "application/json")
+                    .json(units)
+            })
+            .await?;
+
+        let body = read_body(response).await?;
+        parse_body(&body)
+    }
+}
+
+#[async_trait]
+impl PortfolioProvider for PortfolioApiConfig {
+    async fn get_positions(&self, address: &str) -> Result<PortfolioSummary, ApiError> {
+        self.get_portfolio_data(address).await
+    }
+
+    async fn get_top_market_caps(&self) -> Result<Vec<MarketCapToken>, ApiError> {
+        self.get_market_cap_data().await
+    }
+
+    async fn get_token_prices(&self, units: &[String]) -> Result<HashMap<String, f64>, ApiError> {
+        PortfolioApiConfig::get_token_prices(self, units).await
     }
 }
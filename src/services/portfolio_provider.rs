@@ -0,0 +1,18 @@
+use std::collections::HashMap;
+use async_trait::async_trait;
+use crate::models::{market_cap_token::MarketCapToken, portfolio_summary::PortfolioSummary};
+use crate::services::api_error::ApiError;
+
+/// Source of on-chain portfolio and market data. Taptools is the default
+/// implementation; other indexers or offline fixtures can be swapped in.
+#[async_trait]
+pub trait PortfolioProvider: Send + Sync {
+    /// Positions held by a single payment or stake address
+    async fn get_positions(&self, address: &str) -> Result<PortfolioSummary, ApiError>;
+
+    /// Top tokens ranked by market cap
+    async fn get_top_market_caps(&self) -> Result<Vec<MarketCapToken>, ApiError>;
+
+    /// Current ADA price of each token, keyed by `unit`
+    async fn get_token_prices(&self, units: &[String]) -> Result<HashMap<String, f64>, ApiError>;
+}
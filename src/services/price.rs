@@ -1,57 +1,271 @@
+use std::collections::{BTreeSet, HashMap};
+use std::fmt;
+use std::str::FromStr;
+use chrono::{NaiveDate, TimeDelta};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use serde::{Deserialize, Serialize};
 use serde_json::Value;
+use crate::services::api_error::{ApiError, read_body, parse_body, snippet};
+use crate::services::http_client::HttpClient;
 
-pub async fn fetch_ada_price() -> Result<f64, Box<dyn std::error::Error>> {
-    let url = "https://api.coingecko.com/api/v3/simple/price?ids=cardano&vs_currencies=usd&include_24h_change=true";
-    
-    let client = reqwest::Client::new();
-    let response = client
-        .get(url)
-        .header("User-Agent", "Mozilla/5.0")
-        .send()
-        .await?;
+const SIMPLE_PRICE_URL: &str = "/simple/price";
+const DAY_MILLIS: i64 = 24 * 60 * 60 * 1000;
+const MARKET_CHART_RANGE_URL: &str = "/coins/{id}/market_chart/range";
+const DEFAULT_TTL: Duration = Duration::from_secs(60);
 
-    if !response.status().is_success() {
-        return Err(format!("API request failed: {} - {}", response.status(), response.text().await?).into());
+pub const CARDANO_ID: &str = "cardano";
+pub const BITCOIN_ID: &str = "bitcoin";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum QuoteCurrency {
+    Usd,
+    Eur,
+    Gbp,
+    Jpy,
+    Btc,
+}
+
+impl QuoteCurrency {
+    pub const ALL: [QuoteCurrency; 5] = [
+        QuoteCurrency::Usd,
+        QuoteCurrency::Eur,
+        QuoteCurrency::Gbp,
+        QuoteCurrency::Jpy,
+        QuoteCurrency::Btc,
+    ];
+
+    /// CoinGecko `vs_currencies` code
+    pub fn code(&self) -> &'static str {
+        match self {
+            QuoteCurrency::Usd => "usd",
+            QuoteCurrency::Eur => "eur",
+            QuoteCurrency::Gbp => "gbp",
+            QuoteCurrency::Jpy => "jpy",
+            QuoteCurrency::Btc => "btc",
+        }
+    }
+
+    /// Parses a currency code in any case, e.g. `USD` or `eur`
+    pub fn from_code(code: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|currency| currency.code().eq_ignore_ascii_case(code.trim()))
+    }
+}
+
+impl fmt::Display for QuoteCurrency {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.code().to_uppercase())
+    }
+}
+
+impl FromStr for QuoteCurrency {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::from_code(s).ok_or_else(|| format!("Unknown currency '{}'", s))
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Quote {
+    pub price: f64,
+    /// Percent change over the last 24 hours, e.g. `1.5` for +1.5%
+    pub change_24h: Option<f64>,
+}
+
+/// Quotes keyed by CoinGecko id, then by quote currency
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Prices {
+    quotes: HashMap<String, HashMap<QuoteCurrency, Quote>>,
+}
+
+impl Prices {
+    pub fn get(&self, id: &str, currency: QuoteCurrency) -> Option<Quote> {
+        self.quotes.get(id).and_then(|quotes| quotes.get(&currency)).copied()
+    }
+
+    pub fn price(&self, id: &str, currency: QuoteCurrency) -> f64 {
+        self.get(id, currency).map(|quote| quote.price).unwrap_or_default()
+    }
+
+    pub fn insert(&mut self, id: &str, currency: QuoteCurrency, quote: Quote) {
+        self.quotes.entry(id.to_string()).or_default().insert(currency, quote);
     }
 
-    let text = response.text().await?;
+    /// Parses a CoinGecko `/simple/price` body requested with `include_24h_change=true`
+    pub fn from_simple_price(body: &str, ids: &[&str], currencies: &[QuoteCurrency]) -> Result<Self, ApiError> {
+        let json: Value = parse_body(body)?;
+        let mut prices = Prices::default();
+        for id in ids {
+            for currency in currencies {
+                let coin = json.get(*id);
+                let price = coin
+                    .and_then(|c| c.get(currency.code()))
+                    .and_then(|p| p.as_f64())
+                    .ok_or_else(|| ApiError::MalformedBody {
+                        error: format!("missing {} price for {}", currency, id),
+                        snippet: snippet(body),
+                    })?;
+                let change_24h = coin
+                    .and_then(|c| c.get(format!("{}_24h_change", currency.code())))
+                    .and_then(|p| p.as_f64());
+                prices.insert(id, *currency, Quote { price, change_24h });
+            }
+        }
+        Ok(prices)
+    }
+}
+
+/// Parses a CoinGecko `/coins/{id}/market_chart/range` body into the price
+/// closest to the start (UTC) of each of `dates`
+pub fn parse_daily_prices(body: &str, dates: &BTreeSet<NaiveDate>) -> Result<HashMap<NaiveDate, f64>, ApiError> {
+    let json: Value = parse_body(body)?;
+    let malformed = |error: String| ApiError::MalformedBody { error, snippet: snippet(body) };
+    let points = json.get("prices")
+        .and_then(Value::as_array)
+        .ok_or_else(|| malformed("missing prices".to_string()))?
+        .iter()
+        .map(|point| match point.as_array().map(Vec::as_slice) {
+            Some([timestamp, price]) => timestamp.as_i64().zip(price.as_f64()),
+            _ => None,
+        })
+        .collect::<Option<Vec<(i64, f64)>>>()
+        .ok_or_else(|| malformed("prices are not [timestamp, price] pairs".to_string()))?;
+
+    let mut rates = HashMap::new();
+    for date in dates {
+        let start = date.and_hms_opt(0, 0, 0).expect("midnight exists").and_utc().timestamp_millis();
+        // Daily points sit at midnight, so one further than a day away means
+        // the range has no price for this date
+        let (_, price) = points.iter()
+            .min_by_key(|(timestamp, _)| (timestamp - start).abs())
+            .filter(|(timestamp, _)| (timestamp - start).abs() < DAY_MILLIS)
+            .ok_or_else(|| malformed(format!("no price for {}", date)))?;
+        rates.insert(*date, *price);
+    }
+    Ok(rates)
+}
 
-    let json: Value = serde_json::from_str(&text)?;
-    match json.get("cardano").and_then(|c| c.get("usd")).and_then(|p| p.as_f64()) {
-        Some(price) => {
-            Ok(price)
-        },
-        None => {
-            println!("Failed to parse price from JSON"); // Debug print
-            Err("Failed to parse ADA price from response".into())
+/// CoinGecko price lookups with a per-quote TTL cache. Past prices never
+/// change, so they are cached for as long as the service lives.
+#[derive(Debug)]
+pub struct PriceService {
+    http: HttpClient,
+    base_url: String,
+    ttl: Duration,
+    cache: Mutex<HashMap<(String, QuoteCurrency), (Instant, Quote)>>,
+    historical: Mutex<HashMap<(String, NaiveDate, QuoteCurrency), f64>>,
+}
+
+impl PriceService {
+    pub fn new(http: HttpClient, base_url: &str) -> Self {
+        Self::with_ttl(http, base_url, DEFAULT_TTL)
+    }
+
+    pub fn with_ttl(http: HttpClient, base_url: &str, ttl: Duration) -> Self {
+        Self {
+            http,
+            base_url: base_url.to_string(),
+            ttl,
+            cache: Mutex::new(HashMap::new()),
+            historical: Mutex::new(HashMap::new()),
         }
     }
-} 
 
-pub async fn fetch_btc_price() -> Result<f64, Box<dyn std::error::Error>> {
-    let url = "https://api.coingecko.com/api/v3/simple/price?ids=bitcoin&vs_currencies=usd&include_24h_change=true";
-    
-    let client = reqwest::Client::new();
-    let response = client
-        .get(url)
-        .header("User-Agent", "Mozilla/5.0")
-        .send()
-        .await?;
+    /// Fetches every id in every currency with a single request, unless all
+    /// of them are already cached and younger than the TTL.
+    pub async fn fetch(&self, ids: &[&str], currencies: &[QuoteCurrency]) -> Result<Prices, ApiError> {
+        if let Some(prices) = self.cached(ids, currencies) {
+            return Ok(prices);
+        }
+
+        let vs_currencies = currencies.iter().map(|c| c.code()).collect::<Vec<_>>().join(",");
+        let url = format!(
+            "{}{}?ids={}&vs_currencies={}&include_24h_change=true",
+            self.base_url, SIMPLE_PRICE_URL, ids.join(","), vs_currencies
+        );
 
-    if !response.status().is_success() {
-        return Err(format!("API request failed: {} - {}", response.status(), response.text().await?).into());
+        let response = self.http
+            .send(|client| client.get(&url).header("User-Agent", "Mozilla/5.0"))
+            .await?;
+        let body = read_body(response).await?;
+        let prices = Prices::from_simple_price(&body, ids, currencies)?;
+
+        self.store(&prices);
+        Ok(prices)
     }
 
-    let text = response.text().await?;
+    /// Prices of `id` in `currency` at the start of each of `dates`, fetched
+    /// with a single range request for the dates not cached yet
+    pub async fn fetch_historical_range(
+        &self,
+        id: &str,
+        dates: &BTreeSet<NaiveDate>,
+        currency: QuoteCurrency,
+    ) -> Result<HashMap<NaiveDate, f64>, ApiError> {
+        let mut rates = HashMap::new();
+        let mut missing = BTreeSet::new();
+        {
+            let historical = self.historical.lock().unwrap();
+            for date in dates {
+                match historical.get(&(id.to_string(), *date, currency)) {
+                    Some(price) => { rates.insert(*date, *price); },
+                    None => { missing.insert(*date); },
+                }
+            }
+        }
+        let (Some(first), Some(last)) = (missing.first(), missing.last()) else {
+            return Ok(rates);
+        };
+
+        // An hour either side so the first and last midnight have a point near them
+        let from = first.and_hms_opt(0, 0, 0).expect("midnight exists").and_utc() - TimeDelta::hours(1);
+        let to = last.and_hms_opt(0, 0, 0).expect("midnight exists").and_utc() + TimeDelta::hours(1);
+        let url = format!(
+            "{}{}?vs_currency={}&from={}&to={}",
+            self.base_url,
+            MARKET_CHART_RANGE_URL.replace("{id}", id),
+            currency.code(),
+            from.timestamp(),
+            to.timestamp(),
+        );
+        let response = self.http
+            .send(|client| client.get(&url).header("User-Agent", "Mozilla/5.0"))
+            .await?;
+        let body = read_body(response).await?;
+        let fetched = parse_daily_prices(&body, &missing)?;
+
+        let mut historical = self.historical.lock().unwrap();
+        for (date, price) in fetched {
+            historical.insert((id.to_string(), date, currency), price);
+            rates.insert(date, price);
+        }
+        Ok(rates)
+    }
+
+    fn store(&self, prices: &Prices) {
+        let now = Instant::now();
+        let mut cache = self.cache.lock().unwrap();
+        for (id, quotes) in &prices.quotes {
+            for (currency, quote) in quotes {
+                cache.insert((id.clone(), *currency), (now, *quote));
+            }
+        }
+    }
 
-    let json: Value = serde_json::from_str(&text)?;
-    match json.get("bitcoin").and_then(|c| c.get("usd")).and_then(|p| p.as_f64()) {
-        Some(price) => {
-            Ok(price)
-        },
-        None => {
-            println!("Failed to parse price from JSON"); // Debug print
-            Err("Failed to parse BTC price from response".into())
+    fn cached(&self, ids: &[&str], currencies: &[QuoteCurrency]) -> Option<Prices> {
+        let cache = self.cache.lock().unwrap();
+        let mut prices = Prices::default();
+        for id in ids {
+            for currency in currencies {
+                let (fetched_at, quote) = cache.get(&(id.to_string(), *currency))?;
+                if fetched_at.elapsed() > self.ttl {
+                    return None;
+                }
+                prices.insert(id, *currency, *quote);
+            }
         }
+        Some(prices)
     }
-}
\ No newline at end of file
+}
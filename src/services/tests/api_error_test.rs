@@ -0,0 +1,48 @@
+use crate::models::portfolio_summary::PortfolioSummary;
+use crate::services::api_error::{ApiError, parse_body};
+
+#[test]
+fn test_parse_body_valid() {
+    let body = r#"{
+        "adaBalance": 10.0,
+        "adaValue": 12.0,
+        "liquidValue": 11.0,
+        "numFTs": 0,
+        "numNFTs": 0,
+        "positionsFt": [],
+        "positionsNft": [],
+        "positionsLp": []
+    }"#;
+
+    let summary: PortfolioSummary = parse_body(body).unwrap();
+    assert_eq!(summary.ada_value, 12.0);
+}
+
+#[test]
+fn test_parse_body_malformed_keeps_snippet() {
+    let body = r#"{"error": "upstream timeout"}"#;
+
+    match parse_body::<PortfolioSummary>(body) {
+        Err(ApiError::MalformedBody { snippet, .. }) => assert_eq!(snippet, body),
+        other => panic!("Expected MalformedBody, got {:?}", other),
+    }
+}
+
+#[test]
+fn test_parse_body_truncates_long_snippet() {
+    let body = "x".repeat(1000);
+
+    match parse_body::<PortfolioSummary>(&body) {
+        Err(ApiError::MalformedBody { snippet, .. }) => {
+            assert!(snippet.len() < body.len());
+            assert!(snippet.ends_with("..."));
+        },
+        other => panic!("Expected MalformedBody, got {:?}", other),
+    }
+}
+
+#[test]
+fn test_rate_limited_display() {
+    let error = ApiError::RateLimited { retry_after: Some(30) };
+    assert_eq!(error.to_string(), "Rate limited, retry in 30s");
+}
@@ -0,0 +1,72 @@
+use std::collections::HashMap;
+use std::fs;
+use std::path::PathBuf;
+use async_trait::async_trait;
+use uuid::Uuid;
+use crate::models::{market_cap_token::MarketCapToken, portfolio_summary::PortfolioSummary};
+use crate::services::api_error::ApiError;
+use crate::services::fixtures::{RecordingProvider, ReplayProvider};
+use crate::services::portfolio_provider::PortfolioProvider;
+
+// Provider that answers with fixed data, standing in for Taptools
+struct LiveProvider;
+
+#[async_trait]
+impl PortfolioProvider for LiveProvider {
+    async fn get_positions(&self, _address: &str) -> Result<PortfolioSummary, ApiError> {
+        Ok(PortfolioSummary {
+            ada_balance: 42.0,
+            ..Default::default()
+        })
+    }
+
+    async fn get_top_market_caps(&self) -> Result<Vec<MarketCapToken>, ApiError> {
+        Ok(vec![MarketCapToken {
+            circ_supply: 1.0,
+            fdv: 2.0,
+            mcap: 3.0,
+            price: 4.0,
+            ticker: "TKN".to_string(),
+            total_supply: 5.0,
+            unit: "unit_a".to_string(),
+        }])
+    }
+
+    async fn get_token_prices(&self, units: &[String]) -> Result<HashMap<String, f64>, ApiError> {
+        Ok(units.iter().map(|unit| (unit.clone(), 1.5)).collect())
+    }
+}
+
+fn fixture_dir() -> PathBuf {
+    std::env::temp_dir().join(format!("fixtures_test_{}", Uuid::new_v4()))
+}
+
+#[tokio::test]
+async fn test_record_then_replay() {
+    let dir = fixture_dir();
+
+    let recorder = RecordingProvider::new(Box::new(LiveProvider), &dir);
+    recorder.get_positions("addr_a").await.unwrap();
+    recorder.get_top_market_caps().await.unwrap();
+    recorder.get_token_prices(&["unit_a".to_string()]).await.unwrap();
+    recorder.get_token_prices(&["unit_b".to_string()]).await.unwrap();
+
+    let replay = ReplayProvider::new(&dir);
+    assert_eq!(replay.get_positions("addr_a").await.unwrap().ada_balance, 42.0);
+    assert_eq!(replay.get_top_market_caps().await.unwrap()[0].ticker, "TKN");
+
+    let prices = replay.get_token_prices(&["unit_a".to_string(), "unit_b".to_string()]).await.unwrap();
+    assert_eq!(prices.len(), 2);
+
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[tokio::test]
+async fn test_replay_missing_fixture() {
+    let replay = ReplayProvider::new(&fixture_dir());
+
+    match replay.get_positions("addr_missing").await {
+        Err(ApiError::Fixture { path, .. }) => assert!(path.ends_with("positions_addr_missing.json")),
+        other => panic!("Expected Fixture error, got {:?}", other),
+    }
+}
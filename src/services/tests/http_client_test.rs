@@ -0,0 +1,25 @@
+use std::time::Duration;
+use crate::services::http_client::retry_delay;
+
+#[test]
+fn test_retry_delay_grows_exponentially() {
+    assert_eq!(retry_delay(0, None, 0.0), Duration::from_millis(500));
+    assert_eq!(retry_delay(1, None, 0.0), Duration::from_millis(1000));
+    assert_eq!(retry_delay(2, None, 0.0), Duration::from_millis(2000));
+}
+
+#[test]
+fn test_retry_delay_adds_jitter() {
+    assert_eq!(retry_delay(0, None, 0.5), Duration::from_millis(750));
+}
+
+#[test]
+fn test_retry_delay_is_capped() {
+    assert_eq!(retry_delay(20, None, 0.9), Duration::from_secs(30));
+}
+
+#[test]
+fn test_retry_delay_honours_retry_after() {
+    assert_eq!(retry_delay(0, Some(Duration::from_secs(7)), 0.9), Duration::from_secs(7));
+    assert_eq!(retry_delay(0, Some(Duration::from_secs(600)), 0.0), Duration::from_secs(30));
+}
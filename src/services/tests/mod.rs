@@ -0,0 +1,10 @@
+#[cfg(test)]
+mod api_error_test;
+#[cfg(test)]
+mod fixtures_test;
+#[cfg(test)]
+mod http_client_test;
+#[cfg(test)]
+mod price_test;
+#[cfg(test)]
+mod user_service_test;
@@ -0,0 +1,74 @@
+use std::collections::BTreeSet;
+use chrono::NaiveDate;
+use crate::services::api_error::ApiError;
+use crate::services::price::{parse_daily_prices, Prices, QuoteCurrency, BITCOIN_ID, CARDANO_ID};
+
+const SIMPLE_PRICE_BODY: &str = r#"{
+    "cardano": { "usd": 0.45, "usd_24h_change": 2.5, "eur": 0.41, "eur_24h_change": 2.1 },
+    "bitcoin": { "usd": 65000.0, "usd_24h_change": -1.25, "eur": 60000.0 }
+}"#;
+
+#[test]
+fn test_from_simple_price_reads_every_id_and_currency() {
+    let currencies = [QuoteCurrency::Usd, QuoteCurrency::Eur];
+    let prices = Prices::from_simple_price(SIMPLE_PRICE_BODY, &[CARDANO_ID, BITCOIN_ID], &currencies).unwrap();
+
+    let ada_usd = prices.get(CARDANO_ID, QuoteCurrency::Usd).unwrap();
+    assert_eq!(ada_usd.price, 0.45);
+    assert_eq!(ada_usd.change_24h, Some(2.5));
+
+    assert_eq!(prices.price(BITCOIN_ID, QuoteCurrency::Eur), 60000.0);
+    assert_eq!(prices.get(BITCOIN_ID, QuoteCurrency::Eur).unwrap().change_24h, None);
+    assert!(prices.get(CARDANO_ID, QuoteCurrency::Jpy).is_none());
+}
+
+#[test]
+fn test_from_simple_price_missing_currency() {
+    let result = Prices::from_simple_price(SIMPLE_PRICE_BODY, &[CARDANO_ID], &[QuoteCurrency::Gbp]);
+
+    assert!(matches!(result, Err(ApiError::MalformedBody { .. })));
+}
+
+#[test]
+fn test_prices_round_trip_through_json() {
+    let prices = Prices::from_simple_price(SIMPLE_PRICE_BODY, &[CARDANO_ID], &[QuoteCurrency::Usd]).unwrap();
+
+    let json = serde_json::to_string(&prices).unwrap();
+    let restored: Prices = serde_json::from_str(&json).unwrap();
+
+    assert_eq!(restored, prices);
+}
+
+#[test]
+fn test_parse_daily_prices_takes_the_point_nearest_midnight() {
+    // 2024-01-01 00:00 UTC is 1704067200000 ms
+    let body = r#"{"prices": [
+        [1704063600000, 0.58],
+        [1704067260000, 0.59],
+        [1704153600000, 0.61],
+        [1704157200000, 0.62]
+    ]}"#;
+    let dates = BTreeSet::from([
+        NaiveDate::from_ymd_opt(2024, 1, 1).unwrap(),
+        NaiveDate::from_ymd_opt(2024, 1, 2).unwrap(),
+    ]);
+
+    let rates = parse_daily_prices(body, &dates).unwrap();
+    assert_eq!(rates[&NaiveDate::from_ymd_opt(2024, 1, 1).unwrap()], 0.59);
+    assert_eq!(rates[&NaiveDate::from_ymd_opt(2024, 1, 2).unwrap()], 0.61);
+}
+
+#[test]
+fn test_parse_daily_prices_without_points() {
+    let dates = BTreeSet::from([NaiveDate::from_ymd_opt(2024, 1, 1).unwrap()]);
+
+    assert!(matches!(
+        parse_daily_prices(r#"{"prices": []}"#, &dates),
+        Err(ApiError::MalformedBody { .. })
+    ));
+    // A week later is no price for the first of January
+    assert!(matches!(
+        parse_daily_prices(r#"{"prices": [[1704672000000, 0.5]]}"#, &dates),
+        Err(ApiError::MalformedBody { .. })
+    ));
+}
@@ -0,0 +1,208 @@
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::sync::Arc;
+use async_trait::async_trait;
+use chrono::NaiveDate;
+use uuid::Uuid;
+use crate::config::AppConfig;
+use crate::db::Database;
+use crate::models::{market_cap_token::MarketCapToken, portfolio_summary::PortfolioSummary, user::User};
+use crate::services::api_error::ApiError;
+use crate::services::fixtures::{FixtureMode, FixtureStore};
+use crate::services::portfolio_provider::PortfolioProvider;
+use crate::services::price::QuoteCurrency;
+use crate::services::user_service::UserService;
+
+// Provider that reports a fixed ADA balance per address
+struct StaticProvider {
+    balances: HashMap<String, f64>,
+}
+
+#[async_trait]
+impl PortfolioProvider for StaticProvider {
+    async fn get_positions(&self, address: &str) -> Result<PortfolioSummary, ApiError> {
+        let balance = self.balances.get(address).copied().unwrap_or_default();
+        Ok(PortfolioSummary {
+            ada_balance: balance,
+            ada_value: balance,
+            liquid_value: balance,
+            ..Default::default()
+        })
+    }
+
+    async fn get_top_market_caps(&self) -> Result<Vec<MarketCapToken>, ApiError> {
+        Ok(Vec::new())
+    }
+
+    async fn get_token_prices(&self, _units: &[String]) -> Result<HashMap<String, f64>, ApiError> {
+        Ok(HashMap::new())
+    }
+}
+
+const PASSPHRASE: &str = "correct horse battery staple";
+const BASE_ADDRESS: &str = "addr1qx2fxv2umyhttkxyxp8x0dlpdt3k6cwng5pxj3jhsydzer3n0d3vllmyqwsx5wktcd8cc3sq835lu7drv2xwl2wywfgse35a3x";
+const STAKE_ADDRESS: &str = "stake1uyehkck0lajq8gr28t9uxnuvgcqrc6070x3k9r8048z8y5gh6ffgw";
+
+fn create_test_service() -> (UserService, String) {
+    // A file of its own, since wallet changes are saved straight away
+    let path = std::env::temp_dir().join(format!("user_service_{}.json", Uuid::new_v4()));
+    let mut database = Database::at(path);
+    let user_id = database.create_user("Test User".to_string(), "test_key".to_string(), PASSPHRASE)
+        .unwrap();
+    database.add_wallet(&user_id, "Hardware".to_string(), vec!["addr_a".to_string(), "addr_b".to_string()])
+        .unwrap();
+    database.add_wallet(&user_id, "Hot".to_string(), vec!["addr_c".to_string()])
+        .unwrap();
+
+    let service = UserService::with_provider_factory(database, Arc::new(|_: &User| {
+        let balances = HashMap::from([
+            ("addr_a".to_string(), 10.0),
+            ("addr_b".to_string(), 20.0),
+            ("addr_c".to_string(), 5.0),
+            (BASE_ADDRESS.to_string(), 1.0),
+            (STAKE_ADDRESS.to_string(), 100.0),
+        ]);
+        Box::new(StaticProvider { balances }) as Box<dyn PortfolioProvider>
+    }));
+
+    (service, user_id)
+}
+
+#[tokio::test]
+async fn test_fetch_portfolio_data_requires_login() {
+    let (service, _) = create_test_service();
+
+    assert!(service.fetch_portfolio_data().await.is_err());
+}
+
+#[tokio::test]
+async fn test_fetch_portfolio_data_aggregates_all_wallets() {
+    let (service, user_id) = create_test_service();
+    service.login(&user_id, PASSPHRASE).await.unwrap();
+
+    let portfolio = service.fetch_portfolio_data().await.unwrap();
+
+    assert_eq!(portfolio.wallets.len(), 2);
+    assert_eq!(portfolio.wallets[0].wallet_name, "Hardware");
+    assert_eq!(portfolio.wallets[0].summary.ada_balance, 30.0);
+    assert_eq!(portfolio.wallets[1].summary.ada_balance, 5.0);
+    assert_eq!(portfolio.total.ada_balance, 35.0);
+}
+
+#[tokio::test]
+async fn test_login_unlocks_api_key() {
+    let (service, user_id) = create_test_service();
+
+    assert!(service.login(&user_id, "wrong passphrase").await.is_err());
+    assert!(service.get_current_user().await.is_none());
+
+    service.login(&user_id, PASSPHRASE).await.unwrap();
+    let user = service.get_current_user().await.unwrap();
+    assert_eq!(user.taptools_api_key, "test_key");
+
+    // The stored copy stays encrypted
+    let database = service.get_database();
+    let stored = database.lock().await.get_user(&user_id).unwrap().clone();
+    assert!(stored.taptools_api_key.is_empty());
+    assert!(stored.encrypted_api_key.is_some());
+}
+
+#[tokio::test]
+async fn test_wallet_changes_are_saved_and_update_current_user() {
+    let (service, user_id) = create_test_service();
+    service.login(&user_id, PASSPHRASE).await.unwrap();
+
+    let wallets = service.add_wallet("Savings".to_string()).await.unwrap();
+    let savings_id = wallets[2].id.clone();
+    assert!(service.add_address(&savings_id, "addr_d".to_string()).await.is_err());
+    service.add_address(&savings_id, BASE_ADDRESS.to_string()).await.unwrap();
+    service.rename_wallet(&savings_id, "Vault".to_string()).await.unwrap();
+    let hot_id = wallets[1].id.clone();
+    let wallets = service.delete_wallet(&hot_id).await.unwrap();
+
+    let names: Vec<_> = wallets.iter().map(|wallet| wallet.name.as_str()).collect();
+    assert_eq!(names, vec!["Hardware", "Vault"]);
+    let current = service.get_current_user().await.unwrap();
+    assert_eq!(current.wallets[1].addresses, vec![BASE_ADDRESS]);
+    assert!(service.add_address(&savings_id, BASE_ADDRESS.to_string()).await.is_err());
+
+    let database = service.get_database();
+    let path = database.lock().await.path().to_path_buf();
+    let saved = Database::load(&path).unwrap();
+    let saved_names: Vec<_> = saved.get_user(&user_id).unwrap().wallets.iter()
+        .map(|wallet| wallet.name.clone())
+        .collect();
+    assert_eq!(saved_names, vec!["Hardware", "Vault"]);
+    std::fs::remove_file(&path).unwrap();
+}
+
+#[tokio::test]
+async fn test_get_users_keeps_api_keys_locked() {
+    let (service, user_id) = create_test_service();
+    service.get_database().lock().await
+        .create_user("Second User".to_string(), "second_key".to_string(), PASSPHRASE)
+        .unwrap();
+    service.login(&user_id, PASSPHRASE).await.unwrap();
+
+    let users = service.get_users().await;
+    assert_eq!(users.len(), 2);
+    assert!(users.iter().all(|user| user.taptools_api_key.is_empty()));
+}
+
+#[tokio::test]
+async fn test_registered_stake_address_replaces_its_payment_addresses() {
+    let (service, user_id) = create_test_service();
+    service.login(&user_id, PASSPHRASE).await.unwrap();
+    let hot_id = service.get_current_user().await.unwrap().wallets[1].id.clone();
+
+    service.add_address(&hot_id, BASE_ADDRESS.to_string()).await.unwrap();
+    let portfolio = service.fetch_portfolio_data().await.unwrap();
+    assert_eq!(portfolio.wallets[1].summary.ada_balance, 6.0);
+
+    let wallets = service.register_stake_address(&hot_id, BASE_ADDRESS).await.unwrap();
+    assert_eq!(wallets[1].addresses, vec!["addr_c", BASE_ADDRESS, STAKE_ADDRESS]);
+    // The stake address covers the base address, which is not counted twice
+    let portfolio = service.fetch_portfolio_data().await.unwrap();
+    assert_eq!(portfolio.wallets[1].summary.ada_balance, 105.0);
+
+    assert!(service.register_stake_address(&hot_id, STAKE_ADDRESS).await.is_err());
+    let enterprise = "addr1vx2fxv2umyhttkxyxp8x0dlpdt3k6cwng5pxj3jhsydzers66hrl8";
+    assert!(service.register_stake_address(&hot_id, enterprise).await.is_err());
+    std::fs::remove_file(service.get_database().lock().await.path()).unwrap();
+}
+
+#[tokio::test]
+async fn test_fetch_ada_rates_replays_recorded_rates() {
+    let dir = std::env::temp_dir().join(format!("user_service_rates_{}", Uuid::new_v4()));
+    let new_year = NaiveDate::from_ymd_opt(2024, 1, 1).unwrap();
+    let recorded = BTreeMap::from([(new_year, 0.59)]);
+    FixtureStore::new(&dir).save("ada_rates_eur", &recorded).unwrap();
+
+    let database = Database::at(dir.join("portfolio.json"));
+    let service = UserService::with_config(database, AppConfig::default(), FixtureMode::Replay(dir));
+
+    let rates = service.fetch_ada_rates(&BTreeSet::from([new_year]), QuoteCurrency::Eur).await.unwrap();
+    assert_eq!(rates, HashMap::from([(new_year, 0.59)]));
+
+    // A date the fixture does not cover is an error rather than a live request
+    let later = NaiveDate::from_ymd_opt(2024, 6, 1).unwrap();
+    assert!(service.fetch_ada_rates(&BTreeSet::from([later]), QuoteCurrency::Eur).await.is_err());
+    assert!(service.fetch_ada_rates(&BTreeSet::new(), QuoteCurrency::Eur).await.unwrap().is_empty());
+}
+
+#[tokio::test]
+async fn test_login_refuses_plain_text_key() {
+    let path = std::env::temp_dir().join(format!("user_service_{}.json", Uuid::new_v4()));
+    std::fs::write(&path, r#"{
+        "users": { "u1": { "id": "u1", "name": "alice", "taptools_api_key": "legacy-key", "wallets": [] } }
+    }"#).unwrap();
+    let service = UserService::with_provider_factory(Database::load(&path).unwrap(), Arc::new(|_: &User| {
+        Box::new(StaticProvider { balances: HashMap::new() }) as Box<dyn PortfolioProvider>
+    }));
+
+    // Whatever passphrase is entered, it never becomes the key's passphrase
+    assert!(service.login("u1", "typo").await.is_err());
+    assert!(service.get_current_user().await.is_none());
+    let database = service.get_database();
+    assert!(database.lock().await.get_user("u1").unwrap().encrypted_api_key.is_none());
+    std::fs::remove_file(&path).unwrap();
+}
@@ -1,57 +1,277 @@
-use crate::db::Database;
-use crate::models::user::User;
+use crate::config::{AppConfig, DEFAULT_COINGECKO_URL};
+use crate::db::{Database, history::{History, RetentionPolicy}};
+use crate::models::{address::CardanoAddress, display_currency::DisplayCurrency, user::User, wallet::Wallet};
 use crate::services::portfolio_api::PortfolioApiConfig;
+use crate::services::portfolio_provider::PortfolioProvider;
+use crate::services::fixtures::{FixtureMode, FixtureStore, RecordingProvider, ReplayProvider, ADA_RATES_FIXTURE, PRICES_FIXTURE};
+use crate::services::price::{PriceService, Prices, QuoteCurrency, BITCOIN_ID, CARDANO_ID};
+use crate::services::http_client::HttpClient;
 use crate::models::market_cap_token::MarketCapToken;
+use crate::models::portfolio_summary::PortfolioSummary;
+use crate::models::user_portfolio::{UserPortfolio, WalletPortfolio};
+use crate::models::portfolio_snapshot::PortfolioSnapshot;
+use chrono::{NaiveDate, Utc};
+use std::collections::{BTreeMap, BTreeSet, HashMap};
 use std::sync::Arc;
 use tokio::sync::Mutex;
 
+/// Builds the data provider for a user when they log in
+pub type ProviderFactory = Arc<dyn Fn(&User) -> Box<dyn PortfolioProvider> + Send + Sync>;
+
 #[derive(Clone)]
 pub struct UserService {
     database: Arc<Mutex<Database>>,
     current_user: Arc<Mutex<Option<User>>>,
-    portfolio_api: Arc<Mutex<Option<PortfolioApiConfig>>>,
+    portfolio_api: Arc<Mutex<Option<Box<dyn PortfolioProvider>>>>,
+    provider_factory: ProviderFactory,
+    fixture_mode: FixtureMode,
+    price_service: Arc<PriceService>,
+    history: Arc<Mutex<History>>,
+    retention: RetentionPolicy,
 }
 
 impl UserService {
     pub fn new(database: Database) -> Self {
+        Self::with_config(database, AppConfig::default(), FixtureMode::Live)
+    }
+
+    /// Uses `provider_factory` for portfolio data and live CoinGecko prices
+    pub fn with_provider_factory(database: Database, provider_factory: ProviderFactory) -> Self {
+        let price_service = PriceService::new(HttpClient::new(), DEFAULT_COINGECKO_URL);
+        Self::build(database, provider_factory, FixtureMode::Live, price_service)
+    }
+
+    /// Points the Taptools provider and price lookups at the configured
+    /// endpoints, recording or replaying fixtures according to `fixture_mode`.
+    /// Both share one HTTP client so connections are reused across refreshes.
+    pub fn with_config(database: Database, config: AppConfig, fixture_mode: FixtureMode) -> Self {
+        let http = HttpClient::new();
+        let price_service = PriceService::new(http.clone(), &config.coingecko_url);
+
+        let mode = fixture_mode.clone();
+        let provider_factory: ProviderFactory = Arc::new(move |user: &User| {
+            let live = Box::new(PortfolioApiConfig::with_client(
+                &user.taptools_api_key,
+                &config.taptools_url,
+                http.clone(),
+            ));
+            match &mode {
+                FixtureMode::Live => live as Box<dyn PortfolioProvider>,
+                FixtureMode::Record(dir) => Box::new(RecordingProvider::new(live, dir)),
+                FixtureMode::Replay(dir) => Box::new(ReplayProvider::new(dir)),
+            }
+        });
+
+        Self::build(database, provider_factory, fixture_mode, price_service)
+    }
+
+    fn build(
+        database: Database,
+        provider_factory: ProviderFactory,
+        fixture_mode: FixtureMode,
+        price_service: PriceService,
+    ) -> Self {
         Self {
             database: Arc::new(Mutex::new(database)),
             current_user: Arc::new(Mutex::new(None)),
             portfolio_api: Arc::new(Mutex::new(None)),
+            provider_factory,
+            fixture_mode,
+            price_service: Arc::new(price_service),
+            history: Arc::new(Mutex::new(History::new())),
+            retention: RetentionPolicy::default(),
+        }
+    }
+
+    /// Records snapshots into `history`, pruned according to `retention`
+    pub fn with_history(self, history: History, retention: RetentionPolicy) -> Self {
+        Self {
+            history: Arc::new(Mutex::new(history)),
+            retention,
+            ..self
         }
     }
 
-    pub async fn login(&self, user_id: &str) -> Result<(), Box<dyn std::error::Error>> {
+    /// Unlocks the user's API key with `passphrase` and builds their data
+    /// provider. A key still stored in plain text is refused; it has to be
+    /// encrypted with `Database::encrypt_api_key` first, under a passphrase
+    /// the user confirmed.
+    pub async fn login(&self, user_id: &str, passphrase: &str) -> Result<(), Box<dyn std::error::Error>> {
         let db = self.database.lock().await;
-        if let Some(user) = db.get_user(user_id) {
-            let mut current_user = self.current_user.lock().await;
-            let mut portfolio_api = self.portfolio_api.lock().await;
-            *portfolio_api = Some(PortfolioApiConfig::new(&user.taptools_api_key));
-            *current_user = Some(user.clone());
-            Ok(())
-        } else {
-            Err("User not found".into())
+        let mut user = db.get_user(user_id).ok_or("User not found")?.clone();
+        if user.encrypted_api_key.is_none() {
+            return Err(format!(
+                "The API key of {} is stored unencrypted; run `rotate-passphrase --user {}` to encrypt it",
+                user.name, user.name,
+            ).into());
         }
+        user.taptools_api_key = db.unlock_api_key(user_id, passphrase)?;
+
+        let mut current_user = self.current_user.lock().await;
+        let mut portfolio_api = self.portfolio_api.lock().await;
+        *portfolio_api = Some((self.provider_factory)(&user));
+        *current_user = Some(user);
+        Ok(())
     }
-    pub async fn fetch_portfolio_data(&self) -> Result<String, Box<dyn std::error::Error>> {
+    pub async fn fetch_portfolio_data(&self) -> Result<UserPortfolio, Box<dyn std::error::Error>> {
         let current_user = self.current_user.lock().await;
         let portfolio_api = self.portfolio_api.lock().await;
         
         if let (Some(user), Some(api)) = (current_user.as_ref(), portfolio_api.as_ref()) {
-            if let Some(wallet) = user.wallets.first() {
-                if let Some(address) = wallet.addresses.first() {
-                    return Ok(api.get_portfolio_data(address).await?);
+            if user.wallets.iter().all(|wallet| wallet.addresses.is_empty()) {
+                return Err("No wallet or address found".into());
+            }
+
+            let mut wallets = Vec::with_capacity(user.wallets.len());
+            for wallet in &user.wallets {
+                let mut summary = PortfolioSummary::default();
+                for address in wallet.tracked_addresses() {
+                    summary.merge(api.get_positions(address).await?);
                 }
+                wallets.push(WalletPortfolio {
+                    wallet_id: wallet.id.clone(),
+                    wallet_name: wallet.name.clone(),
+                    summary,
+                });
             }
-            Err("No wallet or address found".into())
+            Ok(UserPortfolio::from_wallets(wallets))
         } else {
             Err("Not logged in".into())
         }
     }
+    /// Fetches ADA and BTC quotes in every supported currency, honouring the fixture mode
+    pub async fn fetch_prices(&self) -> Result<Prices, Box<dyn std::error::Error>> {
+        if let FixtureMode::Replay(dir) = &self.fixture_mode {
+            return Ok(FixtureStore::new(dir).load(PRICES_FIXTURE)?);
+        }
+
+        let prices = self.price_service.fetch(&[CARDANO_ID, BITCOIN_ID], &QuoteCurrency::ALL).await?;
+        if let FixtureMode::Record(dir) = &self.fixture_mode {
+            FixtureStore::new(dir).save(PRICES_FIXTURE, &prices)?;
+        }
+        Ok(prices)
+    }
+
+    /// ADA prices in `currency` at the start of each of `dates`, for valuing
+    /// ledger entries, honouring the fixture mode
+    pub async fn fetch_ada_rates(
+        &self,
+        dates: &BTreeSet<NaiveDate>,
+        currency: QuoteCurrency,
+    ) -> Result<HashMap<NaiveDate, f64>, Box<dyn std::error::Error>> {
+        let fixture = format!("{}_{}", ADA_RATES_FIXTURE, currency.code());
+        if let FixtureMode::Replay(dir) = &self.fixture_mode {
+            let recorded: BTreeMap<NaiveDate, f64> = FixtureStore::new(dir).load(&fixture)?;
+            return dates.iter()
+                .map(|date| match recorded.get(date) {
+                    Some(rate) => Ok((*date, *rate)),
+                    None => Err(format!("No recorded ADA rate for {}", date).into()),
+                })
+                .collect();
+        }
+
+        let rates = self.price_service.fetch_historical_range(CARDANO_ID, dates, currency).await?;
+        if let FixtureMode::Record(dir) = &self.fixture_mode {
+            // Keep the rates of earlier runs, which may cover other years
+            let store = FixtureStore::new(dir);
+            let mut recorded: BTreeMap<NaiveDate, f64> = store.load(&fixture).unwrap_or_default();
+            recorded.extend(&rates);
+            store.save(&fixture, &recorded)?;
+        }
+        Ok(rates)
+    }
+
+    /// Appends a snapshot of the merged portfolio to the history file
+    pub async fn record_snapshot(&self, portfolio: &UserPortfolio, prices: &Prices) -> Result<(), Box<dyn std::error::Error>> {
+        let user_id = self.current_user.lock().await
+            .as_ref()
+            .map(|user| user.id.clone())
+            .ok_or("Not logged in")?;
+        let snapshot = PortfolioSnapshot::from_summary(
+            &user_id,
+            &portfolio.total,
+            prices.price(CARDANO_ID, QuoteCurrency::Usd),
+            Utc::now(),
+        );
+
+        let mut history = self.history.lock().await;
+        history.record(snapshot, &self.retention)?;
+        history.save()
+    }
+
+    pub fn get_history(&self) -> Arc<Mutex<History>> {
+        self.history.clone()
+    }
+
     pub async fn get_current_user(&self) -> Option<User> {
         self.current_user.lock().await.clone()
     }
 
+    /// Every stored user as saved, so API keys stay locked
+    pub async fn get_users(&self) -> Vec<User> {
+        self.database.lock().await.users().cloned().collect()
+    }
+
+    /// Stores the display currency on the current user and persists it
+    pub async fn set_display_currency(&self, currency: DisplayCurrency) -> Result<(), Box<dyn std::error::Error>> {
+        let mut current_user = self.current_user.lock().await;
+        let user = current_user.as_mut().ok_or("Not logged in")?;
+        user.display_currency = currency;
+
+        let mut db = self.database.lock().await;
+        db.set_display_currency(&user.id, currency)?;
+        db.save()
+    }
+
+    pub async fn add_wallet(&self, name: String) -> Result<Vec<Wallet>, Box<dyn std::error::Error>> {
+        self.update_wallets(|db, user_id| db.add_wallet(user_id, name, Vec::new()).map(|_| ())).await
+    }
+
+    pub async fn rename_wallet(&self, wallet_id: &str, name: String) -> Result<Vec<Wallet>, Box<dyn std::error::Error>> {
+        self.update_wallets(|db, user_id| db.rename_wallet(user_id, wallet_id, name)).await
+    }
+
+    pub async fn delete_wallet(&self, wallet_id: &str) -> Result<Vec<Wallet>, Box<dyn std::error::Error>> {
+        self.update_wallets(|db, user_id| db.delete_wallet(user_id, wallet_id)).await
+    }
+
+    /// Adds a payment or stake address after checking it is a valid
+    /// Cardano address
+    pub async fn add_address(&self, wallet_id: &str, address: String) -> Result<Vec<Wallet>, Box<dyn std::error::Error>> {
+        let address = CardanoAddress::parse(&address)?;
+        self.update_wallets(|db, user_id| db.add_address(user_id, wallet_id, address.to_string())).await
+    }
+
+    /// Adds the stake address of `address` to the wallet, so positions are
+    /// looked up for every payment address of that stake key. `address` may
+    /// be the stake address itself or any base address using it.
+    pub async fn register_stake_address(&self, wallet_id: &str, address: &str) -> Result<Vec<Wallet>, Box<dyn std::error::Error>> {
+        let stake_address = CardanoAddress::parse(address)?
+            .stake_address()
+            .ok_or("This address has no stake key; enter a stake address or a base address")?;
+        self.update_wallets(|db, user_id| db.add_address(user_id, wallet_id, stake_address.to_string())).await
+    }
+
+    pub async fn remove_address(&self, wallet_id: &str, address: &str) -> Result<Vec<Wallet>, Box<dyn std::error::Error>> {
+        self.update_wallets(|db, user_id| db.remove_address(user_id, wallet_id, address)).await
+    }
+
+    /// Applies a wallet change to the current user, persists it and returns
+    /// their wallets as stored
+    async fn update_wallets<F>(&self, change: F) -> Result<Vec<Wallet>, Box<dyn std::error::Error>>
+    where
+        F: FnOnce(&mut Database, &str) -> Result<(), String>,
+    {
+        let mut current_user = self.current_user.lock().await;
+        let user = current_user.as_mut().ok_or("Not logged in")?;
+
+        let mut db = self.database.lock().await;
+        change(&mut db, &user.id)?;
+        db.save()?;
+        user.wallets = db.get_user(&user.id).ok_or("User not found")?.wallets.clone();
+        Ok(user.wallets.clone())
+    }
+
     pub fn get_database(&self) -> Arc<Mutex<Database>> {
         self.database.clone()
     }
@@ -60,7 +280,7 @@ impl UserService {
         let portfolio_api = self.portfolio_api.lock().await;
         
         if let Some(api) = portfolio_api.as_ref() {
-            Ok(api.get_market_cap_data().await?)
+            Ok(api.get_top_market_caps().await?)
         } else {
             Err("Not logged in".into())
         }
@@ -0,0 +1,58 @@
+use std::path::PathBuf;
+use crate::config::{self, AppConfig, CONFIG_FILE_NAME, DEFAULT_COINGECKO_URL, DEFAULT_TAPTOOLS_URL};
+
+#[test]
+fn test_defaults_without_file_or_env() {
+    let config = AppConfig::from_sources(None, |_| None).unwrap();
+
+    assert_eq!(config.taptools_url, DEFAULT_TAPTOOLS_URL);
+    assert_eq!(config.coingecko_url, DEFAULT_COINGECKO_URL);
+}
+
+#[test]
+fn test_default_path_is_not_relative_to_working_directory() {
+    let path = config::default_path();
+
+    assert!(path.ends_with(CONFIG_FILE_NAME));
+    if dirs::config_dir().is_some() {
+        assert!(path.is_absolute());
+    }
+}
+
+#[test]
+fn test_file_overrides_defaults() {
+    let file = r#"{ "taptools_url": "http://127.0.0.1:8080/taptools/" }"#;
+    let config = AppConfig::from_sources(Some(file), |_| None).unwrap();
+
+    assert_eq!(config.taptools_url, "http://127.0.0.1:8080/taptools");
+    assert_eq!(config.coingecko_url, DEFAULT_COINGECKO_URL);
+}
+
+#[test]
+fn test_env_overrides_file() {
+    let file = r#"{ "coingecko_url": "http://proxy.local/coingecko" }"#;
+    let config = AppConfig::from_sources(Some(file), |name| match name {
+        "COINGECKO_API_URL" => Some("http://127.0.0.1:9090".to_string()),
+        _ => None,
+    }).unwrap();
+
+    assert_eq!(config.coingecko_url, "http://127.0.0.1:9090");
+}
+
+#[test]
+fn test_invalid_file_is_an_error() {
+    assert!(AppConfig::from_sources(Some("not json"), |_| None).is_err());
+}
+
+#[test]
+fn test_export_dir_from_file_or_env() {
+    let file = r#"{ "export_dir": "/srv/exports" }"#;
+    let config = AppConfig::from_sources(Some(file), |_| None).unwrap();
+    assert_eq!(config.export_dir(), PathBuf::from("/srv/exports"));
+
+    let config = AppConfig::from_sources(Some(file), |name| match name {
+        "PORTFOLIO_EXPORT_DIR" => Some("/tmp/exports".to_string()),
+        _ => None,
+    }).unwrap();
+    assert_eq!(config.export_dir(), PathBuf::from("/tmp/exports"));
+}
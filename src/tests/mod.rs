@@ -0,0 +1,11 @@
+use std::path::PathBuf;
+
+#[cfg(test)]
+mod config_test;
+
+/// An empty directory under the system temp dir, unique to this test run
+pub(crate) fn temp_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("{}_{}", name, std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    dir
+}
@@ -5,11 +5,17 @@ use crate::ui::{
     state::{
         PositionsFocus,
         WatchListFocus,
+        HistoryFocus,
         AccountFocus,
+        WalletAction,
+        WalletPanel,
     }
 };
-use crate::models::user::User;
-use crate::services::user_service::UserService;
+use crate::config::AppConfig;
+use crate::models::{user::User, user_portfolio::UserPortfolio};
+use crate::reports::ReportFormat;
+use crate::services::{price::Prices, user_service::UserService};
+use crate::ui::export::export_table;
 use crate::utils::spinner::Spinner;
 use std::io;
 use ratatui::{
@@ -22,23 +28,37 @@ use ratatui::{
     },
 };
 use std::io::stdout;
+use std::path::PathBuf;
 use std::time::Duration;
-
-use crate::services::price::{fetch_ada_price, fetch_btc_price};
+use chrono::Local;
 
 pub struct App {
     pub state: AppState,
     pub user_service: UserService,
+    /// Directory exported tables are written to
+    pub export_dir: PathBuf,
 }
 
 impl App {
-    pub fn new(portfolio_data: String, user: User, user_service: UserService, ada_price: f64, btc_price: f64) -> Self {
+    pub fn new(portfolio_data: UserPortfolio, user: User, user_service: UserService, prices: Prices) -> Self {
         App {
-            state: AppState::new(portfolio_data, user, ada_price, btc_price),
+            state: AppState::new(portfolio_data, user, prices),
             user_service,
+            export_dir: AppConfig::default().export_dir(),
         }
     }
 
+    pub fn with_export_dir(mut self, export_dir: PathBuf) -> Self {
+        self.export_dir = export_dir;
+        self
+    }
+
+    /// Lists every stored profile on the Account page
+    pub async fn load_users(&mut self) {
+        let users = self.user_service.get_users().await;
+        self.state.set_users(users);
+    }
+
     pub async fn run<B: Backend>(&mut self, terminal: &mut Terminal<B>) -> io::Result<()> {
         loop {
             terminal.draw(|f| {
@@ -46,48 +66,95 @@ impl App {
             })?;
 
             if event::poll(Duration::from_millis(250))? {
-                match event::read()? {
-                    CrosstermEvent::Key(KeyEvent { code, .. }) => {
-                        match code {
-                            KeyCode::Char('q') => return Ok(()),
-                            KeyCode::Esc => {
-                                match self.state.current_page() {
-                                    Page::Positions if self.state.positions_focus == PositionsFocus::Content => {
-                                        self.state.toggle_positions_focus()
-                                    },
-                                    Page::WatchList if self.state.watch_list_focus == WatchListFocus::Content => {
-                                        self.state.toggle_watch_list_focus()
-                                    },
-                                    Page::Account if self.state.account_focus == AccountFocus::Content => {
-                                        self.state.toggle_account_focus()
-                                    },
-                                    _ => {}
-                                }
-                            },
-                            _ => {
-                                if self.state.is_content_focused() {
-                                    self.handle_content_input(code).await?;
-                                } else {
-                                    self.handle_menu_input(code).await?;
-                                }
+                if let CrosstermEvent::Key(KeyEvent { code, .. }) = event::read()? {
+                    // An open form takes every key, so typing never triggers shortcuts
+                    if self.state.wallet_form.is_some() {
+                        self.handle_wallet_form_input(code).await;
+                        continue;
+                    }
+                    if self.state.passphrase_input.is_some() {
+                        self.handle_passphrase_input(code).await;
+                        continue;
+                    }
+                    match code {
+                        KeyCode::Char('q') => return Ok(()),
+                        KeyCode::Char('c') => self.cycle_display_currency().await,
+                        KeyCode::Char('e') => self.export_table(ReportFormat::Csv),
+                        KeyCode::Char('E') => self.export_table(ReportFormat::Json),
+                        KeyCode::Char('v') if self.state.current_page() == &Page::Positions => {
+                            self.state.cycle_positions_wallet()
+                        },
+                        KeyCode::Esc => {
+                            match self.state.current_page() {
+                                Page::Positions if self.state.positions_focus == PositionsFocus::Content => {
+                                    self.state.toggle_positions_focus()
+                                },
+                                Page::WatchList if self.state.watch_list_focus == WatchListFocus::Content => {
+                                    self.state.toggle_watch_list_focus()
+                                },
+                                Page::History if self.state.history_focus == HistoryFocus::Content => {
+                                    self.state.toggle_history_focus()
+                                },
+                                Page::Account if self.state.account_focus == AccountFocus::Content => {
+                                    self.state.toggle_account_focus()
+                                },
+                                _ => {}
+                            }
+                        },
+                        _ => {
+                            if self.state.is_content_focused() {
+                                self.handle_content_input(code).await?;
+                            } else {
+                                self.handle_menu_input(code).await?;
                             }
                         }
                     }
-                    _ => {}
                 }
             }
         }
     }
 
+    async fn cycle_display_currency(&mut self) {
+        let currency = self.state.cycle_display_currency();
+        if let Err(e) = self.user_service.set_display_currency(currency).await {
+            self.state.set_status_message(format!("Failed to save currency: {}", e));
+        }
+    }
+
+    fn export_table(&mut self, format: ReportFormat) {
+        if self.state.exportable_table().is_none() {
+            return;
+        }
+        match export_table(&self.state, format, &self.export_dir, Local::now()) {
+            Ok((path, rows)) => {
+                self.state.set_info_message(format!("Exported {} rows to {}", rows, path.display()));
+            }
+            Err(e) => self.state.set_status_message(format!("Export failed: {}", e)),
+        }
+    }
+
+    async fn load_history(&mut self) {
+        if let Some(user) = self.user_service.get_current_user().await {
+            let history = self.user_service.get_history();
+            let history = history.lock().await;
+            self.state.set_history(history.snapshots_for(&user.id).cloned().collect());
+        }
+    }
+
     async fn handle_watch_list_navigation(&mut self) {
         if self.state.selected_watch_list_menu_item == 2 {  // Market Caps index
-            match self.user_service.get_market_cap_data().await {
-                Ok(tokens) => {
-                    self.state.market_cap_tokens = tokens;
-                }
-                Err(e) => {
-                    eprintln!("Failed to fetch market cap data: {}", e);
-                }
+            self.load_market_cap_data().await;
+        }
+    }
+
+    async fn load_market_cap_data(&mut self) {
+        match self.user_service.get_market_cap_data().await {
+            Ok(tokens) => {
+                self.state.market_cap_tokens = tokens;
+                self.state.clear_status_message();
+            }
+            Err(e) => {
+                self.state.set_status_message(format!("Failed to fetch market cap data: {}", e));
             }
         }
     }
@@ -102,6 +169,7 @@ impl App {
                         self.state.next_watch_list_menu_item();
                         self.handle_watch_list_navigation().await;
                     }
+                    Page::History => self.state.next_history_range(),
                     _ => {}
                 }
             },
@@ -110,24 +178,29 @@ impl App {
                     Page::Account => self.state.previous_account_menu_item(),
                     Page::Positions => self.state.previous_positions_menu_item(),
                     Page::WatchList => self.state.previous_watch_list_menu_item(),
+                    Page::History => self.state.previous_history_range(),
                     _ => {}
                 }
             },
             KeyCode::Char('r') => {
                 match self.state.current_page() {
                     Page::Positions => {
-                        let _ = self.refresh_data().await;
+                        if let Err(e) = self.refresh_data().await {
+                            self.state.set_status_message(format!("Refresh failed: {}", e));
+                        }
                     },
                     Page::WatchList if self.state.selected_watch_list_menu_item == 2 => {
-                        if let Ok(tokens) = self.user_service.get_market_cap_data().await {
-                            self.state.market_cap_tokens = tokens;
-                        }
+                        self.load_market_cap_data().await;
                     },
                     _ => {}
                 }
             },
             KeyCode::Char('w') => self.state.set_current_page(Page::WatchList),
             KeyCode::Char('p') => self.state.set_current_page(Page::Positions),
+            KeyCode::Char('h') => {
+                self.load_history().await;
+                self.state.set_current_page(Page::History);
+            },
             KeyCode::Char('a') => self.state.set_current_page(Page::Account),
             KeyCode::Enter => {
                 match self.state.current_page() {
@@ -138,6 +211,9 @@ impl App {
                     Page::WatchList if self.state.watch_list_focus == WatchListFocus::Menu => {
                         self.state.toggle_watch_list_focus()
                     },
+                    Page::History if self.state.history_focus == HistoryFocus::Menu => {
+                        self.state.toggle_history_focus()
+                    },
                     _ => {}
                 }
             },
@@ -190,40 +266,207 @@ impl App {
                     _ => {}
                 }
             },
-            Page::WatchList => {
-                match self.state.selected_watch_list_menu_item {
-                    2 => { // Market Caps
-                        match code {
-                            KeyCode::Down | KeyCode::Char('j') => self.next_market_cap_row(),
-                            KeyCode::Up | KeyCode::Char('k') => self.previous_market_cap_row(),
-                            _ => {}
-                        }
-                    },
+            Page::WatchList if self.state.selected_watch_list_menu_item == 2 => { // Market Caps
+                match code {
+                    KeyCode::Down | KeyCode::Char('j') => self.next_market_cap_row(),
+                    KeyCode::Up | KeyCode::Char('k') => self.previous_market_cap_row(),
                     _ => {}
                 }
             },
-            Page::Account => {
-                // Handle account content input
+            Page::History => {
+                match code {
+                    KeyCode::Down | KeyCode::Char('j') => self.state.next_history_position(),
+                    KeyCode::Up | KeyCode::Char('k') => self.state.previous_history_position(),
+                    _ => {}
+                }
+            },
+            Page::Account if self.state.selected_account_menu_item == 0 => { // Profile
+                match code {
+                    KeyCode::Down | KeyCode::Char('j') => self.state.next_user(),
+                    KeyCode::Up | KeyCode::Char('k') => self.state.previous_user(),
+                    KeyCode::Enter => { self.state.open_passphrase_prompt(); },
+                    _ => {}
+                }
+            },
+            Page::Account if self.state.selected_account_menu_item == 1 => { // Wallets
+                match code {
+                    KeyCode::Down | KeyCode::Char('j') => match self.state.wallet_panel {
+                        WalletPanel::Wallets => self.state.next_wallet(),
+                        WalletPanel::Addresses => self.state.next_address(),
+                    },
+                    KeyCode::Up | KeyCode::Char('k') => match self.state.wallet_panel {
+                        WalletPanel::Wallets => self.state.previous_wallet(),
+                        WalletPanel::Addresses => self.state.previous_address(),
+                    },
+                    KeyCode::Tab | KeyCode::Left | KeyCode::Right => self.state.toggle_wallet_panel(),
+                    KeyCode::Char('n') => { self.state.open_wallet_form(WalletAction::AddWallet); },
+                    KeyCode::Char('r') => { self.state.open_wallet_form(WalletAction::RenameWallet); },
+                    KeyCode::Char('a') => { self.state.open_wallet_form(WalletAction::AddAddress); },
+                    KeyCode::Char('s') => { self.state.open_wallet_form(WalletAction::RegisterStakeAddress); },
+                    KeyCode::Char('d') => {
+                        let action = match self.state.wallet_panel {
+                            WalletPanel::Wallets => WalletAction::DeleteWallet,
+                            WalletPanel::Addresses => WalletAction::RemoveAddress,
+                        };
+                        self.state.open_wallet_form(action);
+                    },
+                    _ => {}
+                }
             },
             _ => {}
         }
         Ok(())
     }
 
+    async fn handle_wallet_form_input(&mut self, code: KeyCode) {
+        let Some(form) = self.state.wallet_form.as_mut() else {
+            return;
+        };
+        if form.action.takes_input() {
+            match code {
+                KeyCode::Char(c) => form.input.push(c),
+                KeyCode::Backspace => { form.input.pop(); },
+                KeyCode::Enter => self.submit_wallet_form().await,
+                KeyCode::Esc => self.state.close_wallet_form(),
+                _ => {}
+            }
+        } else {
+            match code {
+                KeyCode::Char('y') | KeyCode::Char('Y') | KeyCode::Enter => self.submit_wallet_form().await,
+                KeyCode::Char('n') | KeyCode::Char('N') | KeyCode::Esc => self.state.close_wallet_form(),
+                _ => {}
+            }
+        }
+    }
+
+    async fn handle_passphrase_input(&mut self, code: KeyCode) {
+        let Some(input) = self.state.passphrase_input.as_mut() else {
+            return;
+        };
+        match code {
+            KeyCode::Char(c) => input.push(c),
+            KeyCode::Backspace => { input.pop(); },
+            KeyCode::Enter => self.switch_user().await,
+            KeyCode::Esc => self.state.close_passphrase_prompt(),
+            _ => {}
+        }
+    }
+
+    /// Logs in as the selected profile with the typed passphrase and loads
+    /// their portfolio. A failed login keeps the current profile.
+    async fn switch_user(&mut self) {
+        let Some(passphrase) = self.state.passphrase_input.take() else {
+            return;
+        };
+        let Some(user_id) = self.state.users.get(self.state.selected_user_index).map(|user| user.id.clone()) else {
+            return;
+        };
+        if let Err(e) = self.user_service.login(&user_id, &passphrase).await {
+            self.state.set_status_message(format!("Could not switch profile: {}", e));
+            return;
+        }
+        let user = self.user_service.get_current_user().await
+            .expect("User should be logged in");
+        let name = user.name.clone();
+
+        let loaded = Spinner::spin_while(
+            "Loading portfolio data...",
+            async {
+                let portfolio = self.user_service.fetch_portfolio_data().await?;
+                let prices = self.user_service.fetch_prices().await?;
+                Ok::<_, Box<dyn std::error::Error>>((portfolio, prices))
+            }
+        ).await;
+        match loaded {
+            Ok((portfolio, prices)) => {
+                let snapshot_result = self.user_service.record_snapshot(&portfolio, &prices).await;
+                self.state.switch_user(user, portfolio, prices);
+                self.load_history().await;
+                match snapshot_result {
+                    Ok(()) => self.state.set_info_message(format!("Switched to {}", name)),
+                    Err(e) => self.state.set_status_message(format!("Switched to {} but failed to record snapshot: {}", name, e)),
+                }
+            }
+            Err(e) => {
+                // The service is already logged in as the new user, so never
+                // keep showing the previous user's positions
+                let prices = self.state.prices.clone();
+                self.state.switch_user(user, UserPortfolio::default(), prices);
+                self.load_history().await;
+                self.state.set_status_message(format!("Switched to {} but loading their portfolio failed: {}", name, e));
+            }
+        }
+    }
+
+    /// Saves the change described by the open wallet form
+    async fn submit_wallet_form(&mut self) {
+        let Some(form) = self.state.wallet_form.clone() else {
+            return;
+        };
+        let input = form.input.trim().to_string();
+        if form.action.takes_input() && input.is_empty() {
+            return;
+        }
+        let wallet_id = self.state.selected_wallet().map(|wallet| wallet.id.clone()).unwrap_or_default();
+        let address = self.state.selected_address().cloned().unwrap_or_default();
+
+        let service = &self.user_service;
+        let (result, message) = match form.action {
+            WalletAction::AddWallet => (service.add_wallet(input.clone()).await, format!("Added wallet {}", input)),
+            WalletAction::RenameWallet => (service.rename_wallet(&wallet_id, input.clone()).await, format!("Renamed wallet to {}", input)),
+            WalletAction::DeleteWallet => (service.delete_wallet(&wallet_id).await, "Deleted wallet".to_string()),
+            WalletAction::AddAddress => (service.add_address(&wallet_id, input).await, "Added address".to_string()),
+            WalletAction::RemoveAddress => (service.remove_address(&wallet_id, &address).await, "Removed address".to_string()),
+            WalletAction::RegisterStakeAddress => (
+                service.register_stake_address(&wallet_id, &input).await,
+                "Registered stake address, which covers every address of its key".to_string(),
+            ),
+        };
+
+        self.state.close_wallet_form();
+        match result {
+            Ok(wallets) => {
+                let wallet_count = wallets.len();
+                self.state.set_wallets(wallets);
+                // Move the cursor onto what was just added
+                match form.action {
+                    WalletAction::AddWallet => {
+                        self.state.selected_wallet_index = wallet_count.saturating_sub(1);
+                        self.state.selected_address_index = 0;
+                    },
+                    WalletAction::AddAddress | WalletAction::RegisterStakeAddress => {
+                        let addresses = self.state.selected_wallet().map_or(0, |wallet| wallet.addresses.len());
+                        self.state.selected_address_index = addresses.saturating_sub(1);
+                    },
+                    _ => {}
+                }
+                self.state.set_info_message(format!("{}. Refresh positions to update totals.", message));
+            }
+            Err(e) => self.state.set_status_message(format!("Wallet change failed: {}", e)),
+        }
+    }
+
+    /// Fetches the portfolio and prices again. Fails only when the fetch
+    /// does; a snapshot that could not be saved is reported in the status
+    /// line, since the new data is shown either way.
     pub async fn refresh_data(&mut self) -> Result<(), Box<dyn std::error::Error>> {
-        let (portfolio, ada_price, btc_price) = Spinner::spin_while(
+        let (portfolio, prices) = Spinner::spin_while(
             "Refreshing data...",
             async {
                 let portfolio = self.user_service.fetch_portfolio_data().await?;
-                let ada_price = fetch_ada_price().await?;
-                let btc_price = fetch_btc_price().await?;
-                Ok::<_, Box<dyn std::error::Error>>((portfolio, ada_price, btc_price))
+                let prices = self.user_service.fetch_prices().await?;
+                Ok::<_, Box<dyn std::error::Error>>((portfolio, prices))
             }
         ).await?;
 
+        let snapshot_result = self.user_service.record_snapshot(&portfolio, &prices).await;
         self.state.update_portfolio(portfolio);
-        self.state.ada_usd_price = ada_price;
-        self.state.btc_usd_price = btc_price;
+        self.state.update_prices(prices);
+        self.state.clear_status_message();
+        self.load_history().await;
+        if let Err(e) = snapshot_result {
+            self.state.set_status_message(format!("Refreshed, but failed to record snapshot: {}", e));
+        }
         Ok(())
     }
 }
@@ -1,13 +1,13 @@
 use ratatui::{
     Frame,
-    layout::{Rect, Margin},
+    layout::{Alignment, Rect, Margin},
     style::{Color, Style},
     widgets::{Block, Borders, Paragraph},
 };
 
 use crate::ui::{
     state::{AppState, Page},
-    pages::{account, positions, watch_list, common::create_main_layout},
+    pages::{account, positions, watch_list, history, common::create_main_layout},
 };
 
 pub fn draw(f: &mut Frame, state: &mut AppState) {
@@ -21,6 +21,7 @@ pub fn draw(f: &mut Frame, state: &mut AppState) {
         Page::Account => account::draw_account_page(f, state, chunks.content),
         Page::Positions => positions::draw_positions_page(f, state, chunks.content),
         Page::WatchList => watch_list::draw_watch_list_page(f, state, chunks.content),
+        Page::History => history::draw_history_page(f, state, chunks.content),
         _ => {},
     }
 }
@@ -127,13 +128,28 @@ fn draw_navigation(f: &mut Frame, state: &AppState, area: Rect) {
 }
 
 fn draw_page_title(f: &mut Frame, state: &AppState, area: Rect) {
-    let title = &state.menu_items[state.current_menu_item].label;
+    let mut title = format!(
+        "{} | (c) Currency: {}",
+        state.menu_items[state.current_menu_item].label,
+        state.display_currency
+    );
+    if state.exportable_table().is_some() {
+        title.push_str(" | (e) Export CSV (E) Export JSON");
+    }
     let title_block = Block::default()
         .borders(Borders::ALL)
         .style(Style::default().fg(Color::White));
-    let title_paragraph = Paragraph::new(title.clone())
+    let inner_area = title_block.inner(area);
+    let title_paragraph = Paragraph::new(title)
         .block(title_block)
         .style(Style::default().fg(Color::White));
     f.render_widget(title_paragraph, area);
+
+    if let Some(message) = &state.status_message {
+        let status_paragraph = Paragraph::new(message.clone())
+            .alignment(Alignment::Right)
+            .style(Style::default().fg(if state.status_is_error { Color::Red } else { Color::Green }));
+        f.render_widget(status_paragraph, inner_area);
+    }
 }
 
@@ -0,0 +1,44 @@
+use std::fs::{self, File};
+use std::path::{Path, PathBuf};
+use chrono::{DateTime, Local};
+use crate::reports::{write_records, ReportFormat};
+use crate::ui::state::{AppState, ExportTable};
+
+/// Writes the table on screen to a timestamped file in `dir`, creating it
+/// if needed, and returns the file's absolute path and the number of rows
+/// written
+pub fn export_table(
+    state: &AppState,
+    format: ReportFormat,
+    dir: &Path,
+    now: DateTime<Local>,
+) -> Result<(PathBuf, usize), Box<dyn std::error::Error>> {
+    let table = state.exportable_table().ok_or("Nothing to export on this page")?;
+    fs::create_dir_all(dir)?;
+    let file_name = format!("{}_{}.{}", table.file_stem(), now.format("%Y%m%d_%H%M%S"), format);
+    let path = std::path::absolute(dir.join(file_name))?;
+    let file = File::create(&path)?;
+
+    let rows = match table {
+        ExportTable::FungibleTokens => {
+            let rows = state.visible_positions_ft();
+            write_records(file, format, &rows)?;
+            rows.len()
+        },
+        ExportTable::NonFungibleTokens => {
+            let rows = state.visible_positions_nft();
+            write_records(file, format, &rows)?;
+            rows.len()
+        },
+        ExportTable::LiquidityPositions => {
+            let rows = state.visible_positions_lp();
+            write_records(file, format, &rows)?;
+            rows.len()
+        },
+        ExportTable::MarketCaps => {
+            write_records(file, format, &state.market_cap_tokens)?;
+            state.market_cap_tokens.len()
+        },
+    };
+    Ok((path, rows))
+}
@@ -1,6 +1,7 @@
 mod app;
 pub mod state;
 mod draw;
+pub mod export;
 mod pages;
 
 #[cfg(test)]
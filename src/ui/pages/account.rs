@@ -2,9 +2,10 @@ use ratatui::{
     Frame, 
     layout::{Rect, Layout, Direction, Constraint},
     style::{Style, Color, Modifier},
-    widgets::{Block, Borders, List, ListItem, ListState},
+    widgets::{Block, Borders, Clear, List, ListItem, ListState, Paragraph, Wrap},
 };
-use crate::ui::state::{AppState, AccountFocus};
+use crate::ui::pages::common::centered_rect;
+use crate::ui::state::{AppState, AccountFocus, WalletAction, WalletForm, WalletPanel};
 
 pub fn draw_account_page(f: &mut Frame, state: &mut AppState, area: Rect) {
     let account_chunks = Layout::default()
@@ -57,6 +58,10 @@ pub fn draw_account_page(f: &mut Frame, state: &mut AppState, area: Rect) {
         
         let profiles_area = account_chunks[1];
         let inner_profiles_area = profiles_block.inner(profiles_area);
+        let profile_rows = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([Constraint::Min(1), Constraint::Length(1)])
+            .split(inner_profiles_area);
 
         // Split the inner area for the two columns
         let content_layout = Layout::default()
@@ -65,7 +70,7 @@ pub fn draw_account_page(f: &mut Frame, state: &mut AppState, area: Rect) {
                 Constraint::Percentage(50),
                 Constraint::Percentage(50),
             ])
-            .split(inner_profiles_area);
+            .split(profile_rows[0]);
 
         // Render the main profiles container
         f.render_widget(profiles_block, profiles_area);
@@ -105,7 +110,12 @@ pub fn draw_account_page(f: &mut Frame, state: &mut AppState, area: Rect) {
                 } else {
                     Style::default().fg(Color::White)
                 };
-                ListItem::new(user.name.clone()).style(style)
+                let label = if index == state.active_user_index {
+                    format!("{} (active)", user.name)
+                } else {
+                    user.name.clone()
+                };
+                ListItem::new(label).style(style)
             })
             .collect();
         
@@ -130,7 +140,10 @@ pub fn draw_account_page(f: &mut Frame, state: &mut AppState, area: Rect) {
                 .iter()
                 .enumerate()
                 .map(|(index, w)| {
-                    let style = if index == state.selected_wallet_index && state.account_focus == AccountFocus::Content {
+                    let style = if index == state.selected_wallet_index
+                        && state.selected_user_index == state.active_user_index
+                        && state.account_focus == AccountFocus::Content
+                    {
                         Style::default().bg(Color::Rgb(128, 0, 128)).fg(Color::White)
                     } else {
                         Style::default().fg(Color::White)
@@ -149,6 +162,19 @@ pub fn draw_account_page(f: &mut Frame, state: &mut AppState, area: Rect) {
                 .style(Style::default().fg(Color::White)),
             inner_wallet_area,
         );
+
+        let help = if state.account_focus == AccountFocus::Content {
+            "(enter) Switch to profile"
+        } else {
+            "(enter) Choose profile"
+        };
+        f.render_widget(Paragraph::new(help).style(Style::default().fg(Color::Gray)), profile_rows[1]);
+
+        if let Some(input) = &state.passphrase_input {
+            draw_passphrase_prompt(f, state, input, profiles_area);
+        }
+    } else if state.selected_account_menu_item == 1 {  // Wallets section
+        draw_wallets(f, state, account_chunks[1], right_content_style);
     } else {
         // Other menu items just show a simple content block
         let content_block = Block::default()
@@ -158,3 +184,144 @@ pub fn draw_account_page(f: &mut Frame, state: &mut AppState, area: Rect) {
         f.render_widget(content_block, account_chunks[1]);
     }
 }
+
+fn draw_wallets(f: &mut Frame, state: &AppState, area: Rect, border_style: Style) {
+    let wallets_block = Block::default()
+        .title("Wallets")
+        .borders(Borders::ALL)
+        .border_style(border_style);
+    let inner_area = wallets_block.inner(area);
+    f.render_widget(wallets_block, area);
+
+    let rows = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Min(1), Constraint::Length(1)])
+        .split(inner_area);
+    let columns = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([Constraint::Percentage(35), Constraint::Percentage(65)])
+        .split(rows[0]);
+
+    let focused = state.account_focus == AccountFocus::Content;
+    let panel_style = |panel: WalletPanel| {
+        if focused && state.wallet_panel == panel {
+            Style::default().fg(Color::Yellow)
+        } else {
+            Style::default().fg(Color::White)
+        }
+    };
+    let selected_style = Style::default().bg(Color::Rgb(128, 0, 128)).fg(Color::White);
+
+    let wallets = state.active_user()
+        .map(|user| user.wallets.as_slice())
+        .unwrap_or_default();
+    let wallet_items: Vec<ListItem> = wallets.iter()
+        .map(|wallet| ListItem::new(format!("{} ({})", wallet.name, wallet.addresses.len())))
+        .collect();
+    let mut wallet_state = ListState::default();
+    wallet_state.select((!wallets.is_empty()).then_some(state.selected_wallet_index));
+    f.render_stateful_widget(
+        List::new(wallet_items)
+            .block(Block::default().borders(Borders::ALL).border_style(panel_style(WalletPanel::Wallets)).title("Wallet"))
+            .highlight_style(selected_style)
+            .highlight_symbol("> "),
+        columns[0],
+        &mut wallet_state,
+    );
+
+    let addresses = state.selected_wallet()
+        .map(|wallet| wallet.addresses.as_slice())
+        .unwrap_or_default();
+    let address_items: Vec<ListItem> = addresses.iter()
+        .map(|address| ListItem::new(address.clone()))
+        .collect();
+    let mut address_state = ListState::default();
+    address_state.select(
+        (state.wallet_panel == WalletPanel::Addresses && !addresses.is_empty())
+            .then_some(state.selected_address_index),
+    );
+    f.render_stateful_widget(
+        List::new(address_items)
+            .block(Block::default().borders(Borders::ALL).border_style(panel_style(WalletPanel::Addresses)).title("Addresses"))
+            .highlight_style(selected_style)
+            .highlight_symbol("> "),
+        columns[1],
+        &mut address_state,
+    );
+
+    let help = if focused {
+        "(n) New wallet (r) Rename (a) Add address (s) Stake address (d) Delete (tab) Switch list"
+    } else {
+        "(enter) Manage wallets"
+    };
+    f.render_widget(Paragraph::new(help).style(Style::default().fg(Color::Gray)), rows[1]);
+
+    if let Some(form) = &state.wallet_form {
+        draw_wallet_form(f, state, form, area);
+    }
+}
+
+/// The open wallet form as a popup over the panel
+fn draw_wallet_form(f: &mut Frame, state: &AppState, form: &WalletForm, area: Rect) {
+    let wallet_name = state.selected_wallet().map(|wallet| wallet.name.as_str()).unwrap_or_default();
+    let (text, help) = match form.action {
+        WalletAction::DeleteWallet => {
+            let addresses = state.selected_wallet().map_or(0, |wallet| wallet.addresses.len());
+            (format!("Delete wallet '{}' and its {} addresses?", wallet_name, addresses), "(y) Yes (n) No")
+        },
+        WalletAction::RemoveAddress => {
+            let address = state.selected_address().map(String::as_str).unwrap_or_default();
+            (format!("Remove {} from '{}'?", address, wallet_name), "(y) Yes (n) No")
+        },
+        _ => (format!("{}_", form.input), "(enter) Save (esc) Cancel"),
+    };
+
+    let popup = centered_rect(70, 6, area);
+    let block = Block::default()
+        .title(form.action.title())
+        .borders(Borders::ALL)
+        .border_style(Style::default().fg(Color::Yellow));
+    let inner = block.inner(popup);
+    f.render_widget(Clear, popup);
+    f.render_widget(block, popup);
+
+    let rows = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Min(1), Constraint::Length(1)])
+        .split(inner);
+    f.render_widget(
+        Paragraph::new(text).style(Style::default().fg(Color::White)).wrap(Wrap { trim: false }),
+        rows[0],
+    );
+    f.render_widget(Paragraph::new(help).style(Style::default().fg(Color::Gray)), rows[1]);
+}
+
+/// Asks for the passphrase of the profile being switched to, masking it
+fn draw_passphrase_prompt(f: &mut Frame, state: &AppState, input: &str, area: Rect) {
+    let name = state.users.get(state.selected_user_index)
+        .map(|user| user.name.as_str())
+        .unwrap_or_default();
+    let title = format!("Passphrase for {}", name);
+
+    let popup = centered_rect(70, 5, area);
+    let block = Block::default()
+        .title(title)
+        .borders(Borders::ALL)
+        .border_style(Style::default().fg(Color::Yellow));
+    let inner = block.inner(popup);
+    f.render_widget(Clear, popup);
+    f.render_widget(block, popup);
+
+    let rows = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Min(1), Constraint::Length(1)])
+        .split(inner);
+    f.render_widget(
+        Paragraph::new(format!("{}_", "*".repeat(input.chars().count()))).style(Style::default().fg(Color::White)),
+        rows[0],
+    );
+    f.render_widget(
+        Paragraph::new("(enter) Switch (esc) Cancel").style(Style::default().fg(Color::Gray)),
+        rows[1],
+    );
+}
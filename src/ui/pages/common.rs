@@ -21,4 +21,17 @@ pub fn create_main_layout(area: Rect) -> LayoutChunks {
         content: chunks[1],
         navigation: chunks[2],
     }
-}
\ No newline at end of file
+}
+
+/// A box `width_percent` wide and `height` rows tall in the middle of `area`
+pub fn centered_rect(width_percent: u16, height: u16, area: Rect) -> Rect {
+    // In u32, since the product overflows u16 on terminals over 655 columns
+    let width = (u32::from(area.width) * u32::from(width_percent.min(100)) / 100) as u16;
+    let height = height.min(area.height);
+    Rect {
+        x: area.x + (area.width - width) / 2,
+        y: area.y + (area.height - height) / 2,
+        width,
+        height,
+    }
+}
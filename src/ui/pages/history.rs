@@ -0,0 +1,227 @@
+use chrono::{DateTime, Utc};
+use ratatui::{
+    Frame,
+    layout::{Rect, Layout, Direction, Constraint},
+    style::{Style, Color, Modifier},
+    symbols,
+    text::Span,
+    prelude::Margin,
+    widgets::{Axis, Block, Borders, Chart, Dataset, GraphType, List, ListItem, ListState, Paragraph, Sparkline},
+};
+use crate::models::portfolio_snapshot::PortfolioSnapshot;
+use crate::ui::state::{AppState, HistoryFocus, HistoryRange};
+use crate::utils::formatting::{format_ada, format_usd};
+
+const SPARKLINE_HEIGHT: u16 = 2;
+
+pub fn draw_history_page(f: &mut Frame, state: &mut AppState, area: Rect) {
+    let main_block = Block::default()
+        .title("History")
+        .borders(Borders::ALL)
+        .style(Style::default());
+
+    let inner_area = main_block.inner(area);
+
+    // Create horizontal split for menu and content
+    let chunks = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([Constraint::Ratio(1, 3), Constraint::Ratio(2, 3)].as_ref())
+        .split(inner_area);
+
+    // Render the main block
+    f.render_widget(main_block, area);
+
+    // Left Menu
+    let left_menu_style = if state.history_focus == HistoryFocus::Menu {
+        Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD)
+    } else {
+        Style::default().fg(Color::White)
+    };
+
+    let left_menu = Block::default()
+        .borders(Borders::ALL)
+        .border_style(left_menu_style)
+        .title("Range");
+
+    let items: Vec<ListItem> = HistoryRange::ALL
+        .iter()
+        .map(|range| ListItem::new(range.label()))
+        .collect();
+
+    let list = List::new(items)
+        .block(left_menu)
+        .highlight_style(Style::default().bg(Color::Rgb(128, 0, 128)).fg(Color::White))
+        .highlight_symbol("> ");
+
+    let mut list_state = ListState::default();
+    list_state.select(Some(state.selected_history_range));
+
+    f.render_stateful_widget(list, chunks[0], &mut list_state);
+
+    // Right Content
+    let right_content_style = if state.history_focus == HistoryFocus::Content {
+        Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD)
+    } else {
+        Style::default().fg(Color::White)
+    };
+
+    let content_block = Block::default()
+        .borders(Borders::ALL)
+        .border_style(right_content_style)
+        .title(format!("Portfolio Value ({})", state.history_range().label()));
+
+    let margin = Margin {
+        horizontal: 1,
+        vertical: 1,
+    };
+
+    f.render_widget(&content_block, chunks[1]);
+    let content_area = chunks[1].inner(margin);
+
+    let now = Utc::now();
+    let snapshots = state.history_in_range(now);
+    if snapshots.is_empty() {
+        f.render_widget(
+            Paragraph::new("No snapshots in this range yet. Press (r) on the Positions page to record one.")
+                .style(Style::default().fg(Color::White)),
+            content_area,
+        );
+        return;
+    }
+
+    let content_chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Percentage(35),  // ADA chart
+            Constraint::Percentage(35),  // USD chart
+            Constraint::Percentage(30),  // Position sparklines
+        ])
+        .split(content_area);
+
+    let ada_points: Vec<(f64, f64)> = snapshots.iter()
+        .map(|s| (s.timestamp.timestamp() as f64, s.ada_value))
+        .collect();
+    let usd_points: Vec<(f64, f64)> = snapshots.iter()
+        .map(|s| (s.timestamp.timestamp() as f64, s.usd_value()))
+        .collect();
+
+    draw_value_chart(f, &ada_points, "ADA Value", Color::Cyan, format_ada, state.history_range(), content_chunks[0]);
+    draw_value_chart(f, &usd_points, "USD Value", Color::Green, format_usd, state.history_range(), content_chunks[1]);
+    draw_position_sparklines(f, state, &snapshots, content_chunks[2]);
+}
+
+fn draw_value_chart(
+    f: &mut Frame,
+    points: &[(f64, f64)],
+    title: &str,
+    color: Color,
+    format_value: fn(f64, usize) -> String,
+    range: HistoryRange,
+    area: Rect,
+) {
+    let (x_min, x_max) = bounds(points.iter().map(|(x, _)| *x));
+    let (y_min, y_max) = bounds(points.iter().map(|(_, y)| *y));
+
+    let dataset = Dataset::default()
+        .marker(symbols::Marker::Braille)
+        .graph_type(GraphType::Line)
+        .style(Style::default().fg(color))
+        .data(points);
+
+    let x_labels = vec![
+        Span::raw(format_timestamp(x_min, range)),
+        Span::raw(format_timestamp(x_max, range)),
+    ];
+    let y_labels = vec![
+        Span::raw(format_value(y_min, 2)),
+        Span::raw(format_value(y_max, 2)),
+    ];
+
+    let chart = Chart::new(vec![dataset])
+        .block(Block::default().title(title))
+        .x_axis(Axis::default()
+            .style(Style::default().fg(Color::Gray))
+            .bounds([x_min, x_max])
+            .labels(x_labels))
+        .y_axis(Axis::default()
+            .style(Style::default().fg(Color::Gray))
+            .bounds([y_min, y_max])
+            .labels(y_labels));
+
+    f.render_widget(chart, area);
+}
+
+fn draw_position_sparklines(f: &mut Frame, state: &AppState, snapshots: &[&PortfolioSnapshot], area: Rect) {
+    let units = state.history_position_units();
+    let visible_rows = (area.height / SPARKLINE_HEIGHT).max(1) as usize;
+    // Scroll so the selected position stays visible
+    let first_row = state.selected_history_position.saturating_sub(visible_rows - 1);
+
+    for (row, unit) in units.iter().enumerate().skip(first_row).take(visible_rows) {
+        let values: Vec<f64> = snapshots.iter()
+            .map(|s| s.positions.iter()
+                .find(|p| &p.unit == unit)
+                .map(|p| p.ada_value)
+                .unwrap_or_default())
+            .collect();
+        let max = values.iter().cloned().fold(0.0, f64::max);
+        // Sparkline takes integers, so scale each series to its own maximum
+        let data: Vec<u64> = values.iter()
+            .map(|value| if max > 0.0 { (value / max * 100.0) as u64 } else { 0 })
+            .collect();
+
+        let ticker = snapshots.last()
+            .and_then(|s| s.positions.iter().find(|p| &p.unit == unit))
+            .map(|p| p.ticker.clone())
+            .unwrap_or_default();
+        let latest = values.last().copied().unwrap_or_default();
+
+        let row_area = Rect {
+            y: area.y + ((row - first_row) as u16) * SPARKLINE_HEIGHT,
+            height: SPARKLINE_HEIGHT,
+            ..area
+        };
+        let row_chunks = Layout::default()
+            .direction(Direction::Horizontal)
+            .constraints([Constraint::Percentage(35), Constraint::Percentage(65)])
+            .split(row_area);
+
+        let label_style = if state.history_focus == HistoryFocus::Content && row == state.selected_history_position {
+            Style::default().bg(Color::Yellow).fg(Color::Black)
+        } else {
+            Style::default().fg(Color::White)
+        };
+
+        f.render_widget(
+            Paragraph::new(format!("{} {}", ticker, format_ada(latest, 2))).style(label_style),
+            row_chunks[0],
+        );
+        f.render_widget(
+            Sparkline::default()
+                .data(&data)
+                .style(Style::default().fg(Color::Rgb(128, 0, 128))),
+            row_chunks[1],
+        );
+    }
+}
+
+/// Min and max of `values`, widened when equal so the axis has a span
+fn bounds(values: impl Iterator<Item = f64>) -> (f64, f64) {
+    let (min, max) = values.fold((f64::MAX, f64::MIN), |(min, max), v| (min.min(v), max.max(v)));
+    if min >= max {
+        (min - 1.0, max + 1.0)
+    } else {
+        (min, max)
+    }
+}
+
+fn format_timestamp(seconds: f64, range: HistoryRange) -> String {
+    let format = match range {
+        HistoryRange::Day => "%H:%M",
+        HistoryRange::Week | HistoryRange::Month => "%m-%d",
+        HistoryRange::Year | HistoryRange::All => "%Y-%m-%d",
+    };
+    DateTime::<Utc>::from_timestamp(seconds as i64, 0)
+        .map(|t| t.format(format).to_string())
+        .unwrap_or_default()
+}
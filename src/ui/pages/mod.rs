@@ -1,4 +1,5 @@
 pub mod account;
 pub mod positions;
 pub mod watch_list;
+pub mod history;
 pub mod common;
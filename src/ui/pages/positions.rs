@@ -7,23 +7,31 @@ use ratatui::{
     widgets::{Block, Borders, List, ListItem, ListState, Cell, Row, Table},
 };
 use crate::ui::state::{AppState, PositionsFocus};
-use crate::utils::formatting::{format_ada, format_usd, format_number, format_change};
+use crate::services::price::{QuoteCurrency, CARDANO_ID};
+use crate::models::display_currency::DisplayCurrency;
+use crate::utils::formatting::{format_ada, format_currency, format_usd, format_number, format_change, format_pnl};
 
 pub fn draw_positions_page(f: &mut Frame, state: &mut AppState, area: Rect) {
     // Format the ADA and USD info for the title
+    let ada_change = state.prices.get(CARDANO_ID, QuoteCurrency::Usd)
+        .and_then(|quote| quote.change_24h)
+        .map(|change| format!(" ({:+.2}%)", change))
+        .unwrap_or_default();
     let ada_info = format!(
-        "BTC Price: {} | Ada Price: {} | Ada Value: {} | USD Value: {}",
+        "BTC Price: {} | Ada Price: {}{} | Ada Value: {} | USD Value: {}",
         format_usd(state.btc_usd_price, 2),
         format_usd(state.ada_usd_price, 4),
-        format_ada(state.ada_value, 2),
-        format_usd(state.ada_value * state.ada_usd_price, 2)
+        ada_change,
+        format_ada(state.visible_ada_value(), 2),
+        format_usd(state.visible_ada_value() * state.ada_usd_price, 2)
     );
+    let page_title = format!("Positions: {} (v)", state.positions_wallet_label());
 
     // Create the main block with combined title
     let main_block = Block::default()
         .borders(Borders::ALL)
         .title(Span::styled(
-            format!("Positions{:>width$}", ada_info, width = area.width as usize - 10),
+            format!("{}{:>width$}", page_title, ada_info, width = (area.width as usize).saturating_sub(page_title.len() + 1)),
             Style::default().fg(Color::White)
         ));
 
@@ -96,8 +104,13 @@ pub fn draw_ft_positions(f: &mut Frame, state: &AppState, area: Rect) {
     let highlight_color = Color::Rgb(128, 0, 128);  // Purple color
     
     // Create header cells with uppercase text and purple background
-    let header_cells = ["Ticker", "Balance", "ADA Value", "Price", "24h %", "7d %", "30d %"]
-        .iter()
+    let value_header = format!("{} Value", state.display_currency);
+    let fiat_pnl_header = format!("P&L {}", state.pnl_fiat_currency());
+    let header_cells = [
+        "Ticker", "Balance", value_header.as_str(), "Price",
+        "P&L ADA", fiat_pnl_header.as_str(), "24h %", "7d %", "30d %",
+    ]
+        .into_iter()
         .map(|h| {
             Cell::from(h.to_uppercase())
                 .style(Style::default()
@@ -110,11 +123,20 @@ pub fn draw_ft_positions(f: &mut Frame, state: &AppState, area: Rect) {
         .style(Style::default())
         .height(2);  // Increased header height
 
-    let rows = state.positions_ft.iter().enumerate().map(|(index, position)| {
+    let rows = state.visible_positions_ft().into_iter().enumerate().map(|(index, position)| {
         let change_24h = position.change_24h.unwrap_or(0.0) * 100.0;
         let change_7d = position.change_7d.unwrap_or(0.0) * 100.0;
         let change_30d = position.change_30d.unwrap_or(0.0) * 100.0;
 
+        // Positions without recorded lots have no cost basis to compare against
+        let (pnl_ada, pnl_fiat) = match state.unrealized_pnl(position) {
+            Some(pnl) => (
+                format_pnl(format_currency(pnl, DisplayCurrency::Ada, 2), pnl),
+                format_pnl(state.format_fiat_value(pnl, 2), pnl),
+            ),
+            None => (Span::raw("-"), Span::raw("-")),
+        };
+
         let row_style = if state.positions_focus == PositionsFocus::Content && index == state.selected_ft_row {
             Style::default().bg(Color::Yellow).fg(Color::Black)
         } else if index % 2 == 0 {
@@ -126,8 +148,10 @@ pub fn draw_ft_positions(f: &mut Frame, state: &AppState, area: Rect) {
         let row_cells = vec![
             Cell::from(position.ticker.clone()),
             Cell::from(format_number(position.balance, 2)),
-            Cell::from(format_ada(position.ada_value, 2)),
-            Cell::from(format_ada(position.price.unwrap_or(0.0), 4)),
+            Cell::from(state.format_value(position.ada_value, 2)),
+            Cell::from(state.format_value(position.price.unwrap_or(0.0), 4)),
+            Cell::from(pnl_ada),
+            Cell::from(pnl_fiat),
             Cell::from(format_change(change_24h)),
             Cell::from(format_change(change_7d)),
             Cell::from(format_change(change_30d)),
@@ -139,13 +163,15 @@ pub fn draw_ft_positions(f: &mut Frame, state: &AppState, area: Rect) {
     });
 
     let widths = [
-        Constraint::Percentage(15),  // Ticker
-        Constraint::Percentage(20),  // Balance
-        Constraint::Percentage(15),  // ADA Value
-        Constraint::Percentage(15),  // Price
-        Constraint::Percentage(10),  // 24h
-        Constraint::Percentage(10),  // 7d
-        Constraint::Percentage(15),  // 30d
+        Constraint::Percentage(10),  // Ticker
+        Constraint::Percentage(14),  // Balance
+        Constraint::Percentage(12),  // Value
+        Constraint::Percentage(11),  // Price
+        Constraint::Percentage(12),  // P&L ADA
+        Constraint::Percentage(12),  // P&L fiat
+        Constraint::Percentage(9),   // 24h
+        Constraint::Percentage(9),   // 7d
+        Constraint::Percentage(11),  // 30d
     ];
 
     let table = Table::new(rows, widths)
@@ -161,8 +187,9 @@ pub fn draw_ft_positions(f: &mut Frame, state: &AppState, area: Rect) {
 pub fn draw_nft_positions(f: &mut Frame, state: &AppState, area: Rect) {
     let highlight_color = Color::Rgb(128, 0, 128);
     
-    let header_cells = ["Name", "Balance", "Floor Price", "ADA Value", "24h %", "7d %", "30d %"]
-        .iter()
+    let value_header = format!("{} Value", state.display_currency);
+    let header_cells = ["Name", "Balance", "Floor Price", value_header.as_str(), "24h %", "7d %", "30d %"]
+        .into_iter()
         .map(|h| {
             Cell::from(h.to_uppercase())
                 .style(Style::default()
@@ -175,7 +202,7 @@ pub fn draw_nft_positions(f: &mut Frame, state: &AppState, area: Rect) {
         .style(Style::default())
         .height(2);
 
-    let rows = state.positions_nft.iter().enumerate().map(|(index, position)| {
+    let rows = state.visible_positions_nft().into_iter().enumerate().map(|(index, position)| {
         let change_24h = position.change_24h.unwrap_or(0.0) * 100.0;
         let change_7d = position.change_7d.unwrap_or(0.0) * 100.0;
         let change_30d = position.change_30d.unwrap_or(0.0) * 100.0;
@@ -191,8 +218,8 @@ pub fn draw_nft_positions(f: &mut Frame, state: &AppState, area: Rect) {
         let row_cells = vec![
             Cell::from(position.name.clone()),
             Cell::from(format_number(position.balance as f64, 0)),
-            Cell::from(format_ada(position.floor_price, 2)),
-            Cell::from(format_ada(position.ada_value, 2)),
+            Cell::from(state.format_value(position.floor_price, 2)),
+            Cell::from(state.format_value(position.ada_value, 2)),
             Cell::from(format_change(change_24h)),
             Cell::from(format_change(change_7d)),
             Cell::from(format_change(change_30d)),
@@ -207,7 +234,7 @@ pub fn draw_nft_positions(f: &mut Frame, state: &AppState, area: Rect) {
         Constraint::Percentage(25),  // Name
         Constraint::Percentage(10),  // Balance
         Constraint::Percentage(15),  // Floor Price
-        Constraint::Percentage(15),  // ADA Value
+        Constraint::Percentage(15),  // Value
         Constraint::Percentage(10),  // 24h
         Constraint::Percentage(10),  // 7d
         Constraint::Percentage(15),  // 30d
@@ -226,8 +253,9 @@ pub fn draw_nft_positions(f: &mut Frame, state: &AppState, area: Rect) {
 pub fn draw_lp_positions(f: &mut Frame, state: &AppState, area: Rect) {
     let highlight_color = Color::Rgb(128, 0, 128);
     
-    let header_cells = ["Pool", "Token A", "Amount A", "Token B", "Amount B", "ADA Value"]
-        .iter()
+    let value_header = format!("{} Value", state.display_currency);
+    let header_cells = ["Pool", "Token A", "Amount A", "Token B", "Amount B", value_header.as_str()]
+        .into_iter()
         .map(|h| {
             Cell::from(h.to_uppercase())
                 .style(Style::default()
@@ -240,7 +268,7 @@ pub fn draw_lp_positions(f: &mut Frame, state: &AppState, area: Rect) {
         .style(Style::default())
         .height(2);
 
-    let rows = state.positions_lp.iter().enumerate().map(|(index, position)| {
+    let rows = state.visible_positions_lp().into_iter().enumerate().map(|(index, position)| {
         let row_style = if state.positions_focus == PositionsFocus::Content && index == state.selected_lp_row {
             Style::default().bg(Color::Yellow).fg(Color::Black)
         } else if index % 2 == 0 {
@@ -255,7 +283,7 @@ pub fn draw_lp_positions(f: &mut Frame, state: &AppState, area: Rect) {
             Cell::from(format_number(position.token_a_amount, 2)),
             Cell::from(position.token_b_name.clone()),
             Cell::from(format_number(position.token_b_amount, 2)),
-            Cell::from(format_ada(position.ada_value, 2)),
+            Cell::from(state.format_value(position.ada_value, 2)),
         ];
 
         Row::new(row_cells)
@@ -269,7 +297,7 @@ pub fn draw_lp_positions(f: &mut Frame, state: &AppState, area: Rect) {
         Constraint::Percentage(15),  // Amount A
         Constraint::Percentage(15),  // Token B
         Constraint::Percentage(15),  // Amount B
-        Constraint::Percentage(20),  // ADA Value
+        Constraint::Percentage(20),  // Value
     ];
 
     let table = Table::new(rows, widths)
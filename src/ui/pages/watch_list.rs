@@ -6,7 +6,7 @@ use ratatui::{
     widgets::{Block, Borders, List, ListItem, ListState, Cell, Row, Table, TableState},
 };
 use crate::ui::state::{AppState, WatchListFocus};
-use crate::utils::formatting::{format_ada, format_number, format_change};
+use crate::utils::formatting::format_number;
 
 pub fn draw_watch_list_page(f: &mut Frame, state: &mut AppState, area: Rect) {
     let main_block = Block::default()
@@ -81,11 +81,11 @@ pub fn draw_watch_list_page(f: &mut Frame, state: &mut AppState, area: Rect) {
     };
 }
 
-fn draw_recommended_trades(f: &mut Frame, state: &AppState, area: Rect) {
+fn draw_recommended_trades(_f: &mut Frame, _state: &AppState, _area: Rect) {
     // TODO: Implement recommended trades table similar to positions tables
 }
 
-fn draw_watching(f: &mut Frame, state: &AppState, area: Rect) {
+fn draw_watching(_f: &mut Frame, _state: &AppState, _area: Rect) {
     // TODO: Implement watching table similar to positions tables
 }
 
@@ -95,8 +95,9 @@ fn draw_market_caps(f: &mut Frame, state: &AppState, area: Rect) {
         .fg(Color::Yellow)
         .add_modifier(Modifier::BOLD);
     
-    let header_cells = ["Ticker", "Price", "Market Cap", "FDV", "Circ Supply", "Total Supply"]
-        .iter()
+    let price_header = format!("Price ({})", state.display_currency);
+    let header_cells = ["Ticker", price_header.as_str(), "Market Cap", "FDV", "Circ Supply", "Total Supply"]
+        .into_iter()
         .map(|h| {
             Cell::from(h.to_uppercase())
                 .style(Style::default()
@@ -121,9 +122,9 @@ fn draw_market_caps(f: &mut Frame, state: &AppState, area: Rect) {
 
         let row_cells = vec![
             Cell::from(token.ticker.clone()),
-            Cell::from(format_ada(token.price, 6)),
-            Cell::from(format_ada(token.mcap, 0)),
-            Cell::from(format_ada(token.fdv, 0)),
+            Cell::from(state.format_value(token.price, 6)),
+            Cell::from(state.format_value(token.mcap, 0)),
+            Cell::from(state.format_value(token.fdv, 0)),
             Cell::from(format_number(token.circ_supply, 0)),
             Cell::from(format_number(token.total_supply, 0)),
         ];
@@ -1,16 +1,45 @@
 use crate::models::{
     user::User,
+    wallet::Wallet,
+    cost_basis::CostBasisBook,
+    display_currency::DisplayCurrency,
     ft_position::FtPosition,
     nft_position::NftPosition,
     lp_position::LpPosition,
-    portfolio_summary::PortfolioSummary,
     market_cap_token::MarketCapToken,
+    user_portfolio::{UserPortfolio, WalletPortfolio},
+    portfolio_snapshot::PortfolioSnapshot,
 };
+use chrono::{DateTime, Duration, Utc};
+use crate::utils::formatting::format_currency;
+use crate::services::price::{Prices, QuoteCurrency, BITCOIN_ID, CARDANO_ID};
+
+/// A table that can be written to a file with the export keys
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum ExportTable {
+    FungibleTokens,
+    NonFungibleTokens,
+    LiquidityPositions,
+    MarketCaps,
+}
+
+impl ExportTable {
+    /// Used in the exported file name
+    pub fn file_stem(&self) -> &'static str {
+        match self {
+            ExportTable::FungibleTokens => "fungible_tokens",
+            ExportTable::NonFungibleTokens => "nft_collections",
+            ExportTable::LiquidityPositions => "liquidity_positions",
+            ExportTable::MarketCaps => "market_caps",
+        }
+    }
+}
 
 #[derive(Clone, PartialEq, Debug)]
 pub enum Page {
     Positions,
     WatchList,
+    History,
     Account,
     Quit,
     Back,
@@ -38,6 +67,49 @@ pub enum AccountFocus {
     Content,
 }
 
+/// Which list has the cursor on the Account page's Wallets panel
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum WalletPanel {
+    Wallets,
+    Addresses,
+}
+
+/// A change to the current user's wallets started from the Account page
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum WalletAction {
+    AddWallet,
+    RenameWallet,
+    DeleteWallet,
+    AddAddress,
+    RemoveAddress,
+    RegisterStakeAddress,
+}
+
+impl WalletAction {
+    /// Whether the form asks for text rather than a yes/no confirmation
+    pub fn takes_input(&self) -> bool {
+        !matches!(self, WalletAction::DeleteWallet | WalletAction::RemoveAddress)
+    }
+
+    pub fn title(&self) -> &'static str {
+        match self {
+            WalletAction::AddWallet => "New Wallet Name",
+            WalletAction::RenameWallet => "Rename Wallet",
+            WalletAction::DeleteWallet => "Delete Wallet",
+            WalletAction::AddAddress => "Add Address",
+            WalletAction::RemoveAddress => "Remove Address",
+            WalletAction::RegisterStakeAddress => "Stake Address (or any address of the stake key)",
+        }
+    }
+}
+
+/// An open wallet form with the text typed so far
+#[derive(Debug, Clone, PartialEq)]
+pub struct WalletForm {
+    pub action: WalletAction,
+    pub input: String,
+}
+
 #[derive(Debug, Clone, Copy, PartialEq)]
 pub enum PositionsFocus {
     Menu,
@@ -50,10 +122,61 @@ pub enum WatchListFocus {
     Content,
 }
 
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum HistoryFocus {
+    Menu,
+    Content,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum HistoryRange {
+    Day,
+    Week,
+    Month,
+    Year,
+    All,
+}
+
+impl HistoryRange {
+    pub const ALL: [HistoryRange; 5] = [
+        HistoryRange::Day,
+        HistoryRange::Week,
+        HistoryRange::Month,
+        HistoryRange::Year,
+        HistoryRange::All,
+    ];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            HistoryRange::Day => "24h",
+            HistoryRange::Week => "7d",
+            HistoryRange::Month => "30d",
+            HistoryRange::Year => "1y",
+            HistoryRange::All => "All",
+        }
+    }
+
+    pub fn duration(&self) -> Option<Duration> {
+        match self {
+            HistoryRange::Day => Some(Duration::hours(24)),
+            HistoryRange::Week => Some(Duration::days(7)),
+            HistoryRange::Month => Some(Duration::days(30)),
+            HistoryRange::Year => Some(Duration::days(365)),
+            HistoryRange::All => None,
+        }
+    }
+}
+
 pub struct AppState {
     pub current_page: Page,
+    /// Every profile in the database, ordered by name
     pub users: Vec<User>,
+    /// Cursor in the Account page's profile list
     pub selected_user_index: usize,
+    /// The logged-in profile whose portfolio is shown
+    pub active_user_index: usize,
+    /// Passphrase typed to switch to the selected profile
+    pub passphrase_input: Option<String>,
     pub selected_wallet_index: usize,
     pub menu_items: Vec<MenuItem>,
     pub focused_menu_items: Vec<MenuItem>,
@@ -61,12 +184,18 @@ pub struct AppState {
     pub positions_ft: Vec<FtPosition>,
     pub positions_nft: Vec<NftPosition>,
     pub positions_lp: Vec<LpPosition>,
+    pub wallet_portfolios: Vec<WalletPortfolio>,
+    /// Wallet id the Positions page is limited to, `None` for all wallets
+    pub positions_wallet: Option<String>,
     pub ada_balance: f64,
     pub ada_value: f64,
     pub liquid_value: f64,
     pub account_menu_items: Vec<String>,
     pub selected_account_menu_item: usize,
     pub account_focus: AccountFocus,
+    pub wallet_panel: WalletPanel,
+    pub selected_address_index: usize,
+    pub wallet_form: Option<WalletForm>,
     pub positions_focus: PositionsFocus,
     pub positions_menu_items: Vec<String>,
     pub selected_positions_menu_item: usize,
@@ -78,7 +207,17 @@ pub struct AppState {
     pub selected_lp_row: usize,
     pub ada_usd_price: f64,
     pub btc_usd_price: f64,
+    pub prices: Prices,
+    pub display_currency: DisplayCurrency,
+    pub cost_basis: CostBasisBook,
     pub selected_market_cap_row: Option<usize>,
+    pub history: Vec<PortfolioSnapshot>,
+    pub history_focus: HistoryFocus,
+    pub selected_history_range: usize,
+    pub selected_history_position: usize,
+    pub status_message: Option<String>,
+    /// Whether the status message reports a failure rather than a confirmation
+    pub status_is_error: bool,
 }
 
 trait CircularNavigation {
@@ -107,13 +246,13 @@ impl<T> CircularNavigation for Vec<T> {
 }
 
 impl AppState {
-    pub fn new(portfolio_json: String, user: User, ada_price: f64, btc_price: f64) -> Self {
-        let portfolio: PortfolioSummary = serde_json::from_str(&portfolio_json)
-            .expect("Failed to parse portfolio data");
+    pub fn new(portfolio: UserPortfolio, user: User, prices: Prices) -> Self {
+        let UserPortfolio { total, wallets } = portfolio;
 
         let menu_items = vec![
             MenuItem::new("p", "Crypto Positions", Page::Positions),
             MenuItem::new("w", "Watch List", Page::WatchList),
+            MenuItem::new("h", "History", Page::History),
             MenuItem::new("a", "Account", Page::Account),
             MenuItem::new("r", "Refresh", Page::Positions),
             MenuItem::new("q", "Quit", Page::Quit),
@@ -124,20 +263,27 @@ impl AppState {
             MenuItem::new("esc", "Back", Page::Back),
         ];
 
+        let display_currency = user.display_currency;
+        let cost_basis = user.effective_cost_basis();
+
         AppState {
             current_page: Page::Positions,
             users: vec![user],
             selected_user_index: 0,
+            active_user_index: 0,
+            passphrase_input: None,
             selected_wallet_index: 0,
             menu_items,
             focused_menu_items,
             current_menu_item: 0,
-            positions_ft: portfolio.positions_ft,
-            positions_nft: portfolio.positions_nft,
-            positions_lp: portfolio.positions_lp,
-            ada_balance: portfolio.ada_balance,
-            ada_value: portfolio.ada_value,
-            liquid_value: portfolio.liquid_value,
+            positions_ft: total.positions_ft,
+            positions_nft: total.positions_nft,
+            positions_lp: total.positions_lp,
+            wallet_portfolios: wallets,
+            positions_wallet: None,
+            ada_balance: total.ada_balance,
+            ada_value: total.ada_value,
+            liquid_value: total.liquid_value,
             account_menu_items: vec![
                 "Profile".to_string(),
                 "Wallets".to_string(),
@@ -145,6 +291,9 @@ impl AppState {
             ],
             selected_account_menu_item: 0,
             account_focus: AccountFocus::Menu,
+            wallet_panel: WalletPanel::Wallets,
+            selected_address_index: 0,
+            wallet_form: None,
             positions_focus: PositionsFocus::Menu,
             positions_menu_items: vec![
                 "Fungible Tokens".to_string(),
@@ -158,9 +307,18 @@ impl AppState {
             selected_ft_row: 0,
             selected_nft_row: 0,
             selected_lp_row: 0,
-            ada_usd_price: ada_price,
-            btc_usd_price: btc_price,
+            ada_usd_price: prices.price(CARDANO_ID, QuoteCurrency::Usd),
+            btc_usd_price: prices.price(BITCOIN_ID, QuoteCurrency::Usd),
+            prices,
+            display_currency,
+            cost_basis,
             selected_market_cap_row: Some(0),
+            history: Vec::new(),
+            history_focus: HistoryFocus::Menu,
+            selected_history_range: 0,
+            selected_history_position: 0,
+            status_message: None,
+            status_is_error: false,
         }
     }
 
@@ -197,11 +355,174 @@ impl AppState {
         };
     }
 
+    pub fn active_user(&self) -> Option<&User> {
+        self.users.get(self.active_user_index)
+    }
+
+    pub fn next_user(&mut self) {
+        self.selected_user_index = self.navigate_next(&self.users, self.selected_user_index);
+    }
+
+    pub fn previous_user(&mut self) {
+        self.selected_user_index = self.navigate_previous(&self.users, self.selected_user_index);
+    }
+
+    /// Replaces the profile list with every stored user. The active user
+    /// keeps their logged-in copy and both cursors stay on the same users.
+    pub fn set_users(&mut self, mut users: Vec<User>) {
+        let active = self.active_user().cloned();
+        let active_id = active.as_ref().map(|user| user.id.clone());
+        let selected_id = self.users.get(self.selected_user_index).map(|user| user.id.clone());
+        if let Some(active) = active {
+            match users.iter_mut().find(|user| user.id == active.id) {
+                Some(user) => *user = active,
+                None => users.push(active),
+            }
+        }
+        users.sort_by(|a, b| a.name.cmp(&b.name));
+
+        let index_of = |id: Option<&str>| users.iter().position(|user| Some(user.id.as_str()) == id);
+        self.active_user_index = index_of(active_id.as_deref()).unwrap_or(0);
+        self.selected_user_index = index_of(selected_id.as_deref()).unwrap_or(self.active_user_index);
+        self.users = users;
+    }
+
+    /// Starts asking for the passphrase of the selected profile, unless it
+    /// is already the active one. A profile whose API key is still stored in
+    /// plain text cannot be unlocked, so it only gets the command to fix it.
+    pub fn open_passphrase_prompt(&mut self) -> bool {
+        if self.users.is_empty() || self.selected_user_index == self.active_user_index {
+            return false;
+        }
+        let user = &self.users[self.selected_user_index];
+        if user.encrypted_api_key.is_none() {
+            let message = format!(
+                "The API key of {} is stored unencrypted; run `rotate-passphrase --user {}` to encrypt it",
+                user.name, user.name,
+            );
+            self.set_status_message(message);
+            return false;
+        }
+        self.passphrase_input = Some(String::new());
+        true
+    }
+
+    pub fn close_passphrase_prompt(&mut self) {
+        self.passphrase_input = None;
+    }
+
+    /// Makes `user`, just logged in, the active profile and shows their
+    /// portfolio. Everything selected for the previous user is reset.
+    pub fn switch_user(&mut self, user: User, portfolio: UserPortfolio, prices: Prices) {
+        let index = match self.users.iter().position(|existing| existing.id == user.id) {
+            Some(index) => index,
+            None => {
+                self.users.push(user.clone());
+                self.users.len() - 1
+            }
+        };
+        self.display_currency = user.display_currency;
+        self.cost_basis = user.effective_cost_basis();
+        self.users[index] = user;
+        self.active_user_index = index;
+        self.selected_user_index = index;
+
+        self.selected_wallet_index = 0;
+        self.selected_address_index = 0;
+        self.wallet_panel = WalletPanel::Wallets;
+        self.selected_ft_row = 0;
+        self.selected_nft_row = 0;
+        self.selected_lp_row = 0;
+        self.positions_wallet = None;
+        self.update_portfolio(portfolio);
+        self.update_prices(prices);
+        self.set_history(Vec::new());
+    }
+
+    pub fn selected_wallet(&self) -> Option<&Wallet> {
+        self.active_user()?
+            .wallets
+            .get(self.selected_wallet_index)
+    }
+
+    pub fn selected_address(&self) -> Option<&String> {
+        self.selected_wallet()?.addresses.get(self.selected_address_index)
+    }
+
+    pub fn next_wallet(&mut self) {
+        if let Some(user) = self.active_user() {
+            self.selected_wallet_index = user.wallets.next_index(self.selected_wallet_index);
+            self.selected_address_index = 0;
+        }
+    }
+
+    pub fn previous_wallet(&mut self) {
+        if let Some(user) = self.active_user() {
+            self.selected_wallet_index = user.wallets.previous_index(self.selected_wallet_index);
+            self.selected_address_index = 0;
+        }
+    }
+
+    pub fn next_address(&mut self) {
+        if let Some(wallet) = self.selected_wallet() {
+            self.selected_address_index = wallet.addresses.next_index(self.selected_address_index);
+        }
+    }
+
+    pub fn previous_address(&mut self) {
+        if let Some(wallet) = self.selected_wallet() {
+            self.selected_address_index = wallet.addresses.previous_index(self.selected_address_index);
+        }
+    }
+
+    pub fn toggle_wallet_panel(&mut self) {
+        self.wallet_panel = match self.wallet_panel {
+            WalletPanel::Wallets => WalletPanel::Addresses,
+            WalletPanel::Addresses => WalletPanel::Wallets,
+        };
+    }
+
+    /// Opens the form for `action`. Actions on an existing wallet or address
+    /// need one to be selected; renaming starts from the current name.
+    pub fn open_wallet_form(&mut self, action: WalletAction) -> bool {
+        let input = match action {
+            WalletAction::AddWallet => Some(String::new()),
+            WalletAction::RenameWallet => self.selected_wallet().map(|wallet| wallet.name.clone()),
+            WalletAction::DeleteWallet | WalletAction::AddAddress | WalletAction::RegisterStakeAddress => {
+                self.selected_wallet().map(|_| String::new())
+            },
+            WalletAction::RemoveAddress => self.selected_address().map(|_| String::new()),
+        };
+        match input {
+            Some(input) => {
+                self.wallet_form = Some(WalletForm { action, input });
+                true
+            }
+            None => false,
+        }
+    }
+
+    pub fn close_wallet_form(&mut self) {
+        self.wallet_form = None;
+    }
+
+    /// Replaces the current user's wallets after a change was saved, keeping
+    /// the selection within the new lists
+    pub fn set_wallets(&mut self, wallets: Vec<Wallet>) {
+        if let Some(user) = self.users.get_mut(self.active_user_index) {
+            user.wallets = wallets;
+            self.selected_wallet_index = self.selected_wallet_index.min(user.wallets.len().saturating_sub(1));
+        }
+        let addresses = self.selected_wallet().map_or(0, |wallet| wallet.addresses.len());
+        self.selected_address_index = self.selected_address_index.min(addresses.saturating_sub(1));
+    }
+
     pub fn is_content_focused(&self) -> bool {
         match self.current_page() {
             Page::Account => self.account_focus == AccountFocus::Content,
             Page::Positions => self.positions_focus == PositionsFocus::Content,
             Page::WatchList => self.watch_list_focus == WatchListFocus::Content,
+            Page::History => self.history_focus == HistoryFocus::Content,
             _ => false,
         }
     }
@@ -221,17 +542,152 @@ impl AppState {
         };
     }
 
-    pub fn update_portfolio(&mut self, portfolio_json: String) {
-        if let Ok(portfolio) = serde_json::from_str::<PortfolioSummary>(&portfolio_json) {
-            self.positions_ft = portfolio.positions_ft;
-            self.positions_nft = portfolio.positions_nft;
-            self.positions_lp = portfolio.positions_lp;
-            self.ada_balance = portfolio.ada_balance;
-            self.ada_value = portfolio.ada_value;
-            self.liquid_value = portfolio.liquid_value;
+    pub fn update_portfolio(&mut self, portfolio: UserPortfolio) {
+        let UserPortfolio { total, wallets } = portfolio;
+        self.positions_ft = total.positions_ft;
+        self.positions_nft = total.positions_nft;
+        self.positions_lp = total.positions_lp;
+        self.wallet_portfolios = wallets;
+        // Fall back to all wallets when the chosen one is gone
+        if self.positions_wallet_portfolio().is_none() {
+            self.positions_wallet = None;
+        }
+        self.ada_balance = total.ada_balance;
+        self.ada_value = total.ada_value;
+        self.liquid_value = total.liquid_value;
+    }
+
+    pub fn update_prices(&mut self, prices: Prices) {
+        self.ada_usd_price = prices.price(CARDANO_ID, QuoteCurrency::Usd);
+        self.btc_usd_price = prices.price(BITCOIN_ID, QuoteCurrency::Usd);
+        self.prices = prices;
+    }
+
+    pub fn cycle_display_currency(&mut self) -> DisplayCurrency {
+        self.display_currency = self.display_currency.next();
+        self.display_currency
+    }
+
+    /// Converts an ADA amount into the selected display currency
+    pub fn to_display_currency(&self, ada: f64) -> f64 {
+        match self.display_currency.quote_currency() {
+            Some(quote) => ada * self.prices.price(CARDANO_ID, quote),
+            None => ada,
+        }
+    }
+
+    /// Converts and formats an ADA amount in the selected display currency
+    pub fn format_value(&self, ada: f64, decimals: usize) -> String {
+        format_currency(self.to_display_currency(ada), self.display_currency, decimals)
+    }
+
+    /// Fiat currency for the P&L column: the display currency when it is a
+    /// fiat one, USD otherwise
+    pub fn pnl_fiat_currency(&self) -> DisplayCurrency {
+        match self.display_currency {
+            DisplayCurrency::Ada | DisplayCurrency::Btc => DisplayCurrency::Usd,
+            fiat => fiat,
         }
     }
 
+    /// Converts and formats an ADA amount in the P&L fiat currency
+    pub fn format_fiat_value(&self, ada: f64, decimals: usize) -> String {
+        let currency = self.pnl_fiat_currency();
+        let fiat = currency.quote_currency()
+            .map(|quote| ada * self.prices.price(CARDANO_ID, quote))
+            .unwrap_or(ada);
+        format_currency(fiat, currency, decimals)
+    }
+
+    /// Unrealized P&L in ADA of an FT position, `None` when the current user
+    /// has no lots recorded for its unit
+    pub fn unrealized_pnl(&self, position: &FtPosition) -> Option<f64> {
+        self.cost_basis.unrealized_pnl(&position.unit, position.balance, position.ada_value)
+    }
+
+    pub fn set_status_message(&mut self, message: String) {
+        self.status_message = Some(message);
+        self.status_is_error = true;
+    }
+
+    /// Shows a confirmation in the status line
+    pub fn set_info_message(&mut self, message: String) {
+        self.status_message = Some(message);
+        self.status_is_error = false;
+    }
+
+    /// The table on screen, if the current page shows one that can be exported
+    pub fn exportable_table(&self) -> Option<ExportTable> {
+        match self.current_page() {
+            Page::Positions => match self.selected_positions_menu_item {
+                0 => Some(ExportTable::FungibleTokens),
+                1 => Some(ExportTable::NonFungibleTokens),
+                2 => Some(ExportTable::LiquidityPositions),
+                _ => None,
+            },
+            Page::WatchList if self.selected_watch_list_menu_item == 2 => Some(ExportTable::MarketCaps),
+            _ => None,
+        }
+    }
+
+    /// The wallet the Positions page is limited to, if any
+    pub fn positions_wallet_portfolio(&self) -> Option<&WalletPortfolio> {
+        let id = self.positions_wallet.as_ref()?;
+        self.wallet_portfolios.iter().find(|wallet| &wallet.wallet_id == id)
+    }
+
+    /// Steps the Positions page through all wallets, then each wallet in turn
+    pub fn cycle_positions_wallet(&mut self) {
+        let next = match self.positions_wallet.as_ref() {
+            None => 0,
+            Some(id) => self.wallet_portfolios.iter()
+                .position(|wallet| &wallet.wallet_id == id)
+                .map_or(0, |index| index + 1),
+        };
+        self.positions_wallet = self.wallet_portfolios.get(next).map(|wallet| wallet.wallet_id.clone());
+        self.selected_ft_row = 0;
+        self.selected_nft_row = 0;
+        self.selected_lp_row = 0;
+    }
+
+    /// Name of the wallet the Positions page shows
+    pub fn positions_wallet_label(&self) -> &str {
+        self.positions_wallet_portfolio()
+            .map_or("All wallets", |wallet| wallet.wallet_name.as_str())
+    }
+
+    /// ADA value of the holdings the Positions page shows
+    pub fn visible_ada_value(&self) -> f64 {
+        self.positions_wallet_portfolio()
+            .map_or(self.ada_value, |wallet| wallet.summary.ada_value)
+    }
+
+    /// Fungible tokens in the order the table shows them
+    pub fn visible_positions_ft(&self) -> Vec<&FtPosition> {
+        match self.positions_wallet_portfolio() {
+            Some(wallet) => wallet.summary.positions_ft.iter().collect(),
+            None => self.positions_ft.iter().collect(),
+        }
+    }
+
+    pub fn visible_positions_nft(&self) -> Vec<&NftPosition> {
+        match self.positions_wallet_portfolio() {
+            Some(wallet) => wallet.summary.positions_nft.iter().collect(),
+            None => self.positions_nft.iter().collect(),
+        }
+    }
+
+    pub fn visible_positions_lp(&self) -> Vec<&LpPosition> {
+        match self.positions_wallet_portfolio() {
+            Some(wallet) => wallet.summary.positions_lp.iter().collect(),
+            None => self.positions_lp.iter().collect(),
+        }
+    }
+
+    pub fn clear_status_message(&mut self) {
+        self.status_message = None;
+    }
+
     pub fn next_watch_list_menu_item(&mut self) {
         self.selected_watch_list_menu_item = self.navigate_next(&self.positions_menu_items, self.selected_watch_list_menu_item);
     }
@@ -247,28 +703,91 @@ impl AppState {
         };
     }
 
+    pub fn set_history(&mut self, history: Vec<PortfolioSnapshot>) {
+        self.history = history;
+        self.selected_history_position = 0;
+    }
+
+    pub fn history_range(&self) -> HistoryRange {
+        HistoryRange::ALL[self.selected_history_range]
+    }
+
+    pub fn next_history_range(&mut self) {
+        self.selected_history_range = (self.selected_history_range + 1) % HistoryRange::ALL.len();
+    }
+
+    pub fn previous_history_range(&mut self) {
+        self.selected_history_range = match self.selected_history_range {
+            0 => HistoryRange::ALL.len() - 1,
+            index => index - 1,
+        };
+    }
+
+    pub fn toggle_history_focus(&mut self) {
+        self.history_focus = match self.history_focus {
+            HistoryFocus::Menu => HistoryFocus::Content,
+            HistoryFocus::Content => HistoryFocus::Menu,
+        };
+    }
+
+    /// Snapshots within the selected range, ending at `now`
+    pub fn history_in_range(&self, now: DateTime<Utc>) -> Vec<&PortfolioSnapshot> {
+        match self.history_range().duration() {
+            Some(duration) => {
+                let start = now - duration;
+                self.history.iter().filter(|s| s.timestamp >= start).collect()
+            },
+            None => self.history.iter().collect(),
+        }
+    }
+
+    /// Units of positions in the latest snapshot, largest ADA value first
+    pub fn history_position_units(&self) -> Vec<String> {
+        let mut positions: Vec<_> = self.history.last()
+            .map(|snapshot| snapshot.positions.iter().collect())
+            .unwrap_or_default();
+        positions.sort_by(|a, b| b.ada_value.total_cmp(&a.ada_value));
+        positions.into_iter().map(|p| p.unit.clone()).collect()
+    }
+
+    pub fn next_history_position(&mut self) {
+        let units = self.history_position_units();
+        self.selected_history_position = self.navigate_next(&units, self.selected_history_position);
+    }
+
+    pub fn previous_history_position(&mut self) {
+        let units = self.history_position_units();
+        self.selected_history_position = self.navigate_previous(&units, self.selected_history_position);
+    }
+
     pub fn next_ft_row(&mut self) {
-        self.selected_ft_row = self.navigate_next(&self.positions_ft, self.selected_ft_row);
+        let rows = self.visible_positions_ft();
+        self.selected_ft_row = self.navigate_next(&rows, self.selected_ft_row);
     }
 
     pub fn previous_ft_row(&mut self) {
-        self.selected_ft_row = self.navigate_previous(&self.positions_ft, self.selected_ft_row);
+        let rows = self.visible_positions_ft();
+        self.selected_ft_row = self.navigate_previous(&rows, self.selected_ft_row);
     }
 
     pub fn next_nft_row(&mut self) {
-        self.selected_nft_row = self.navigate_next(&self.positions_nft, self.selected_nft_row);
+        let rows = self.visible_positions_nft();
+        self.selected_nft_row = self.navigate_next(&rows, self.selected_nft_row);
     }
 
     pub fn previous_nft_row(&mut self) {
-        self.selected_nft_row = self.navigate_previous(&self.positions_nft, self.selected_nft_row);
+        let rows = self.visible_positions_nft();
+        self.selected_nft_row = self.navigate_previous(&rows, self.selected_nft_row);
     }
 
     pub fn next_lp_row(&mut self) {
-        self.selected_lp_row = self.navigate_next(&self.positions_lp, self.selected_lp_row);
+        let rows = self.visible_positions_lp();
+        self.selected_lp_row = self.navigate_next(&rows, self.selected_lp_row);
     }
 
     pub fn previous_lp_row(&mut self) {
-        self.selected_lp_row = self.navigate_previous(&self.positions_lp, self.selected_lp_row);
+        let rows = self.visible_positions_lp();
+        self.selected_lp_row = self.navigate_previous(&rows, self.selected_lp_row);
     }
 
     // Generic navigation methods
@@ -0,0 +1,18 @@
+use ratatui::layout::Rect;
+use crate::ui::pages::common::centered_rect;
+
+#[test]
+fn test_centered_rect_in_the_middle() {
+    let area = Rect { x: 10, y: 5, width: 100, height: 40 };
+
+    assert_eq!(centered_rect(60, 10, area), Rect { x: 30, y: 20, width: 60, height: 10 });
+    // Never taller or wider than the area
+    assert_eq!(centered_rect(150, 50, area), area);
+}
+
+#[test]
+fn test_centered_rect_on_a_very_wide_terminal() {
+    let area = Rect { x: 0, y: 0, width: 1000, height: 50 };
+
+    assert_eq!(centered_rect(60, 10, area).width, 600);
+}
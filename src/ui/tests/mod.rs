@@ -1,5 +1,4 @@
 #[cfg(test)]
-mod state_test;
-
+mod common_test;
 #[cfg(test)]
-pub use state_test::*;
+mod state_test;
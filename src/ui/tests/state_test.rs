@@ -1,5 +1,17 @@
-use crate::models::user::User;
-use crate::ui::state::{AppState, Page, AccountFocus, PositionsFocus, WatchListFocus};
+use crate::db::secret::{EncryptedSecret, KdfParams};
+use crate::models::portfolio_snapshot::PortfolioSnapshot;
+use crate::models::portfolio_summary::PortfolioSummary;
+use chrono::{Duration, Local, TimeZone, Utc};
+use crate::models::{cost_basis::CostBasisBook, display_currency::DisplayCurrency, user::User};
+use crate::models::user_portfolio::{UserPortfolio, WalletPortfolio};
+use crate::services::price::{Prices, Quote, QuoteCurrency, BITCOIN_ID, CARDANO_ID};
+use crate::reports::ReportFormat;
+use crate::tests::temp_dir;
+use crate::ui::export::export_table;
+use crate::models::wallet::Wallet;
+use crate::ui::state::{
+    AppState, ExportTable, Page, AccountFocus, WatchListFocus, HistoryFocus, HistoryRange, WalletAction,
+};
 
 // Helper function to create a basic AppState for testing
 fn create_test_state() -> AppState {
@@ -7,7 +19,11 @@ fn create_test_state() -> AppState {
         id: "test_id".to_string(),
         name: "Test User".to_string(),
         taptools_api_key: "test_key".to_string(),
+        encrypted_api_key: None,
         wallets: vec![],
+        display_currency: DisplayCurrency::Ada,
+        cost_basis: CostBasisBook::default(),
+        ledger: Vec::new(),
     };
     
     let portfolio_json = r#"{
@@ -20,9 +36,19 @@ fn create_test_state() -> AppState {
         "adaBalance": 1000.0,
         "adaValue": 1000.0,
         "liquidValue": 1000.0
-    }"#.to_string();
+    }"#;
 
-    AppState::new(portfolio_json, user, 0.5, 30000.0)
+    let portfolio = UserPortfolio {
+        total: serde_json::from_str(portfolio_json).unwrap(),
+        wallets: vec![],
+    };
+
+    let mut prices = Prices::default();
+    prices.insert(CARDANO_ID, QuoteCurrency::Usd, Quote { price: 0.5, change_24h: Some(1.5) });
+    prices.insert(BITCOIN_ID, QuoteCurrency::Usd, Quote { price: 30000.0, change_24h: None });
+    prices.insert(CARDANO_ID, QuoteCurrency::Eur, Quote { price: 0.4, change_24h: None });
+
+    AppState::new(portfolio, user, prices)
 }
 
 #[test]
@@ -32,7 +58,7 @@ fn test_app_state_new() {
     assert_eq!(state.current_page, Page::Positions);
     assert_eq!(state.ada_usd_price, 0.5);
     assert_eq!(state.btc_usd_price, 30000.0);
-    assert_eq!(state.menu_items.len(), 5);
+    assert_eq!(state.menu_items.len(), 6);
     assert_eq!(state.focused_menu_items.len(), 2);
 }
 
@@ -127,13 +153,20 @@ fn test_portfolio_update() {
         "adaBalance": 2000.0,
         "adaValue": 2500.0,
         "liquidValue": 3000.0
-    }"#.to_string();
+    }"#;
+    let summary: PortfolioSummary = serde_json::from_str(updated_portfolio).unwrap();
     
-    state.update_portfolio(updated_portfolio);
+    state.update_portfolio(UserPortfolio::from_wallets(vec![WalletPortfolio {
+        wallet_id: "wallet_1".to_string(),
+        wallet_name: "Main".to_string(),
+        summary,
+    }]));
     
     assert_eq!(state.ada_balance, 2000.0);
     assert_eq!(state.ada_value, 2500.0);
     assert_eq!(state.liquid_value, 3000.0);
+    assert_eq!(state.wallet_portfolios.len(), 1);
+    assert_eq!(state.wallet_portfolios[0].wallet_name, "Main");
 }
 
 #[test]
@@ -158,3 +191,341 @@ fn test_is_content_focused() {
     state.toggle_watch_list_focus();
     assert!(state.is_content_focused());
 }
+
+#[test]
+fn test_status_message() {
+    let mut state = create_test_state();
+
+    assert!(state.status_message.is_none());
+
+    state.set_status_message("Refresh failed: Rate limited".to_string());
+    assert_eq!(state.status_message.as_deref(), Some("Refresh failed: Rate limited"));
+
+    state.clear_status_message();
+    assert!(state.status_message.is_none());
+}
+
+#[test]
+fn test_display_currency_conversion() {
+    let mut state = create_test_state();
+
+    assert_eq!(state.display_currency, DisplayCurrency::Ada);
+    assert_eq!(state.format_value(1000.0, 2), "₳1,000.00");
+
+    assert_eq!(state.cycle_display_currency(), DisplayCurrency::Usd);
+    assert_eq!(state.to_display_currency(1000.0), 500.0);
+    assert_eq!(state.format_value(1000.0, 2), "$500.00");
+
+    state.cycle_display_currency();
+    assert_eq!(state.format_value(1000.0, 2), "€400.00");
+}
+
+#[test]
+fn test_display_currency_cycles_back_to_ada() {
+    let mut state = create_test_state();
+
+    for _ in 0..DisplayCurrency::ALL.len() {
+        state.cycle_display_currency();
+    }
+    assert_eq!(state.display_currency, DisplayCurrency::Ada);
+}
+
+#[test]
+fn test_history_page_navigation() {
+    let mut state = create_test_state();
+
+    state.set_current_page(Page::History);
+    assert_eq!(*state.current_page(), Page::History);
+    assert!(!state.is_content_focused());
+
+    state.toggle_history_focus();
+    assert_eq!(state.history_focus, HistoryFocus::Content);
+    assert!(state.is_content_focused());
+}
+
+#[test]
+fn test_history_range_navigation() {
+    let mut state = create_test_state();
+
+    assert_eq!(state.history_range(), HistoryRange::Day);
+
+    state.previous_history_range();
+    assert_eq!(state.history_range(), HistoryRange::All);
+
+    state.next_history_range();
+    state.next_history_range();
+    assert_eq!(state.history_range(), HistoryRange::Week);
+}
+
+#[test]
+fn test_history_in_range() {
+    let mut state = create_test_state();
+    let now = Utc::now();
+    let summary = PortfolioSummary::default();
+
+    state.set_history(vec![
+        PortfolioSnapshot::from_summary("test_id", &summary, 0.5, now - Duration::days(40)),
+        PortfolioSnapshot::from_summary("test_id", &summary, 0.5, now - Duration::days(3)),
+        PortfolioSnapshot::from_summary("test_id", &summary, 0.5, now - Duration::hours(1)),
+    ]);
+
+    assert_eq!(state.history_in_range(now).len(), 1);
+
+    state.next_history_range();  // 7d
+    assert_eq!(state.history_in_range(now).len(), 2);
+
+    state.previous_history_range();
+    state.previous_history_range();  // All
+    assert_eq!(state.history_in_range(now).len(), 3);
+}
+
+
+// Helper function to build a summary holding a single fungible token
+fn summary_with_ft(unit: &str, balance: f64, ada_value: f64) -> PortfolioSummary {
+    serde_json::from_str(&format!(r#"{{
+        "numFTs": 1,
+        "numNFTs": 0,
+        "positionsFt": [{{
+            "balance": {balance},
+            "liquidBalance": {balance},
+            "adaValue": {ada_value},
+            "liquidValue": {ada_value},
+            "price": null,
+            "ticker": "TKN",
+            "unit": "{unit}",
+            "fingerprint": "asset1",
+            "24h": null,
+            "7d": null,
+            "30d": null
+        }}],
+        "positionsNft": [],
+        "positionsLp": [],
+        "adaBalance": 0.0,
+        "adaValue": {ada_value},
+        "liquidValue": {ada_value}
+    }}"#)).unwrap()
+}
+
+#[test]
+fn test_exportable_table_follows_page() {
+    let mut state = create_test_state();
+    assert_eq!(state.exportable_table(), Some(ExportTable::FungibleTokens));
+
+    state.next_positions_menu_item();
+    state.next_positions_menu_item();
+    assert_eq!(state.exportable_table(), Some(ExportTable::LiquidityPositions));
+
+    state.set_current_page(Page::WatchList);
+    assert_eq!(state.exportable_table(), None);
+    state.selected_watch_list_menu_item = 2;
+    assert_eq!(state.exportable_table(), Some(ExportTable::MarketCaps));
+
+    state.set_current_page(Page::History);
+    assert_eq!(state.exportable_table(), None);
+}
+
+#[test]
+fn test_export_writes_timestamped_file() {
+    let mut state = create_test_state();
+    state.update_portfolio(UserPortfolio {
+        total: summary_with_ft("unit1", 10.0, 5.0),
+        wallets: vec![],
+    });
+
+    // Created on the first export
+    let dir = temp_dir("export_test").join("exports");
+    let now = Local.with_ymd_and_hms(2024, 5, 1, 9, 30, 0).unwrap();
+
+    let (path, rows) = export_table(&state, ReportFormat::Json, &dir, now).unwrap();
+    assert_eq!(rows, 1);
+    assert_eq!(path.file_name().unwrap(), "fungible_tokens_20240501_093000.json");
+    assert!(path.is_absolute());
+    let json: serde_json::Value = serde_json::from_str(&std::fs::read_to_string(&path).unwrap()).unwrap();
+    assert_eq!(json[0]["unit"], "unit1");
+
+    state.set_current_page(Page::History);
+    assert!(export_table(&state, ReportFormat::Csv, &dir, now).is_err());
+    std::fs::remove_dir_all(dir.parent().unwrap()).unwrap();
+}
+
+fn wallet(name: &str, addresses: &[&str]) -> Wallet {
+    Wallet {
+        id: name.to_lowercase(),
+        name: name.to_string(),
+        addresses: addresses.iter().map(|address| address.to_string()).collect(),
+    }
+}
+
+#[test]
+fn test_wallet_navigation() {
+    let mut state = create_test_state();
+    assert!(state.selected_wallet().is_none());
+    state.next_wallet();
+    assert_eq!(state.selected_wallet_index, 0);
+
+    state.set_wallets(vec![wallet("Main", &["addr1", "addr2"]), wallet("Cold", &[])]);
+    state.next_address();
+    assert_eq!(state.selected_address().map(String::as_str), Some("addr2"));
+
+    state.next_wallet();
+    assert_eq!(state.selected_wallet().unwrap().name, "Cold");
+    assert_eq!(state.selected_address_index, 0);
+    assert!(state.selected_address().is_none());
+
+    state.next_wallet();
+    assert_eq!(state.selected_wallet().unwrap().name, "Main");
+}
+
+#[test]
+fn test_wallet_form_needs_a_selection() {
+    let mut state = create_test_state();
+
+    assert!(!state.open_wallet_form(WalletAction::RenameWallet));
+    assert!(!state.open_wallet_form(WalletAction::AddAddress));
+    assert!(state.open_wallet_form(WalletAction::AddWallet));
+    state.close_wallet_form();
+
+    state.set_wallets(vec![wallet("Main", &[])]);
+    assert!(!state.open_wallet_form(WalletAction::RemoveAddress));
+    assert!(state.open_wallet_form(WalletAction::RenameWallet));
+    let form = state.wallet_form.as_ref().unwrap();
+    assert_eq!(form.input, "Main");
+    assert!(form.action.takes_input());
+    assert!(!WalletAction::DeleteWallet.takes_input());
+}
+
+#[test]
+fn test_set_wallets_keeps_selection_in_range() {
+    let mut state = create_test_state();
+    state.set_wallets(vec![wallet("Main", &["addr1", "addr2"]), wallet("Cold", &["addr3"])]);
+    state.next_wallet();
+
+    state.set_wallets(vec![wallet("Main", &["addr1", "addr2"])]);
+    assert_eq!(state.selected_wallet_index, 0);
+
+    state.next_address();
+    state.set_wallets(vec![wallet("Main", &["addr1"])]);
+    assert_eq!(state.selected_address().map(String::as_str), Some("addr1"));
+}
+
+fn profile(id: &str, name: &str) -> User {
+    User {
+        id: id.to_string(),
+        name: name.to_string(),
+        taptools_api_key: String::new(),
+        encrypted_api_key: None,
+        wallets: vec![wallet("Main", &["addr1"])],
+        display_currency: DisplayCurrency::Usd,
+        cost_basis: CostBasisBook::default(),
+        ledger: Vec::new(),
+    }
+}
+
+#[test]
+fn test_set_users_keeps_logged_in_user_active() {
+    let mut state = create_test_state();
+    let stored = User { taptools_api_key: String::new(), ..state.users[0].clone() };
+    state.set_users(vec![profile("zed", "Zed"), stored, profile("amy", "Amy")]);
+
+    let names: Vec<_> = state.users.iter().map(|user| user.name.as_str()).collect();
+    assert_eq!(names, vec!["Amy", "Test User", "Zed"]);
+    assert_eq!(state.active_user_index, 1);
+    assert_eq!(state.selected_user_index, 1);
+    // The stored copy never replaces the unlocked key
+    assert_eq!(state.active_user().unwrap().taptools_api_key, "test_key");
+}
+
+#[test]
+fn test_passphrase_prompt_only_for_other_profiles() {
+    let mut state = create_test_state();
+    state.set_users(vec![profile("amy", "Amy")]);
+    state.selected_user_index = state.active_user_index;
+    assert!(!state.open_passphrase_prompt());
+
+    state.next_user();
+    assert_eq!(state.users[state.selected_user_index].name, "Amy");
+    // A plain-text key cannot be unlocked, so there is nothing to type
+    assert!(!state.open_passphrase_prompt());
+    assert!(state.passphrase_input.is_none());
+    assert!(state.status_message.as_deref().unwrap().contains("rotate-passphrase --user Amy"));
+
+    let kdf = KdfParams { memory_kib: 64, iterations: 1, parallelism: 1 };
+    let index = state.selected_user_index;
+    state.users[index].encrypted_api_key = Some(EncryptedSecret::seal_with("amy_key", "secret", kdf).unwrap());
+    assert!(state.open_passphrase_prompt());
+    assert_eq!(state.passphrase_input.as_deref(), Some(""));
+    state.close_passphrase_prompt();
+    assert!(state.passphrase_input.is_none());
+}
+
+#[test]
+fn test_switch_user_shows_new_profile() {
+    let mut state = create_test_state();
+    state.set_users(vec![profile("amy", "Amy")]);
+    state.selected_ft_row = 3;
+    state.history = vec![PortfolioSnapshot::from_summary("test_id", &PortfolioSummary::default(), 0.5, Utc::now())];
+
+    let portfolio = UserPortfolio {
+        total: PortfolioSummary { ada_balance: 42.0, ..PortfolioSummary::default() },
+        wallets: vec![],
+    };
+    let amy = User { taptools_api_key: "amy_key".to_string(), ..profile("amy", "Amy") };
+    state.switch_user(amy, portfolio, state.prices.clone());
+
+    let active = state.active_user().unwrap();
+    assert_eq!(active.name, "Amy");
+    assert_eq!(active.taptools_api_key, "amy_key");
+    assert_eq!(state.selected_user_index, state.active_user_index);
+    assert_eq!(state.display_currency, DisplayCurrency::Usd);
+    assert_eq!(state.ada_balance, 42.0);
+    assert_eq!(state.selected_ft_row, 0);
+    assert!(state.history.is_empty());
+    assert_eq!(state.selected_wallet().unwrap().name, "Main");
+}
+
+fn wallet_portfolio(id: &str, unit: &str, ada_value: f64) -> WalletPortfolio {
+    WalletPortfolio {
+        wallet_id: id.to_string(),
+        wallet_name: id.to_uppercase(),
+        summary: summary_with_ft(unit, 1.0, ada_value),
+    }
+}
+
+#[test]
+fn test_positions_wallet_filter_cycles_through_wallets() {
+    let mut state = create_test_state();
+    state.update_portfolio(UserPortfolio::from_wallets(vec![
+        wallet_portfolio("hw", "unit1", 10.0),
+        wallet_portfolio("hot", "unit2", 5.0),
+    ]));
+    assert_eq!(state.positions_wallet_label(), "All wallets");
+    assert_eq!(state.visible_positions_ft().len(), 2);
+    assert_eq!(state.visible_ada_value(), 15.0);
+
+    state.next_ft_row();
+    state.cycle_positions_wallet();
+    assert_eq!(state.positions_wallet_label(), "HW");
+    assert_eq!(state.selected_ft_row, 0);
+    assert_eq!(state.visible_positions_ft()[0].unit, "unit1");
+    assert_eq!(state.visible_ada_value(), 10.0);
+    // Rows wrap within the wallet's own table
+    state.next_ft_row();
+    assert_eq!(state.selected_ft_row, 0);
+
+    state.cycle_positions_wallet();
+    assert_eq!(state.visible_positions_ft()[0].unit, "unit2");
+    state.cycle_positions_wallet();
+    assert_eq!(state.positions_wallet, None);
+}
+
+#[test]
+fn test_positions_wallet_filter_resets_when_wallet_is_gone() {
+    let mut state = create_test_state();
+    state.update_portfolio(UserPortfolio::from_wallets(vec![wallet_portfolio("hw", "unit1", 10.0)]));
+    state.cycle_positions_wallet();
+    assert_eq!(state.positions_wallet.as_deref(), Some("hw"));
+
+    state.update_portfolio(UserPortfolio::from_wallets(vec![wallet_portfolio("hot", "unit2", 5.0)]));
+    assert_eq!(state.positions_wallet, None);
+    assert_eq!(state.visible_positions_ft()[0].unit, "unit2");
+}
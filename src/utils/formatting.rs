@@ -2,13 +2,18 @@ use ratatui::{
     style::{Style, Color},
     text::Span,
 };
+use crate::models::display_currency::DisplayCurrency;
 
 /// Formats a number with comma separators and specified decimal places
 pub fn format_number(value: f64, decimals: usize) -> String {
-    let whole = value.trunc() as i64;
-    let decimal = (value.fract() * 10f64.powi(decimals as i32)).abs().round();
-    
-    let whole_formatted = whole.to_string()
+    // Round before splitting so e.g. 0.999 becomes "1.00" rather than "0.100"
+    let rounded = format!("{:.*}", decimals, value.abs());
+    let (whole, decimal) = match rounded.split_once('.') {
+        Some((whole, decimal)) => (whole, Some(decimal)),
+        None => (rounded.as_str(), None),
+    };
+
+    let whole_formatted = whole
         .chars()
         .rev()
         .collect::<Vec<char>>()
@@ -19,11 +24,11 @@ pub fn format_number(value: f64, decimals: usize) -> String {
         .chars()
         .rev()
         .collect::<String>();
-        
-    if decimals > 0 {
-        format!("{}.{:0width$}", whole_formatted, decimal as i64, width = decimals)
-    } else {
-        whole_formatted
+
+    let sign = if value < 0.0 && rounded.chars().any(|c| c.is_ascii_digit() && c != '0') { "-" } else { "" };
+    match decimal {
+        Some(decimal) => format!("{}{}.{}", sign, whole_formatted, decimal),
+        None => format!("{}{}", sign, whole_formatted),
     }
 }
 
@@ -36,16 +41,38 @@ pub fn format_usd(value: f64, decimals: usize) -> String {
     format!("${}", format_number(value, decimals))
 }
 
+/// Formats a value already converted to `currency` with its symbol. BTC
+/// always gets 8 decimal places and JPY none, whatever `decimals` says.
+pub fn format_currency(value: f64, currency: DisplayCurrency, decimals: usize) -> String {
+    let decimals = match currency {
+        DisplayCurrency::Btc => 8,
+        DisplayCurrency::Jpy => 0,
+        _ => decimals,
+    };
+    let formatted = format_number(value, decimals);
+    match formatted.strip_prefix('-') {
+        Some(magnitude) => format!("-{}{}", currency.symbol(), magnitude),
+        None => format!("{}{}", currency.symbol(), formatted),
+    }
+}
+
 /// Formats a percentage change with colors and +/- symbol
 pub fn format_change(change: f64) -> Span<'static> {
     let formatted = format!("{:+.2}%", change);
-    let color = if change > 0.0 {
+    Span::styled(formatted, Style::default().fg(sign_color(change)))
+}
+
+/// Colors an already formatted profit or loss by the sign of `value`
+pub fn format_pnl(formatted: String, value: f64) -> Span<'static> {
+    Span::styled(formatted, Style::default().fg(sign_color(value)))
+}
+
+fn sign_color(value: f64) -> Color {
+    if value > 0.0 {
         Color::Green
-    } else if change < 0.0 {
+    } else if value < 0.0 {
         Color::Red
     } else {
         Color::White
-    };
-    
-    Span::styled(formatted, Style::default().fg(color))
-} 
\ No newline at end of file
+    }
+}
@@ -1,3 +1,7 @@
 pub mod formatting;
 pub mod spinner;
-pub mod ascii_art;
\ No newline at end of file
+pub mod ascii_art;
+pub mod passphrase;
+
+#[cfg(test)]
+mod tests;
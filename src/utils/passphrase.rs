@@ -0,0 +1,39 @@
+use std::env;
+use std::io::{self, Write};
+use termion::input::TermRead;
+
+/// Environment variable read instead of prompting, for scripts and cron jobs
+pub const PASSPHRASE_ENV: &str = "PORTFOLIO_PASSPHRASE";
+
+/// Returns the passphrase from `PORTFOLIO_PASSPHRASE`, or asks for it on the
+/// terminal without echoing it
+pub fn read_passphrase(prompt: &str) -> io::Result<String> {
+    if let Ok(passphrase) = env::var(PASSPHRASE_ENV) {
+        return Ok(passphrase);
+    }
+    prompt_passphrase(prompt)
+}
+
+/// Always asks on the terminal, e.g. for a new passphrase
+pub fn prompt_passphrase(prompt: &str) -> io::Result<String> {
+    let mut stdout = io::stdout();
+    writeln!(stdout, "{}", prompt)?;
+    stdout.flush()?;
+
+    let passphrase = io::stdin().read_passwd(&mut stdout)?
+        .ok_or_else(|| io::Error::new(io::ErrorKind::UnexpectedEof, "No passphrase entered"))?;
+    writeln!(stdout)?;
+    Ok(passphrase)
+}
+
+/// Asks for a new passphrase twice and checks both entries match
+pub fn prompt_new_passphrase(prompt: &str) -> io::Result<String> {
+    let passphrase = prompt_passphrase(prompt)?;
+    if passphrase.is_empty() {
+        return Err(io::Error::new(io::ErrorKind::InvalidInput, "Passphrase must not be empty"));
+    }
+    if prompt_passphrase("Repeat the passphrase:")? != passphrase {
+        return Err(io::Error::new(io::ErrorKind::InvalidInput, "Passphrases do not match"));
+    }
+    Ok(passphrase)
+}
@@ -6,6 +6,12 @@ pub struct Spinner {
     current: usize,
 }
 
+impl Default for Spinner {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl Spinner {
     pub fn new() -> Self {
         Self {
@@ -14,7 +20,7 @@ impl Spinner {
         }
     }
 
-    pub fn next(&mut self) -> &str {
+    pub fn next_frame(&mut self) -> &str {
         let frame = self.frames[self.current];
         self.current = (self.current + 1) % self.frames.len();
         frame
@@ -29,7 +35,7 @@ impl Spinner {
         let message = message.to_string();
         let spinner_handle = tokio::spawn(async move {
             loop {
-                print!("\r{} {}", spinner.next(), message);
+                print!("\r{} {}", spinner.next_frame(), message);
                 std::io::Write::flush(&mut std::io::stdout()).unwrap();
                 sleep(Duration::from_millis(100)).await;
             }
@@ -0,0 +1,46 @@
+use crate::models::display_currency::DisplayCurrency;
+use crate::utils::formatting::{format_currency, format_number};
+
+#[test]
+fn test_format_number_groups_thousands() {
+    assert_eq!(format_number(0.0, 2), "0.00");
+    assert_eq!(format_number(999.0, 0), "999");
+    assert_eq!(format_number(1234.5, 2), "1,234.50");
+    assert_eq!(format_number(1234567.891, 2), "1,234,567.89");
+}
+
+#[test]
+fn test_format_number_rounds_before_grouping() {
+    assert_eq!(format_number(0.999, 2), "1.00");
+    assert_eq!(format_number(999.999, 2), "1,000.00");
+    assert_eq!(format_number(999_999.5, 0), "1,000,000");
+    assert_eq!(format_number(1.005, 1), "1.0");
+}
+
+#[test]
+fn test_format_number_negative_values() {
+    assert_eq!(format_number(-1234.5, 2), "-1,234.50");
+    assert_eq!(format_number(-0.999, 2), "-1.00");
+    assert_eq!(format_number(-0.5, 1), "-0.5");
+}
+
+#[test]
+fn test_format_number_never_shows_negative_zero() {
+    assert_eq!(format_number(-0.0, 2), "0.00");
+    assert_eq!(format_number(-0.004, 2), "0.00");
+    assert_eq!(format_number(-0.4, 0), "0");
+}
+
+#[test]
+fn test_format_currency_puts_sign_before_symbol() {
+    assert_eq!(format_currency(-25.0, DisplayCurrency::Usd, 2), "-$25.00");
+    assert_eq!(format_currency(-50.0, DisplayCurrency::Ada, 2), "-₳50.00");
+    assert_eq!(format_currency(1234.5, DisplayCurrency::Ada, 2), "₳1,234.50");
+    assert_eq!(format_currency(-0.001, DisplayCurrency::Usd, 2), "$0.00");
+}
+
+#[test]
+fn test_format_currency_fixed_decimals() {
+    assert_eq!(format_currency(0.123456789, DisplayCurrency::Btc, 2), "₿0.12345679");
+    assert_eq!(format_currency(-1234.6, DisplayCurrency::Jpy, 2), "-¥1,235");
+}